@@ -0,0 +1,111 @@
+use crate::simulation_domain_2d::SimulationDomain2D;
+use crate::simulation_domain_3d::SimulationDomain3D;
+use crate::tessellations::{DelaunayTriangulation2D, DelaunayTetrahedralization3D, VoronoiGrid3D, InsertionOrder, CheckLevel};
+use crate::error::MiniSwiftError;
+use crate::random_points;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::time::Instant;
+
+
+/// Wall clock benchmarks of the tessellation construction. Run them in release mode, in debug
+/// mode the consistency checks dominate the timings:
+///
+/// `cargo run --release -- bench`
+pub fn run_benchmarks() -> Result<(), MiniSwiftError> {
+    bench_insertion_order(&[10_000, 100_000, 1_000_000])?;
+    bench_moving_generators(&[1_000, 10_000, 100_000], 10)?;
+    bench_3d(&[10_000, 100_000, 1_000_000])
+}
+
+const MAX_N_INPUT_ORDER: usize = 100_000;
+
+/// Compares the construction time of the triangulation of uniformly distributed random points
+/// for the different insertion orders.
+fn bench_insertion_order(n_values: &[usize]) -> Result<(), MiniSwiftError> {
+    let domain = SimulationDomain2D::new([0., 0.], [1., 1.]);
+    let orders = [InsertionOrder::Input, InsertionOrder::Hilbert, InsertionOrder::Brio { seed: 42 }];
+    println!("# Insertion order");
+    println!("# n\tinput (s)\thilbert (s)\tbrio (s)");
+    for &n in n_values {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (x_values, y_values) = random_points(n as i32, &domain, true, &mut rng);
+        let mut timings = Vec::with_capacity(orders.len());
+        for &order in orders.iter() {
+            // Inserting in input order scales quadratically, skip it for the largest sizes.
+            if order == InsertionOrder::Input && n > MAX_N_INPUT_ORDER {
+                timings.push("-".to_string());
+                continue;
+            }
+            let start = Instant::now();
+            DelaunayTriangulation2D::from_points_with_order(&x_values, &y_values, domain, false, order)?;
+            timings.push(format!("{:.4}", start.elapsed().as_secs_f64()));
+        }
+        println!("{}\t{}", n, timings.join("\t"));
+    }
+    Ok(())
+}
+
+/// Compares moving the generators of an existing triangulation to rebuilding the triangulation
+/// from scratch, for displacements of 10% of the mean interparticle distance per step.
+fn bench_moving_generators(n_values: &[usize], n_steps: usize) -> Result<(), MiniSwiftError> {
+    let domain = SimulationDomain2D::new([0., 0.], [1., 1.]);
+    println!("# Moving generators: {} steps", n_steps);
+    println!("# n\trebuild (s)\tupdate_positions (s)\tspeedup");
+    for &n in n_values {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (initial_x, initial_y) = random_points(n as i32, &domain, true, &mut rng);
+        let (mut x_values, mut y_values) = (initial_x.clone(), initial_y.clone());
+        let displacement = 0.1 / f64::sqrt(n as f64);
+        let mut steps = Vec::with_capacity(n_steps);
+        for _ in 0..n_steps {
+            for (x, y) in x_values.iter_mut().zip(y_values.iter_mut()) {
+                *x = (*x + displacement * (rng.gen::<f64>() - 0.5)).rem_euclid(1.);
+                *y = (*y + displacement * (rng.gen::<f64>() - 0.5)).rem_euclid(1.);
+            }
+            steps.push((x_values.clone(), y_values.clone()));
+        }
+
+        let start = Instant::now();
+        for (x_values, y_values) in steps.iter() {
+            DelaunayTriangulation2D::from_points(x_values, y_values, domain, false)?;
+        }
+        let rebuild_time = start.elapsed().as_secs_f64();
+
+        let mut d = DelaunayTriangulation2D::from_points(&initial_x, &initial_y, domain, false)?;
+        d.set_check_level(CheckLevel::None);
+        let start = Instant::now();
+        for (x_values, y_values) in steps.iter() {
+            d.update_positions(x_values, y_values)?;
+        }
+        let update_time = start.elapsed().as_secs_f64();
+
+        println!("{}\t{:.4}\t{:.4}\t{:.2}", n, rebuild_time, update_time, rebuild_time / update_time);
+    }
+    Ok(())
+}
+
+/// Construction time of the 3D Delaunay tetrahedralization and voronoi grid of uniformly
+/// distributed random points in a periodic box.
+fn bench_3d(n_values: &[usize]) -> Result<(), MiniSwiftError> {
+    let domain = SimulationDomain3D::new([0., 0., 0.], [1., 1., 1.]);
+    println!("# 3D construction");
+    println!("# n\ttetrahedralization (s)\tvoronoi grid (s)");
+    for &n in n_values {
+        let mut rng = StdRng::seed_from_u64(42);
+        let x_values: Vec<f64> = (0..n).map(|_| rng.gen()).collect();
+        let y_values: Vec<f64> = (0..n).map(|_| rng.gen()).collect();
+        let z_values: Vec<f64> = (0..n).map(|_| rng.gen()).collect();
+
+        let start = Instant::now();
+        let d = DelaunayTetrahedralization3D::from_points(&x_values, &y_values, &z_values, domain, true)?;
+        let tetrahedralization_time = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        VoronoiGrid3D::from_delaunay_tetrahedralization(&d)?;
+        let voronoi_time = start.elapsed().as_secs_f64();
+
+        println!("{}\t{:.4}\t{:.4}", n, tetrahedralization_time, voronoi_time);
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// Errors reported by the tessellations and the cell interactions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MiniSwiftError {
+    /// The Delaunay triangulation was already finalized.
+    AlreadyFinalized,
+    /// Ghost vertices can only be added to a finalized Delaunay triangulation.
+    NotFinalized,
+    /// The Delaunay triangulation was already made periodic.
+    AlreadyPeriodic,
+    /// The operation is only allowed on cells without progeny.
+    NotALeaf,
+    /// The Delaunay triangulation of the cell was not initialized.
+    MissingTessellation,
+    /// The point does not lie within the region covered by the tessellation.
+    PointOutsideDomain { position: Vec<f64> },
+    /// The point location walk did not terminate or ended up in a degenerate simplex.
+    LocationWalkFailure { position: Vec<f64> },
+    /// A vertex cannot be moved to the position of another vertex.
+    DuplicateVertex { position: Vec<f64> },
+    /// A cell contains two particles at exactly the same position.
+    DuplicateParticle { position: Vec<f64> },
+    /// The voronoi cell of the generator with the given index is degenerate.
+    DegenerateCell(usize),
+    /// The voronoi cell of the particle at the given position reaches the boundary of the
+    /// tessellation of its leaf, i.e. the ghosts do not cover the search radius of the particle.
+    OpenCell { position: Vec<f64> },
+    /// There is no (removable) vertex with the given index.
+    InvalidVertex(usize),
+    /// There is no actual (non-ghost) cell with the given index.
+    InvalidCell(usize),
+    /// The operation is not supported for triangulations containing ghost vertices.
+    GhostVerticesPresent,
+    /// The operation is not supported for weighted (regular) triangulations.
+    WeightedVerticesPresent,
+    /// The boundary conditions are not valid for the requested operation, e.g. only one side of an
+    /// axis is periodic.
+    InvalidBoundaryConditions,
+    /// The voronoi grids do not cover the same region, e.g. because their domains or periodic axes
+    /// differ, or because one of them contains ghosts from neighbouring cells.
+    IncompatibleGrids,
+    /// The number of values does not match the number of vertices or cells they belong to.
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for MiniSwiftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MiniSwiftError::AlreadyFinalized => write!(f, "Delaunay triangulation was already finalized!"),
+            MiniSwiftError::NotFinalized => write!(f, "Trying to add ghost vertex to non-finalized Delaunay triangulation!"),
+            MiniSwiftError::AlreadyPeriodic => write!(f, "Delaunay triangulation is already periodic!"),
+            MiniSwiftError::NotALeaf => write!(f, "Cell is not a leaf!"),
+            MiniSwiftError::MissingTessellation => write!(f, "Delaunay tessellation not yet initialized!"),
+            MiniSwiftError::PointOutsideDomain { position } => write!(f, "Point {:?} lies outside the domain!", position),
+            MiniSwiftError::LocationWalkFailure { position } => write!(f, "Failed to locate point {:?} in the tessellation!", position),
+            MiniSwiftError::DuplicateVertex { position } => write!(f, "There already is a vertex at {:?}!", position),
+            MiniSwiftError::DuplicateParticle { position } => write!(f, "Cell contains duplicate particles at {:?}!", position),
+            MiniSwiftError::DegenerateCell(idx) => write!(f, "Voronoi cell {} is degenerate!", idx),
+            MiniSwiftError::OpenCell { position } => write!(f, "Voronoi cell of particle at {:?} is not closed by ghosts!", position),
+            MiniSwiftError::InvalidVertex(idx) => write!(f, "Invalid vertex index: {}!", idx),
+            MiniSwiftError::InvalidCell(idx) => write!(f, "Invalid cell index: {}!", idx),
+            MiniSwiftError::GhostVerticesPresent => write!(f, "Delaunay triangulation contains ghost vertices!"),
+            MiniSwiftError::WeightedVerticesPresent => write!(f, "Delaunay triangulation contains weighted vertices!"),
+            MiniSwiftError::InvalidBoundaryConditions => write!(f, "Invalid boundary conditions!"),
+            MiniSwiftError::IncompatibleGrids => write!(f, "Voronoi grids do not cover the same region!"),
+            MiniSwiftError::LengthMismatch { expected, found } => write!(f, "Expected {} values, got {}!", expected, found),
+        }
+    }
+}
+
+impl std::error::Error for MiniSwiftError {}
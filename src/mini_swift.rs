@@ -1,8 +1,11 @@
 pub use cell::Cell;
-pub use do_iact::do_iact_test;
+pub use do_iact::{do_advection_convergence_test, do_iact_test, do_iact_test_1d, do_iact_test_3d, do_iact_test_reflective};
+pub use riemann::{ExactRiemannSolver, HllcRiemannSolver, RiemannSolver, RiemannState, TwoRarefactionRiemannSolver};
 
 mod cell;
 mod particle;
 pub mod direction;
 mod do_iact;
+mod riemann;
+mod slope_limiter;
 
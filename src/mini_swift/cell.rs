@@ -1,87 +1,153 @@
 use crate::mini_swift::particle::Particle;
-use crate::simulation_domain_2d::SimulationDomain2D;
-use crate::tessellations::{DelaunayTriangulation2D, VoronoiGrid2D};
+use crate::tessellations::{Dimension, Domain, Tessellation};
+use crate::error::MiniSwiftError;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 mod hydro_iact_density;
+mod hydro_iact_gradient;
 mod hydro_iact_force;
 mod hydro_ghost;
 
-#[derive(Default)]
-pub struct Cell {
-    domain: SimulationDomain2D,
-    particles: Option<Vec<Particle>>,
-    pub progeny: Option<[Box<Cell>; 4]>,
-    pub del_tess: Option<DelaunayTriangulation2D>,
-    pub vor_tess: Option<VoronoiGrid2D>,
+use hydro_iact_density::GhostKey;
+
+/// The id of the next cell to be created
+static NEXT_CELL_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Cell<D: Dimension> {
+    /// Unique among all cells, used to trace ghosts back to the cell of their original particle
+    id: usize,
+    domain: D::Domain,
+    particles: Option<Vec<Particle<D>>>,
+    /// The 2^DIM subcells of this cell (if it is split)
+    pub progeny: Option<Vec<Cell<D>>>,
+    pub del_tess: Option<D::Delaunay>,
+    pub vor_tess: Option<D::Voronoi>,
+    /// The mirrored copies of particles inserted in `del_tess` at reflective or open boundaries,
+    /// indexed by the index of their origin in `ghost_origins` of `del_tess` (`None` for the
+    /// other ghosts).
+    pub boundary_ghosts: Vec<Option<Particle<D>>>,
+    /// The origins of the ghosts inserted in `del_tess` so far, see `GhostKey`
+    inserted_ghosts: HashSet<GhostKey>,
     max_h: f64
 }
 
-impl Cell {
-    pub fn from_dimensions(anchor: [f64; 2], sides: [f64; 2]) -> Self {
+impl<D: Dimension> Cell<D> {
+    pub fn from_dimensions(anchor: D::Position, sides: D::Position) -> Self {
         Cell {
-            domain: SimulationDomain2D::new(anchor, sides),
+            id: NEXT_CELL_ID.fetch_add(1, Ordering::Relaxed),
+            domain: D::Domain::new(anchor, sides),
             particles: Some(vec![]),
-            max_h: 0.,
-            ..Cell::default() }
+            progeny: None,
+            del_tess: None,
+            vor_tess: None,
+            boundary_ghosts: Vec::new(),
+            inserted_ghosts: HashSet::new(),
+            max_h: 0. }
     }
 
-    pub fn anchor(&self) -> [f64; 2] {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn anchor(&self) -> D::Position {
         self.domain.anchor()
     }
 
-    pub fn sides(&self) -> [f64; 2] {
+    pub fn sides(&self) -> D::Position {
         self.domain.sides()
     }
 
-    pub fn domain(&self) -> SimulationDomain2D {
+    pub fn domain(&self) -> D::Domain {
         self.domain
     }
 
-    pub fn add_particles(&mut self, x_values: &[f64], y_values: &[f64], h: f64) {
-        for (&x, &y) in x_values.iter().zip(y_values.iter()) {
-            self.add_particle(x, y, h);
+    pub fn add_particles(&mut self, positions: &[D::Position], h: f64) -> Result<(), MiniSwiftError> {
+        for &position in positions {
+            self.add_particle(position, h)?;
         }
+        Ok(())
+    }
+
+    fn add_particle(&mut self, position: D::Position, h: f64) -> Result<(), MiniSwiftError> {
+        self.push_particle(Particle::new(position, h))
     }
 
-    fn add_particle(&mut self, x: f64, y: f64, h: f64) {
+    fn push_particle(&mut self, particle: Particle<D>) -> Result<(), MiniSwiftError> {
         match self.particles.as_mut() {
             Some(particles) => {
-                if h > self.max_h { self.max_h = h; }
-                particles.push(Particle::new(x, y, h));
+                if particle.h > self.max_h { self.max_h = particle.h; }
+                particles.push(particle);
+                Ok(())
             },
-            None => panic!("Trying to add a particle to cell which is not a leaf!")
+            None => Err(MiniSwiftError::NotALeaf)
         }
     }
 
-    pub fn split(&mut self) {
-        assert!(self.progeny.is_none() && self.particles.is_some(),
-                "Trying to split a cell which is not a leaf!");
+    /// The particles of this cell, `None` if it is split
+    pub fn particles(&self) -> Option<&[Particle<D>]> {
+        self.particles.as_deref()
+    }
 
-        // create progeny
+    pub fn particles_mut(&mut self) -> Option<&mut [Particle<D>]> {
+        self.particles.as_deref_mut()
+    }
+
+    /// Calls `f` on all particles in the leaves of this cell
+    pub fn for_each_particle(&self, f: &mut dyn FnMut(&Particle<D>)) {
+        match self.progeny.as_ref() {
+            Some(progeny) => progeny.iter().for_each(|cell| cell.for_each_particle(f)),
+            None => self.particles.as_ref().unwrap().iter().for_each(f),
+        }
+    }
+
+    /// Calls `f` on all particles in the leaves of this cell, e.g. to set up initial conditions
+    pub fn for_each_particle_mut(&mut self, f: &mut dyn FnMut(&mut Particle<D>)) {
+        match self.progeny.as_mut() {
+            Some(progeny) => progeny.iter_mut().for_each(|cell| cell.for_each_particle_mut(f)),
+            None => self.particles.as_mut().unwrap().iter_mut().for_each(f),
+        }
+    }
+
+    pub fn split(&mut self) -> Result<(), MiniSwiftError> {
+        if self.progeny.is_some() || self.particles.is_none() {
+            return Err(MiniSwiftError::NotALeaf);
+        }
+
+        // create progeny, the i-th bit of the index of a subcell indicates whether it lies in the
+        // upper half of this cell along the i-th axis
         let anchor = self.anchor();
-        let half_sides = [self.sides()[0] / 2., self.sides()[1] / 2.];
-        let mut progeny = [
-            Box::new(Cell::from_dimensions(anchor, half_sides)),
-            Box::new(Cell::from_dimensions([anchor[0] + half_sides[0], anchor[1]], half_sides)),
-            Box::new(Cell::from_dimensions([anchor[0], anchor[1] + half_sides[1]], half_sides)),
-            Box::new(Cell::from_dimensions([anchor[0] + half_sides[0], anchor[1] + half_sides[1]], half_sides))
-        ];
+        let mut half_sides = self.sides();
+        for side in half_sides.as_mut() {
+            *side /= 2.;
+        }
+        let mut progeny: Vec<Cell<D>> = (0..1 << D::DIM).map(|pid| {
+            let mut sub_anchor = anchor;
+            for (i, a) in sub_anchor.as_mut().iter_mut().enumerate() {
+                if pid >> i & 1 == 1 { *a += half_sides.as_ref()[i]; }
+            }
+            Cell::from_dimensions(sub_anchor, half_sides)
+        }).collect();
         // divide particles over progeny
-        for particle in self.particles.as_ref().unwrap() {
-            let pid = (particle.x() >= anchor[0] + half_sides[0]) as u8
-                + 2 * ((particle.y() >= anchor[1] + half_sides[1]) as u8);
-            progeny[pid as usize].add_particle(particle.x(), particle.y(), particle.h);
+        for particle in self.particles.take().unwrap() {
+            let position = particle.position();
+            let pid: usize = (0..D::DIM)
+                .map(|i| ((position.as_ref()[i] >= anchor.as_ref()[i] + half_sides.as_ref()[i]) as usize) << i)
+                .sum();
+            progeny[pid].push_particle(particle)?;
         }
 
         self.progeny = Some(progeny);
-        self.particles = None;
+        Ok(())
     }
 
     pub fn delaunay_init(&mut self) {
         match self.particles.as_ref() {
             Some(particles) => {
                 let n_parts = particles.len();
-                self.del_tess = Some(DelaunayTriangulation2D::new(self.domain, n_parts, 2 * n_parts));
+                self.del_tess = Some(D::Delaunay::new(self.domain, n_parts, 2 * n_parts));
+                self.boundary_ghosts.clear();
+                self.inserted_ghosts.clear();
             }
             None => {
                 for cell in self.progeny.as_mut().unwrap() {
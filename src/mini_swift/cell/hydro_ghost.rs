@@ -1,17 +1,18 @@
 use crate::mini_swift::Cell;
-use crate::tessellations::VoronoiGrid2D;
-use std::panic::panic_any;
+use crate::mini_swift::slope_limiter::SlopeLimiter;
+use crate::tessellations::{Dimension, Tessellation, VoronoiGrid};
+use crate::error::MiniSwiftError;
 
-impl Cell {
-    pub fn update_search_radii(&mut self) -> u32 {
+impl<D: Dimension> Cell<D> {
+    pub fn update_search_radii(&mut self) -> Result<u32, MiniSwiftError> {
         let mut n_updated = 0;
         match self.particles.as_mut(){
             Some(particles) => {
-                let del_tess = self.del_tess.as_mut().unwrap();
-                del_tess.update_vertex_search_radii(0., del_tess.n_vertices);
+                let del_tess = self.del_tess.as_mut().ok_or(MiniSwiftError::MissingTessellation)?;
+                let n_vertices = del_tess.n_vertices();
+                del_tess.update_vertex_search_radii(0., n_vertices);
                 for (i, particle) in particles.iter_mut().enumerate() {
-                    let search_radius = del_tess.vertices[i + 3].search_radius;
-                    if del_tess.vertices[i + 3].search_radius > particle.h * 0.95 {
+                    if del_tess.search_radius(i) > particle.h * 0.95 {
                         particle.h *= 1.1;
                         n_updated += 1;
                         if particle.h > self.max_h {
@@ -22,28 +23,41 @@ impl Cell {
             }
             None => {
                 for cell in self.progeny.as_mut().unwrap() {
-                    n_updated += cell.update_search_radii();
+                    n_updated += cell.update_search_radii()?;
+                    if cell.max_h > self.max_h {
+                        self.max_h = cell.max_h;
+                    }
                 }
             }
         }
-        n_updated
+        Ok(n_updated)
     }
 
-    pub fn end_density(&mut self) {
+    /// Constructs the voronoi grids of the leaves of this cell and copies the volume and centroid
+    /// of their cells to the particles, updating the primitive quantities of the particles.
+    pub fn end_density(&mut self) -> Result<(), MiniSwiftError> {
         match self.progeny.as_mut() {
             Some(progeny) => {
                 for cell in progeny {
-                    cell.end_density();
+                    cell.end_density()?;
                 }
             }
             None => {
-                match self.del_tess.as_ref(){
-                    Some(del_tess) => {
-                        self.vor_tess = Some(VoronoiGrid2D::from_delaunay_triangulation(del_tess));
-                    }
-                    None => panic!("Trying to construct voronoi grid for cell without delaunay triangulation!")
+                let del_tess = self.del_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?;
+                let vor_tess = D::Voronoi::from_tessellation(del_tess)?;
+                for (i, particle) in self.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?.iter_mut().enumerate() {
+                    particle.set_geometry(vor_tess.cell_volume(i), vor_tess.cell_centroid(i));
                 }
+                self.vor_tess = Some(vor_tess);
             }
         }
+        Ok(())
+    }
+
+    /// Computes the gradients of the primitive quantities of the particles of this cell from the
+    /// contributions accumulated by the gradient loop (see `iact_gradient_self`), limited by
+    /// `limiter` if it is a cell-wise limiter.
+    pub fn end_gradient(&mut self, limiter: SlopeLimiter) {
+        self.for_each_particle_mut(&mut |particle| particle.end_gradient(limiter));
     }
-}
\ No newline at end of file
+}
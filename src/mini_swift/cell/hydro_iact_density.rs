@@ -1,130 +1,255 @@
 use crate::mini_swift::Cell;
-use crate::mini_swift::direction::{Direction, get_direction, direction_as_vector, invert_direction, direction_to_sort_list_id};
-use rand_distr::num_traits::abs;
+use crate::mini_swift::particle::Particle;
+use crate::tessellations::{Dimension, Tessellation, VertexInsertion, GhostOrigin};
+use crate::simulation_domain_2d::BoundaryCondition;
+use crate::error::MiniSwiftError;
+use crate::mini_swift::direction::{Direction, get_direction, direction_as_vector};
 
-impl Cell{
-    fn iact_density_self_base(&mut self) {
+/// Identifies a ghost by its origin: the cell id and index of the original particle, the bit
+/// patterns of the components of the shift and the mirrored axes (see `GhostOrigin`).
+pub(super) type GhostKey = (Option<usize>, usize, [u64; 3], [bool; 3]);
+
+fn ghost_key<D: Dimension>(origin: &GhostOrigin<D>) -> GhostKey {
+    let mut shift = [0; 3];
+    for (bits, s) in shift.iter_mut().zip(origin.shift.as_ref()) {
+        // adding 0 turns -0 into 0
+        *bits = (s + 0.).to_bits();
+    }
+    (origin.cell_id, origin.particle_idx, shift, origin.mirrored)
+}
+
+/// Returns `position + sign * shift`
+fn shift_position<D: Dimension>(position: D::Position, shift: D::Position, sign: f64) -> D::Position {
+    let mut shifted = position;
+    for (p, s) in shifted.as_mut().iter_mut().zip(shift.as_ref()) {
+        *p += sign * s;
+    }
+    shifted
+}
+
+impl<D: Dimension> Cell<D> {
+    fn iact_density_self_base(&mut self) -> Result<(), MiniSwiftError> {
         // add this cells particles to this cells Delaunay tesselation
-        assert!(self.particles.is_some(), "Cells particles is None!");
-        assert!(self.del_tess.is_some(), "Delaunay tesselation not yet initialized!");
-        let del_tess = self.del_tess.as_mut().unwrap();
-        for particle in self.particles.as_ref().unwrap() {
-            del_tess.insert_vertex(particle.x(), particle.y());
+        let particles = self.particles.as_ref().ok_or(MiniSwiftError::NotALeaf)?;
+        let del_tess = self.del_tess.as_mut().ok_or(MiniSwiftError::MissingTessellation)?;
+        for particle in particles {
+            if let VertexInsertion::Duplicate(_) = del_tess.insert_vertex(particle.position())? {
+                return Err(MiniSwiftError::DuplicateParticle { position: particle.position().as_ref().to_vec() });
+            }
         }
-        del_tess.finalize();
+        del_tess.finalize()
     }
 
-    fn iact_density_self_sub_pairs(&mut self) {
+    fn iact_density_self_sub_pairs(&mut self) -> Result<(), MiniSwiftError> {
         let progeny = self.progeny.as_mut().unwrap();
-        for i in 0..4 {
-            for j in i+1..4 {
-                let (ci, cj): (&mut Box<Cell>, &mut Box<Cell>);
-                unsafe{
-                    // j > i, so this is in fact safe
-                    ci = &mut *(progeny.get_unchecked_mut(i) as *mut _);
-                    cj = &mut *(progeny.get_unchecked_mut(j) as *mut _);
-                }
-                ci.iact_density_pair(cj, get_direction(ci.anchor(), cj.anchor()));
+        for i in 0..progeny.len() {
+            for j in i+1..progeny.len() {
+                // j > i, so both subcells can be borrowed mutably at the same time
+                let (head, tail) = progeny.split_at_mut(j);
+                let (ci, cj) = (&mut head[i], &mut tail[0]);
+                let direction = get_direction(ci.anchor().as_ref(), cj.anchor().as_ref());
+                ci.iact_density_pair(cj, direction)?;
             }
         }
+        Ok(())
     }
 
-    pub fn iact_density_self(&mut self) {
+    pub fn iact_density_self(&mut self) -> Result<(), MiniSwiftError> {
         match self.progeny.as_mut() {
             Some(progeny) => {
                 // Do self interactions for subcells
                 for cell in progeny.iter_mut() {
-                    cell.iact_density_self()
+                    cell.iact_density_self()?;
                 }
                 // Do pair interactions between cells
-                self.iact_density_self_sub_pairs();
+                self.iact_density_self_sub_pairs()
             }
             None => self.iact_density_self_base()
         }
     }
 
-    pub fn iact_density_self_ghost(&mut self) {
-        match self.progeny.as_mut() {
-            Some(progeny) => {
-                // Do only pair interactions between cells
-                self.iact_density_self_sub_pairs();
-            }
-            None => ()
+    pub fn iact_density_self_ghost(&mut self) -> Result<(), MiniSwiftError> {
+        if self.progeny.is_some() {
+            // Do only pair interactions between cells
+            self.iact_density_self_sub_pairs()?;
         }
+        Ok(())
     }
 
-    fn iact_density_pair_base(&mut self, other: &mut Cell, direction: Direction, shift: [f64; 2]) {
-        let del_tess = self.del_tess.as_mut().unwrap();
-        let other_del_tess = other.del_tess.as_mut().unwrap();
-        let sid = direction_to_sort_list_id(direction);
-        let inv_direction = invert_direction(direction);
-        let inv_sid = direction_to_sort_list_id(inv_direction);
-
-        for particle in self.particles.as_mut().unwrap() {
-            for other_particle in other.particles.as_mut().unwrap() {
-                if other_particle.x() == 1.0502736853580725 {
-                    let a = 0;
-                }
-                let x = particle.x();
-                let y = particle.y();
-                let other_x = (other_particle.x() + shift[0]);
-                let other_y = (other_particle.y() + shift[1]);
-                let delta_x = x - other_x;
-                let delta_y = y - other_y;
-                let dist_2 = delta_x * delta_x + delta_y * delta_y;
+    fn iact_density_pair_base(&mut self, other: &mut Cell<D>, shift: D::Position) -> Result<(), MiniSwiftError> {
+        let del_tess = self.del_tess.as_mut().ok_or(MiniSwiftError::MissingTessellation)?;
+        let other_del_tess = other.del_tess.as_mut().ok_or(MiniSwiftError::MissingTessellation)?;
+        let inv_shift = shift_position::<D>(D::Position::default(), shift, -1.);
+
+        // A particle can be added to several leaves in the same direction (e.g. to all progeny of
+        // a split neighbour) and is considered again in later iterations of the density loop, so
+        // we skip the ghosts already inserted.
+        for (i, particle) in self.particles.as_ref().ok_or(MiniSwiftError::NotALeaf)?.iter().enumerate() {
+            for (j, other_particle) in other.particles.as_ref().ok_or(MiniSwiftError::NotALeaf)?.iter().enumerate() {
+                let position = particle.position();
+                let other_position = shift_position::<D>(other_particle.position(), shift, 1.);
+                let dist_2: f64 = position.as_ref().iter().zip(other_position.as_ref())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum();
                 // first direction
                 // TODO symmetrize?
                 if dist_2 < particle.h * particle.h {
-                    if other_particle.added_to_del_tess & 1 << inv_sid == 0 {
-                        del_tess.insert_ghost_vertex(other_x, other_y, direction);
-                        other_particle.added_to_del_tess |= 1 << inv_sid;
+                    let origin = GhostOrigin::shifted(Some(other.id), j, shift);
+                    if self.inserted_ghosts.insert(ghost_key(&origin)) {
+                        del_tess.insert_ghost_vertex(other_position, origin)?;
                     }
                 }
                 // the other direction
                 if dist_2 < other_particle.h * other_particle.h {
-                    if particle.added_to_del_tess & 1 << sid == 0 {
-                        other_del_tess.insert_ghost_vertex(x - shift[0], y - shift[1], invert_direction(direction));
-                        particle.added_to_del_tess |= 1 << sid;
+                    let origin = GhostOrigin::shifted(Some(self.id), i, inv_shift);
+                    if other.inserted_ghosts.insert(ghost_key(&origin)) {
+                        other_del_tess.insert_ghost_vertex(shift_position::<D>(position, shift, -1.), origin)?;
                     }
                 }
             }
         }
+        Ok(())
     }
 
-    pub fn iact_density_pair_shift(&mut self, other: &mut Cell, direction: Direction, shift: [f64; 2]) {
-        if !self.can_interact(other, direction, shift) { return; }
+    pub fn iact_density_pair_shift(&mut self, other: &mut Cell<D>, direction: Direction, shift: D::Position) -> Result<(), MiniSwiftError> {
+        if !self.can_interact(other, direction, shift) { return Ok(()); }
 
         match self.progeny.as_mut() {
             Some(progeny) => {
                 for child in progeny.iter_mut() {
-                    child.iact_density_pair_shift(other, direction, shift);
+                    child.iact_density_pair_shift(other, direction, shift)?;
                 }
+                Ok(())
             }
             None => {
                 match other.progeny.as_mut() {
                     Some(ohter_progeny) => {
                         for other_child in ohter_progeny.iter_mut() {
-                            self.iact_density_pair_shift(other_child, direction, shift);
+                            self.iact_density_pair_shift(other_child, direction, shift)?;
+                        }
+                        Ok(())
+                    }
+                    None => self.iact_density_pair_base(other, shift)
+                }
+            }
+        }
+    }
+
+    pub fn iact_density_pair(&mut self, other: &mut Cell<D>, direction: Direction) -> Result<(), MiniSwiftError> {
+        self.iact_density_pair_shift(other, direction, D::Position::default())
+    }
+
+    /// Inserts mirrored copies of the particles near the side(s) of this cell in the given
+    /// direction as ghost vertices, for cells at a reflective or open boundary of the simulation
+    /// domain. `boundary_conditions` contains the boundary condition of those sides per axis, for
+    /// the axes along which `direction` is non-zero. The mirrored copies of the particles are
+    /// stored in `boundary_ghosts`, with the velocity and momentum components normal to reflective
+    /// sides reversed. Periodic sides are handled by `iact_density_pair_shift` instead.
+    pub fn iact_density_boundary(&mut self, direction: Direction, boundary_conditions: &[BoundaryCondition]) -> Result<(), MiniSwiftError> {
+        let components = direction.components();
+        let (anchor, sides) = (self.anchor(), self.sides());
+        let mut walls = D::Position::default();
+        for (i, wall) in walls.as_mut().iter_mut().enumerate() {
+            if components[i] == 0 { continue; }
+            if boundary_conditions[i] == BoundaryCondition::Periodic {
+                return Err(MiniSwiftError::InvalidBoundaryConditions);
+            }
+            *wall = anchor.as_ref()[i] + if components[i] > 0 { sides.as_ref()[i] } else { 0. };
+        }
+
+        let mut ghosts = Vec::new();
+        self.collect_mirrored_particles(direction, walls, boundary_conditions, self.max_h, &mut ghosts)?;
+        self.insert_boundary_ghosts(&ghosts)
+    }
+
+    /// Collects the mirror images of the particles less than `max_h` away from the given walls,
+    /// together with the origin of each of them.
+    fn collect_mirrored_particles(&self, direction: Direction, walls: D::Position, boundary_conditions: &[BoundaryCondition],
+                                  max_h: f64, ghosts: &mut Vec<(Particle<D>, GhostOrigin<D>)>) -> Result<(), MiniSwiftError> {
+        match self.progeny.as_ref() {
+            Some(progeny) => {
+                for cell in progeny {
+                    cell.collect_mirrored_particles(direction, walls, boundary_conditions, max_h, ghosts)?;
+                }
+            }
+            None => {
+                let components = direction.components();
+                // the mirror image of x in a wall is 2 * wall - x
+                let mut origin: GhostOrigin<D> = GhostOrigin::shifted(Some(self.id), 0, D::Position::default());
+                for (i, wall) in walls.as_ref().iter().enumerate() {
+                    if components[i] == 0 { continue; }
+                    origin.shift.as_mut()[i] = 2. * wall;
+                    origin.mirrored[i] = true;
+                }
+                for (idx, particle) in self.particles.as_ref().ok_or(MiniSwiftError::NotALeaf)?.iter().enumerate() {
+                    let position = particle.position();
+                    let mut reflected = [false; 3];
+                    let mut dist_2 = 0.;
+                    for i in 0..D::DIM {
+                        if components[i] == 0 { continue; }
+                        let (x, wall) = (position.as_ref()[i], walls.as_ref()[i]);
+                        // a particle on the wall would coincide with its mirror image
+                        if (x - wall) * components[i] as f64 >= 0. {
+                            return Err(MiniSwiftError::PointOutsideDomain { position: position.as_ref().to_vec() });
                         }
+                        dist_2 += (x - wall) * (x - wall);
+                        reflected[i] = boundary_conditions[i] == BoundaryCondition::Reflective;
+                    }
+                    if dist_2 < max_h * max_h {
+                        let origin = GhostOrigin { particle_idx: idx, ..origin };
+                        ghosts.push((particle.ghost(&origin, &reflected), origin));
                     }
-                    None => self.iact_density_pair_base(other, direction, shift)
                 }
             }
         }
+        Ok(())
     }
 
-    pub fn iact_density_pair(&mut self, other: &mut Cell, direction: Direction) {
-        self.iact_density_pair_shift(other, direction, [0., 0.]);
+    /// Inserts the given ghosts in the Delaunay tessellations of the leaves of this cell
+    /// containing particles for which they are within the search radius.
+    fn insert_boundary_ghosts(&mut self, ghosts: &[(Particle<D>, GhostOrigin<D>)]) -> Result<(), MiniSwiftError> {
+        match self.progeny.as_mut() {
+            Some(progeny) => {
+                for cell in progeny {
+                    cell.insert_boundary_ghosts(ghosts)?;
+                }
+            }
+            None => {
+                let particles = self.particles.as_ref().ok_or(MiniSwiftError::NotALeaf)?;
+                let del_tess = self.del_tess.as_mut().ok_or(MiniSwiftError::MissingTessellation)?;
+                for (ghost, origin) in ghosts {
+                    let ghost_position = ghost.position();
+                    let in_search_radius = particles.iter().any(|particle| {
+                        let dist_2: f64 = particle.position().as_ref().iter().zip(ghost_position.as_ref())
+                            .map(|(a, b)| (a - b) * (a - b))
+                            .sum();
+                        dist_2 < particle.h * particle.h
+                    });
+                    // skip the ghosts inserted during a previous iteration of the density loop
+                    if !in_search_radius || !self.inserted_ghosts.insert(ghost_key(origin)) { continue; }
+                    if let VertexInsertion::Inserted(_) = del_tess.insert_ghost_vertex(ghost_position, *origin)? {
+                        let origin_idx = del_tess.ghost_origins().len() - 1;
+                        self.boundary_ghosts.resize(origin_idx + 1, None);
+                        self.boundary_ghosts[origin_idx] = Some(ghost.clone());
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 
-    fn can_interact(&self, other: &Cell, direction: Direction, shift: [f64; 2]) -> bool {
+    pub(super) fn can_interact(&self, other: &Cell<D>, direction: Direction, shift: D::Position) -> bool {
         // Calculate minimal possible distance between a point of self and other
         let direction_vec = direction_as_vector(direction);
-        let min_delta_x = direction_vec[0] * (self.anchor()[0] + self.sides()[0]* direction_vec[0] - (other.anchor()[0] + shift[0]));
-        let min_delta_y = direction_vec[1] * (self.anchor()[1] + self.sides()[1]* direction_vec[1] - (other.anchor()[1] + shift[1]));
-        let min_dist = min_delta_x * min_delta_x + min_delta_y * min_delta_y;
+        let (anchor, sides) = (self.anchor(), self.sides());
+        let other_anchor = shift_position::<D>(other.anchor(), shift, 1.);
+        let mut min_dist = 0.;
+        for (((d, a), s), other_a) in direction_vec.iter().zip(anchor.as_ref()).zip(sides.as_ref()).zip(other_anchor.as_ref()) {
+            let min_delta = d * (a + s * d - other_a);
+            min_dist += min_delta * min_delta;
+        }
         // Can interact?
         min_dist < self.max_h * self.max_h || min_dist < other.max_h * other.max_h
     }
 
-}
\ No newline at end of file
+}
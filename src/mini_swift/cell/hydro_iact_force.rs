@@ -1,7 +1,277 @@
 use crate::mini_swift::Cell;
+use crate::mini_swift::particle::{Particle, N_PRIMITIVES};
+use crate::mini_swift::direction::{Direction, get_direction};
+use crate::mini_swift::riemann::{RiemannSolver, RiemannState};
+use crate::mini_swift::slope_limiter::SlopeLimiter;
+use crate::tessellations::{Dimension, Tessellation, VoronoiGrid, VoronoiFace, FaceNeighbour};
+use crate::error::MiniSwiftError;
 
-impl Cell{
-    pub fn iact_force_self(&mut self) {}
 
-    pub fn iact_force_pair(&mut self, other: &mut Cell) {}
+/// The mass, momentum and total energy exchanged through a face during a time step
+struct Flux<D: Dimension> {
+    mass: f64,
+    momentum: D::Position,
+    energy: f64,
+}
+
+impl<D: Dimension> Flux<D> {
+    /// Moves the exchanged quantities from the particle on the `from` side of the face to the one
+    /// on the `to` side.
+    fn exchange(&self, from: &mut Particle<D>, to: &mut Particle<D>) {
+        self.subtract_from(from);
+        self.add_to(to);
+    }
+
+    fn add_to(&self, particle: &mut Particle<D>) {
+        particle.add_conserved(self.mass, self.momentum, self.energy);
+    }
+
+    fn subtract_from(&self, particle: &mut Particle<D>) {
+        let mut momentum = self.momentum;
+        for p in momentum.as_mut() {
+            *p = -*p;
+        }
+        particle.add_conserved(-self.mass, momentum, -self.energy);
+    }
+}
+
+
+pub(super) fn dot<D: Dimension>(a: D::Position, b: D::Position) -> f64 {
+    a.as_ref().iter().zip(b.as_ref()).map(|(a, b)| a * b).sum()
+}
+
+/// The primitive quantities of `particle` (at `position`, e.g. a periodic image) at the midpoint
+/// of `face` half a time step `dt` ahead (MUSCL-Hancock, Toro 2009, section 14.4): extrapolated
+/// linearly from the centroid with the gradients of the particle (see `Cell::end_gradient`),
+/// limited by the pairwise `limiter` with respect to the `neighbour` (at `neighbour_position`)
+/// and predicted with the primitive Euler equations in the frame of the face moving with
+/// `face_velocity`. The prediction falls back to the values of the particle when it does not
+/// have a positive density and pressure.
+#[allow(clippy::too_many_arguments, reason = "the particle and its neighbour are each passed with the position of their image")]
+fn reconstruct<D: Dimension>(particle: &Particle<D>, position: D::Position, neighbour: &Particle<D>, neighbour_position: D::Position,
+                             face: &D::Face, face_velocity: D::Position, limiter: SlopeLimiter, gamma: f64, dt: f64) -> [f64; N_PRIMITIVES] {
+    let (primitives, gradients) = (particle.primitives(), particle.gradients());
+    if primitives[0] <= 0. {
+        return primitives;
+    }
+    let (mut offset, mut to_face, mut to_neighbour) = (face.midpoint(), face.midpoint(), neighbour_position);
+    for k in 0..D::DIM {
+        // The centroid of the image of the particle at `position`
+        offset.as_mut()[k] -= particle.centroid().as_ref()[k] + position.as_ref()[k] - particle.position().as_ref()[k];
+        to_face.as_mut()[k] -= position.as_ref()[k];
+        to_neighbour.as_mut()[k] -= position.as_ref()[k];
+    }
+    let (face_distance, neighbour_distance) = (dot::<D>(to_face, to_face).sqrt(), dot::<D>(to_neighbour, to_neighbour).sqrt());
+    let neighbour_primitives = neighbour.primitives();
+    let mut reconstructed = primitives;
+    for q in 0..N_PRIMITIVES {
+        let extrapolated = primitives[q] + dot::<D>(gradients[q], offset);
+        reconstructed[q] = limiter.limit_face(primitives[q], neighbour_primitives[q], extrapolated, face_distance, neighbour_distance);
+    }
+
+    let (density, pressure) = (primitives[0], primitives[N_PRIMITIVES - 1]);
+    let (mut relative_velocity, mut divergence) = (face_velocity, 0.);
+    for k in 0..D::DIM {
+        relative_velocity.as_mut()[k] = primitives[k + 1] - face_velocity.as_ref()[k];
+        divergence += gradients[k + 1].as_ref()[k];
+    }
+    let pressure_gradient = gradients[N_PRIMITIVES - 1];
+    reconstructed[0] -= 0.5 * dt * (dot::<D>(relative_velocity, gradients[0]) + density * divergence);
+    for k in 0..D::DIM {
+        reconstructed[k + 1] -= 0.5 * dt * (dot::<D>(relative_velocity, gradients[k + 1]) + pressure_gradient.as_ref()[k] / density);
+    }
+    reconstructed[N_PRIMITIVES - 1] -= 0.5 * dt * (dot::<D>(relative_velocity, pressure_gradient) + gamma * pressure * divergence);
+    if reconstructed[0] > 0. && reconstructed[N_PRIMITIVES - 1] > 0. { reconstructed } else { primitives }
+}
+
+/// The fluxes through the given face from the particle `left` to the particle `right` (at
+/// `right_position`, e.g. a periodic image), integrated over the area of the face and the time step
+/// `dt`. The face moves with the generators, the Riemann problem is solved by `solver` along the
+/// normal of the face between the states reconstructed at the face by `reconstruct`.
+fn face_flux<D: Dimension>(left: &Particle<D>, right: &Particle<D>, right_position: D::Position, face: &D::Face,
+                           solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Flux<D> {
+    let (x_left, w_left, w_right) = (left.position(), left.generator_velocity(), right.generator_velocity());
+    let (mut separation, mut offset, mut face_velocity) = (right_position, face.midpoint(), D::Position::default());
+    for k in 0..D::DIM {
+        separation.as_mut()[k] -= x_left.as_ref()[k];
+        offset.as_mut()[k] -= 0.5 * (x_left.as_ref()[k] + right_position.as_ref()[k]);
+    }
+    let distance_2 = dot::<D>(separation, separation);
+    // The mean velocity of the generators, corrected for the offset of the face from the midpoint
+    // between them (Springel 2010, eq. 33)
+    let correction = (0..D::DIM).map(|k| (w_left.as_ref()[k] - w_right.as_ref()[k]) * offset.as_ref()[k]).sum::<f64>()
+        / distance_2;
+    let mut normal = separation;
+    for k in 0..D::DIM {
+        face_velocity.as_mut()[k] = 0.5 * (w_left.as_ref()[k] + w_right.as_ref()[k]) + correction * separation.as_ref()[k];
+        normal.as_mut()[k] /= distance_2.sqrt();
+    }
+
+    // The Riemann problem along the normal
+    let gamma = solver.gamma();
+    let primitives_left = reconstruct(left, x_left, right, right_position, face, face_velocity, limiter, gamma, dt);
+    let primitives_right = reconstruct(right, right_position, left, x_left, face, face_velocity, limiter, gamma, dt);
+    let velocity = |primitives: &[f64; N_PRIMITIVES]| {
+        let mut velocity = D::Position::default();
+        velocity.as_mut().copy_from_slice(&primitives[1..=D::DIM]);
+        velocity
+    };
+    let (v_left, v_right) = (velocity(&primitives_left), velocity(&primitives_right));
+    let state = |primitives: &[f64; N_PRIMITIVES], velocity: D::Position| {
+        RiemannState::new(primitives[0], dot::<D>(velocity, normal), primitives[N_PRIMITIVES - 1])
+    };
+    let flux = solver.flux(state(&primitives_left, v_left), state(&primitives_right, v_right), dot::<D>(face_velocity, normal));
+    // The tangential velocity is taken from the upwind side
+    let mut tangential_velocity = if flux.mass >= 0. { v_left } else { v_right };
+    let upwind_normal_velocity = dot::<D>(tangential_velocity, normal);
+    for k in 0..D::DIM {
+        tangential_velocity.as_mut()[k] -= upwind_normal_velocity * normal.as_ref()[k];
+    }
+
+    let factor = face.area() * dt;
+    let mut momentum = normal;
+    for k in 0..D::DIM {
+        momentum.as_mut()[k] = factor * (flux.momentum * normal.as_ref()[k] + flux.mass * tangential_velocity.as_ref()[k]);
+    }
+    Flux {
+        mass: factor * flux.mass,
+        momentum,
+        energy: factor * (flux.energy + 0.5 * flux.mass * dot::<D>(tangential_velocity, tangential_velocity)),
+    }
+}
+
+
+impl<D: Dimension> Cell<D> {
+    /// Exchanges the fluxes through the faces of the voronoi grid of this leaf between its own
+    /// particles, between its particles and their periodic images and through the reflective or
+    /// open boundaries (see `iact_density_boundary`). The states behind those boundaries are
+    /// those of the mirrored copies in `boundary_ghosts`. Faces towards ghosts of other cells are
+    /// handled by `iact_force_pair_base`.
+    fn iact_force_self_base(&mut self, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        let vor_tess = self.vor_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?;
+        let ghost_origins = self.del_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?.ghost_origins();
+        let particles = self.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        let id = self.id;
+        for face in vor_tess.faces() {
+            let (i, neighbour) = match face.sides(particles.len()) {
+                Some(sides) => sides,
+                None => continue,
+            };
+            match neighbour {
+                FaceNeighbour::Local(j) => {
+                    let flux = face_flux(&particles[i], &particles[j], particles[j].position(), face, solver, limiter, dt);
+                    flux.subtract_from(&mut particles[i]);
+                    flux.add_to(&mut particles[j]);
+                }
+                FaceNeighbour::Ghost(k) => {
+                    let origin = &ghost_origins[k];
+                    if origin.mirrored.iter().any(|&mirrored| mirrored) {
+                        // Nothing flows back out of the mirrored copy
+                        if let Some(Some(ghost)) = self.boundary_ghosts.get(k) {
+                            face_flux(&particles[i], ghost, ghost.position(), face, solver, limiter, dt).subtract_from(&mut particles[i]);
+                        }
+                    } else if origin.cell_id.is_none_or(|cell_id| cell_id == id) {
+                        // The face between the original and the image of a particle shifted the
+                        // other way is the same face, so only one of them is used.
+                        let j = origin.particle_idx;
+                        if j < i { continue; }
+                        let right_position = origin.ghost_position(particles[j].position());
+                        let flux = face_flux(&particles[i], &particles[j], right_position, face, solver, limiter, dt);
+                        flux.subtract_from(&mut particles[i]);
+                        flux.add_to(&mut particles[j]);
+                    }
+                }
+                FaceNeighbour::Boundary => {
+                    return Err(MiniSwiftError::OpenCell { position: particles[i].position().as_ref().to_vec() });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn iact_force_self_sub_pairs(&mut self, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        let progeny = self.progeny.as_mut().unwrap();
+        for i in 0..progeny.len() {
+            for j in i+1..progeny.len() {
+                let (head, tail) = progeny.split_at_mut(j);
+                let (ci, cj) = (&mut head[i], &mut tail[0]);
+                let direction = get_direction(ci.anchor().as_ref(), cj.anchor().as_ref());
+                ci.iact_force_pair(cj, direction, solver, limiter, dt)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Exchanges the fluxes of mass, momentum and energy over a time step `dt` through all faces
+    /// within this cell, see `iact_force_self_base`, solving the Riemann problems with `solver`
+    /// between the states reconstructed with the gradients of the particles and `limiter` (see
+    /// `end_gradient`, the gradients vanish when the gradient loop is skipped). The particles need
+    /// to know their volume and primitive quantities, see `end_density`. The primitive quantities
+    /// are not updated.
+    pub fn iact_force_self(&mut self, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        match self.progeny.as_mut() {
+            Some(progeny) => {
+                for cell in progeny.iter_mut() {
+                    cell.iact_force_self(solver, limiter, dt)?;
+                }
+                self.iact_force_self_sub_pairs(solver, limiter, dt)
+            }
+            None => self.iact_force_self_base(solver, limiter, dt)
+        }
+    }
+
+    /// Exchanges the fluxes through the faces between the particles of this leaf and the ghosts of
+    /// the particles of `other` shifted by `shift`. Every such face is used once, as it appears in
+    /// the voronoi grid of this leaf: the fluxes are subtracted from the particle of this leaf and
+    /// added to the original particle of `other`.
+    fn iact_force_pair_base(&mut self, other: &mut Cell<D>, shift: D::Position, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        let vor_tess = self.vor_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?;
+        let ghost_origins = self.del_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?.ghost_origins();
+        let particles = self.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        let other_particles = other.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        for face in vor_tess.faces() {
+            let (i, k) = match face.sides(particles.len()) {
+                Some((i, FaceNeighbour::Ghost(k))) => (i, k),
+                _ => continue,
+            };
+            let origin = &ghost_origins[k];
+            if origin.cell_id != Some(other.id) || origin.shift != shift || origin.mirrored.iter().any(|&m| m) {
+                continue;
+            }
+            let other_particle = &mut other_particles[origin.particle_idx];
+            let right_position = origin.ghost_position(other_particle.position());
+            face_flux(&particles[i], other_particle, right_position, face, solver, limiter, dt).exchange(&mut particles[i], other_particle);
+        }
+        Ok(())
+    }
+
+    /// Exchanges the fluxes through the faces between this cell and `other` shifted by `shift`,
+    /// recursing over the progeny of both cells like `iact_density_pair_shift`.
+    pub fn iact_force_pair_shift(&mut self, other: &mut Cell<D>, direction: Direction, shift: D::Position, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        if !self.can_interact(other, direction, shift) { return Ok(()); }
+
+        match self.progeny.as_mut() {
+            Some(progeny) => {
+                for child in progeny.iter_mut() {
+                    child.iact_force_pair_shift(other, direction, shift, solver, limiter, dt)?;
+                }
+                Ok(())
+            }
+            None => {
+                match other.progeny.as_mut() {
+                    Some(other_progeny) => {
+                        for other_child in other_progeny.iter_mut() {
+                            self.iact_force_pair_shift(other_child, direction, shift, solver, limiter, dt)?;
+                        }
+                        Ok(())
+                    }
+                    None => self.iact_force_pair_base(other, shift, solver, limiter, dt)
+                }
+            }
+        }
+    }
+
+    pub fn iact_force_pair(&mut self, other: &mut Cell<D>, direction: Direction, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+        self.iact_force_pair_shift(other, direction, D::Position::default(), solver, limiter, dt)
+    }
 }
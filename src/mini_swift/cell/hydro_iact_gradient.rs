@@ -0,0 +1,179 @@
+use crate::mini_swift::Cell;
+use crate::mini_swift::particle::{Particle, N_PRIMITIVES};
+use crate::mini_swift::direction::{Direction, get_direction};
+use crate::tessellations::{Dimension, Tessellation, VoronoiGrid, VoronoiFace, FaceNeighbour};
+use crate::error::MiniSwiftError;
+use super::hydro_iact_force::dot;
+
+
+/// The contribution of a face to the gradients of the particle on one of its sides, see
+/// `Particle::add_gradient_contribution`
+struct GradientTerm<D: Dimension> {
+    neighbour: [f64; N_PRIMITIVES],
+    weight: D::Position,
+    face_distance: f64,
+}
+
+impl<D: Dimension> GradientTerm<D> {
+    fn add_to(&self, particle: &mut Particle<D>) {
+        particle.add_gradient_contribution(&self.neighbour, self.weight, self.face_distance);
+    }
+}
+
+/// The contributions of the given face to the gradients of the particles `left` and `right` (at
+/// `right_position`, e.g. a periodic image). The estimate of Springel (2010, eq. 21) adds
+/// A (phi_j - phi_i) (f - x_i) / |x_j - x_i| for every face to the gradient of particle i, with
+/// f the midpoint of the face, since the sum of the oriented areas of the faces of a closed cell
+/// vanishes.
+fn gradient_terms<D: Dimension>(left: &Particle<D>, right: &Particle<D>, right_position: D::Position,
+                                face: &D::Face) -> (GradientTerm<D>, GradientTerm<D>) {
+    let (x_left, midpoint) = (left.position(), face.midpoint());
+    let (mut separation, mut left_weight, mut right_weight) = (right_position, midpoint, midpoint);
+    let (mut left_offset, mut right_offset) = (midpoint, midpoint);
+    for k in 0..D::DIM {
+        separation.as_mut()[k] -= x_left.as_ref()[k];
+        left_weight.as_mut()[k] -= x_left.as_ref()[k];
+        right_weight.as_mut()[k] -= right_position.as_ref()[k];
+        left_offset.as_mut()[k] -= left.centroid().as_ref()[k];
+        // The centroid of the image of the right particle
+        right_offset.as_mut()[k] -= right.centroid().as_ref()[k] + right_position.as_ref()[k] - right.position().as_ref()[k];
+    }
+    let factor = face.area() / dot::<D>(separation, separation).sqrt();
+    for k in 0..D::DIM {
+        left_weight.as_mut()[k] *= factor;
+        right_weight.as_mut()[k] *= factor;
+    }
+    (GradientTerm { neighbour: right.primitives(), weight: left_weight, face_distance: dot::<D>(left_offset, left_offset).sqrt() },
+     GradientTerm { neighbour: left.primitives(), weight: right_weight, face_distance: dot::<D>(right_offset, right_offset).sqrt() })
+}
+
+
+impl<D: Dimension> Cell<D> {
+    /// Adds the contributions of the faces of the voronoi grid of this leaf between its own
+    /// particles, between its particles and their periodic images and towards the mirrored copies
+    /// in `boundary_ghosts` to the gradients of its particles, using the faces like
+    /// `iact_force_self_base`.
+    fn iact_gradient_self_base(&mut self) -> Result<(), MiniSwiftError> {
+        let vor_tess = self.vor_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?;
+        let ghost_origins = self.del_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?.ghost_origins();
+        let particles = self.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        let id = self.id;
+        for face in vor_tess.faces() {
+            let (i, neighbour) = match face.sides(particles.len()) {
+                Some(sides) => sides,
+                None => continue,
+            };
+            match neighbour {
+                FaceNeighbour::Local(j) => {
+                    let (left, right) = gradient_terms(&particles[i], &particles[j], particles[j].position(), face);
+                    left.add_to(&mut particles[i]);
+                    right.add_to(&mut particles[j]);
+                }
+                FaceNeighbour::Ghost(k) => {
+                    let origin = &ghost_origins[k];
+                    if origin.mirrored.iter().any(|&mirrored| mirrored) {
+                        if let Some(Some(ghost)) = self.boundary_ghosts.get(k) {
+                            gradient_terms(&particles[i], ghost, ghost.position(), face).0.add_to(&mut particles[i]);
+                        }
+                    } else if origin.cell_id.is_none_or(|cell_id| cell_id == id) {
+                        let j = origin.particle_idx;
+                        if j < i { continue; }
+                        let right_position = origin.ghost_position(particles[j].position());
+                        let (left, right) = gradient_terms(&particles[i], &particles[j], right_position, face);
+                        left.add_to(&mut particles[i]);
+                        right.add_to(&mut particles[j]);
+                    }
+                }
+                FaceNeighbour::Boundary => {
+                    return Err(MiniSwiftError::OpenCell { position: particles[i].position().as_ref().to_vec() });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn iact_gradient_self_sub_pairs(&mut self) -> Result<(), MiniSwiftError> {
+        let progeny = self.progeny.as_mut().unwrap();
+        for i in 0..progeny.len() {
+            for j in i+1..progeny.len() {
+                let (head, tail) = progeny.split_at_mut(j);
+                let (ci, cj) = (&mut head[i], &mut tail[0]);
+                let direction = get_direction(ci.anchor().as_ref(), cj.anchor().as_ref());
+                ci.iact_gradient_pair(cj, direction)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Accumulates the contributions of all faces within this cell to the gradients of the
+    /// primitive quantities of its particles, see `iact_gradient_self_base`. The particles need
+    /// to know their volume and primitive quantities, see `end_density`. The gradients are
+    /// computed from these sums by `end_gradient`.
+    pub fn iact_gradient_self(&mut self) -> Result<(), MiniSwiftError> {
+        match self.progeny.as_mut() {
+            Some(progeny) => {
+                for cell in progeny.iter_mut() {
+                    cell.iact_gradient_self()?;
+                }
+                self.iact_gradient_self_sub_pairs()
+            }
+            None => self.iact_gradient_self_base()
+        }
+    }
+
+    /// Adds the contributions of the faces between the particles of this leaf and the ghosts of
+    /// the particles of `other` shifted by `shift` to the gradients of the particles on both
+    /// sides, using every face once like `iact_force_pair_base`.
+    fn iact_gradient_pair_base(&mut self, other: &mut Cell<D>, shift: D::Position) -> Result<(), MiniSwiftError> {
+        let vor_tess = self.vor_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?;
+        let ghost_origins = self.del_tess.as_ref().ok_or(MiniSwiftError::MissingTessellation)?.ghost_origins();
+        let particles = self.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        let other_particles = other.particles.as_mut().ok_or(MiniSwiftError::NotALeaf)?;
+        for face in vor_tess.faces() {
+            let (i, k) = match face.sides(particles.len()) {
+                Some((i, FaceNeighbour::Ghost(k))) => (i, k),
+                _ => continue,
+            };
+            let origin = &ghost_origins[k];
+            if origin.cell_id != Some(other.id) || origin.shift != shift || origin.mirrored.iter().any(|&m| m) {
+                continue;
+            }
+            let other_particle = &mut other_particles[origin.particle_idx];
+            let right_position = origin.ghost_position(other_particle.position());
+            let (left, right) = gradient_terms(&particles[i], other_particle, right_position, face);
+            left.add_to(&mut particles[i]);
+            right.add_to(other_particle);
+        }
+        Ok(())
+    }
+
+    /// Adds the contributions of the faces between this cell and `other` shifted by `shift` to
+    /// the gradients, recursing over the progeny of both cells like `iact_density_pair_shift`.
+    pub fn iact_gradient_pair_shift(&mut self, other: &mut Cell<D>, direction: Direction, shift: D::Position) -> Result<(), MiniSwiftError> {
+        if !self.can_interact(other, direction, shift) { return Ok(()); }
+
+        match self.progeny.as_mut() {
+            Some(progeny) => {
+                for child in progeny.iter_mut() {
+                    child.iact_gradient_pair_shift(other, direction, shift)?;
+                }
+                Ok(())
+            }
+            None => {
+                match other.progeny.as_mut() {
+                    Some(other_progeny) => {
+                        for other_child in other_progeny.iter_mut() {
+                            self.iact_gradient_pair_shift(other_child, direction, shift)?;
+                        }
+                        Ok(())
+                    }
+                    None => self.iact_gradient_pair_base(other, shift)
+                }
+            }
+        }
+    }
+
+    pub fn iact_gradient_pair(&mut self, other: &mut Cell<D>, direction: Direction) -> Result<(), MiniSwiftError> {
+        self.iact_gradient_pair_shift(other, direction, D::Position::default())
+    }
+}
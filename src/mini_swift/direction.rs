@@ -1,87 +1,59 @@
+/// The direction from one cell to another, given by the sign (-1, 0 or 1) of their offset along
+/// each axis. The components of unused axes (e.g. z in 2D) are 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Direction([i8; 3]);
 
-#[derive(Debug, Clone, Copy)]
-pub enum Direction{
-    RightDown,
-    Right,
-    RightUp,
-    Up,
-    None,
-    Down,
-    LeftDown,
-    Left,
-    LeftUp
+impl Direction {
+    pub const RIGHT_DOWN: Direction = Direction([1, -1, 0]);
+    pub const RIGHT: Direction = Direction([1, 0, 0]);
+    pub const RIGHT_UP: Direction = Direction([1, 1, 0]);
+    pub const UP: Direction = Direction([0, 1, 0]);
+    pub const NONE: Direction = Direction([0, 0, 0]);
+    pub const DOWN: Direction = Direction([0, -1, 0]);
+    pub const LEFT_DOWN: Direction = Direction([-1, -1, 0]);
+    pub const LEFT: Direction = Direction([-1, 0, 0]);
+    pub const LEFT_UP: Direction = Direction([-1, 1, 0]);
+
+    /// Creates the direction with the given components (at most 3, each -1, 0 or 1).
+    pub fn new(components: &[i8]) -> Direction {
+        assert!(components.len() <= 3, "Directions can have at most 3 components!");
+        let mut direction = Direction::NONE;
+        for (i, &c) in components.iter().enumerate() {
+            assert!((-1..=1).contains(&c), "The components of a direction must be -1, 0 or 1!");
+            direction.0[i] = c;
+        }
+        direction
+    }
+
+    pub fn components(&self) -> [i8; 3] {
+        self.0
+    }
 }
 
+#[allow(dead_code, reason = "kept for the sorted pair interactions, which are not implemented yet")]
 pub fn direction_to_sort_list_id(dir: Direction) -> usize {
-    match dir {
-        Direction::RightDown => 0,
-        Direction::Right => 1,
-        Direction::RightUp => 2,
-        Direction::Up => 3,
-        Direction::None => 4,
-        Direction::Down => 5,
-        Direction::LeftDown => 6,
-        Direction::Left => 7,
-        Direction::LeftUp => 8,
-    }
+    dir.0.iter().rev().fold(0, |id, &c| 3 * id + (c + 1) as usize)
 }
 
+#[allow(dead_code, reason = "kept for the sorted pair interactions, which are not implemented yet")]
 pub fn invert_direction(dir: Direction) -> Direction {
-    match dir {
-        Direction::RightDown => Direction::LeftUp,
-        Direction::Right => Direction::Left,
-        Direction::RightUp => Direction::LeftDown,
-        Direction::Up => Direction::Down,
-        Direction::None => Direction::None,
-        Direction::Down => Direction::Up,
-        Direction::LeftDown => Direction::RightUp,
-        Direction::Left => Direction::Right,
-        Direction::LeftUp => Direction::RightDown,
-    }
+    Direction([-dir.0[0], -dir.0[1], -dir.0[2]])
 }
 
-pub fn direction_as_vector(dir: Direction) -> [f64; 2] {
-    match dir {
-        Direction::RightDown => [1., -1.],
-        Direction::Right => [1., 0.],
-        Direction::RightUp => [1., 1.],
-        Direction::Up => [0., 1.],
-        Direction::None => [0., 0.],
-        Direction::Down => [0., -1.],
-        Direction::LeftDown => [-1., -1.],
-        Direction::Left => [-1., 0.],
-        Direction::LeftUp => [-1., 1.],
-    }
+pub fn direction_as_vector(dir: Direction) -> [f64; 3] {
+    [dir.0[0] as f64, dir.0[1] as f64, dir.0[2] as f64]
 }
 
-pub fn get_direction(anchor_i: [f64; 2], anchor_j: [f64; 2]) -> Direction {
-    let left = anchor_i[0] > anchor_j[0];
-    let right = anchor_i[0] < anchor_j[0];
-    let down = anchor_i[1] > anchor_j[1];
-    let up = anchor_i[1] < anchor_j[1];
-    if left {
-        if down {
-            Direction::LeftDown
-        } else if up {
-            Direction::LeftUp
-        } else {
-            Direction::Left
-        }
-    } else if right {
-        if down {
-            Direction::RightDown
-        } else if up {
-            Direction::RightUp
+pub fn get_direction(anchor_i: &[f64], anchor_j: &[f64]) -> Direction {
+    let mut direction = Direction::NONE;
+    for (i, (&a_i, &a_j)) in anchor_i.iter().zip(anchor_j.iter()).enumerate() {
+        direction.0[i] = if a_i > a_j {
+            -1
+        } else if a_i < a_j {
+            1
         } else {
-            Direction::Right
-        }
-    } else {
-        if down {
-            Direction::Down
-        } else if up {
-            Direction::Up
-        } else {
-            Direction::None
-        }
+            0
+        };
     }
-}
\ No newline at end of file
+    direction
+}
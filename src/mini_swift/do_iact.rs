@@ -1,115 +1,531 @@
 use crate::mini_swift::Cell;
 use crate::random_points;
 use crate::mini_swift::direction::Direction;
+use crate::mini_swift::particle::HYDRO_GAMMA;
+use crate::mini_swift::riemann::{ExactRiemannSolver, HllcRiemannSolver, RiemannSolver, TwoRarefactionRiemannSolver};
+use crate::mini_swift::slope_limiter::SlopeLimiter;
+use crate::tessellations::{CheckLevel, Dimension, OneD, Tessellation, TwoD, ThreeD, VoronoiGrid, VoronoiGrid3D};
+use crate::simulation_domain_2d::BoundaryCondition;
+use crate::error::MiniSwiftError;
 
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 
+type Cell2D = Cell<TwoD>;
 
-fn init_cells_4_by_4() -> (Cell, Cell, Cell, Cell) {
+
+fn random_positions(n: i32, cell: &Cell2D, rng: &mut rand::rngs::StdRng) -> Vec<[f64; 2]> {
+    let (x_values, y_values) = random_points(n, &cell.domain(), true, rng);
+    x_values.into_iter().zip(y_values).map(|(x, y)| [x, y]).collect()
+}
+
+
+fn init_cells_4_by_4() -> Result<(Cell2D, Cell2D, Cell2D, Cell2D), MiniSwiftError> {
     let mut rng = rand::rngs::StdRng::seed_from_u64(42);
     let mut ci = Cell::from_dimensions([0., 0.], [1., 1.]);
-    let (x_values, y_values) = random_points(100, &ci.domain(), true, &mut rng);
-    ci.add_particles(&x_values, &y_values, 0.1);
-    ci.split();
+    let positions = random_positions(100, &ci, &mut rng);
+    ci.add_particles(&positions, 0.1)?;
+    ci.split()?;
     ci.delaunay_init();
-    ci.iact_density_self();
+    ci.iact_density_self()?;
 
     let mut cj = Cell::from_dimensions([1., 0.], [1., 1.]);
-    let (x_values, y_values) = random_points(50, &cj.domain(), true, &mut rng);
-    cj.add_particles(&x_values, &y_values, 0.1);
+    let positions = random_positions(50, &cj, &mut rng);
+    cj.add_particles(&positions, 0.1)?;
     cj.delaunay_init();
-    cj.iact_density_self();
+    cj.iact_density_self()?;
 
     let mut ck = Cell::from_dimensions([0., 1.], [1., 1.]);
-    let (x_values, y_values) = random_points(50, &ck.domain(), true, &mut rng);
-    ck.add_particles(&x_values, &y_values, 0.1);
-    // ck.split();
+    let positions = random_positions(50, &ck, &mut rng);
+    ck.add_particles(&positions, 0.1)?;
+    // ck.split()?;
     ck.delaunay_init();
-    ck.iact_density_self();
+    ck.iact_density_self()?;
 
     let mut cl = Cell::from_dimensions([1., 1.], [1., 1.]);
-    let (x_values, y_values) = random_points(100, &cl.domain(), true, &mut rng);
-    cl.add_particles(&x_values, &y_values, 0.1);
-    cl.split();
+    let positions = random_positions(100, &cl, &mut rng);
+    cl.add_particles(&positions, 0.1)?;
+    cl.split()?;
     cl.delaunay_init();
-    cl.iact_density_self();
+    cl.iact_density_self()?;
 
-    (ci, cj, ck, cl)
+    Ok((ci, cj, ck, cl))
 }
 
 // cells are assumed to be in this orientation: ck cl
 //                                              ci cj
-fn do_pair_iact_periodic(ci: &mut Cell, cj: &mut Cell, ck: &mut Cell, cl: &mut Cell) {
-    ci.iact_density_pair_shift(cl, Direction::RightDown, [0., -2.]);
-    ci.iact_density_pair(cj, Direction::Right);
-    ci.iact_density_pair(cl, Direction::RightUp);
-    ci.iact_density_pair(ck, Direction::Up);
+fn do_pair_iact_periodic(ci: &mut Cell2D, cj: &mut Cell2D, ck: &mut Cell2D, cl: &mut Cell2D) -> Result<(), MiniSwiftError> {
+    ci.iact_density_pair_shift(cl, Direction::RIGHT_DOWN, [0., -2.])?;
+    ci.iact_density_pair(cj, Direction::RIGHT)?;
+    ci.iact_density_pair(cl, Direction::RIGHT_UP)?;
+    ci.iact_density_pair(ck, Direction::UP)?;
+
+    cj.iact_density_pair_shift(ck, Direction::RIGHT_DOWN, [2., -2.])?;
+    cj.iact_density_pair_shift(ci, Direction::RIGHT, [2., 0.])?;
+    cj.iact_density_pair_shift(ck, Direction::RIGHT_UP, [2., 0.])?;
+    cj.iact_density_pair(cl, Direction::UP)?;
+
+    ck.iact_density_pair(cj, Direction::RIGHT_DOWN)?;
+    ck.iact_density_pair(cl, Direction::RIGHT)?;
+    ck.iact_density_pair_shift(cj, Direction::RIGHT_UP, [2., 0.])?;
+    ck.iact_density_pair_shift(ci, Direction::UP, [0., 2.])?;
+
+    cl.iact_density_pair_shift(ci, Direction::RIGHT_DOWN, [2., 0.])?;
+    cl.iact_density_pair_shift(ck, Direction::RIGHT, [2., 0.])?;
+    cl.iact_density_pair_shift(ci, Direction::RIGHT_UP, [2., 2.])?;
+    cl.iact_density_pair_shift(cj, Direction::UP, [0., 2.])
+}
+
+/// The gradient loop over the same pairs of cells as `do_pair_iact_periodic`, followed by
+/// `end_gradient` with the given `limiter`
+fn do_gradient_periodic(ci: &mut Cell2D, cj: &mut Cell2D, ck: &mut Cell2D, cl: &mut Cell2D, limiter: SlopeLimiter) -> Result<(), MiniSwiftError> {
+    ci.iact_gradient_self()?;
+    cj.iact_gradient_self()?;
+    ck.iact_gradient_self()?;
+    cl.iact_gradient_self()?;
+
+    ci.iact_gradient_pair_shift(cl, Direction::RIGHT_DOWN, [0., -2.])?;
+    ci.iact_gradient_pair(cj, Direction::RIGHT)?;
+    ci.iact_gradient_pair(cl, Direction::RIGHT_UP)?;
+    ci.iact_gradient_pair(ck, Direction::UP)?;
+
+    cj.iact_gradient_pair_shift(ck, Direction::RIGHT_DOWN, [2., -2.])?;
+    cj.iact_gradient_pair_shift(ci, Direction::RIGHT, [2., 0.])?;
+    cj.iact_gradient_pair_shift(ck, Direction::RIGHT_UP, [2., 0.])?;
+    cj.iact_gradient_pair(cl, Direction::UP)?;
 
-    cj.iact_density_pair_shift(ck, Direction::RightDown, [2., -2.]);
-    cj.iact_density_pair_shift(ci, Direction::Right, [2., 0.]);
-    cj.iact_density_pair_shift(ck, Direction::RightUp, [2., 0.]);
-    cj.iact_density_pair(cl, Direction::Up);
+    ck.iact_gradient_pair(cj, Direction::RIGHT_DOWN)?;
+    ck.iact_gradient_pair(cl, Direction::RIGHT)?;
+    ck.iact_gradient_pair_shift(cj, Direction::RIGHT_UP, [2., 0.])?;
+    ck.iact_gradient_pair_shift(ci, Direction::UP, [0., 2.])?;
 
-    ck.iact_density_pair(cj, Direction::RightDown);
-    ck.iact_density_pair(cl, Direction::Right);
-    ck.iact_density_pair_shift(cj, Direction::RightUp, [2., 0.]);
-    ck.iact_density_pair_shift(ci, Direction::Up, [0., 2.]);
+    cl.iact_gradient_pair_shift(ci, Direction::RIGHT_DOWN, [2., 0.])?;
+    cl.iact_gradient_pair_shift(ck, Direction::RIGHT, [2., 0.])?;
+    cl.iact_gradient_pair_shift(ci, Direction::RIGHT_UP, [2., 2.])?;
+    cl.iact_gradient_pair_shift(cj, Direction::UP, [0., 2.])?;
 
-    cl.iact_density_pair_shift(ci, Direction::RightDown, [2., 0.]);
-    cl.iact_density_pair_shift(ck, Direction::Right, [2., 0.]);
-    cl.iact_density_pair_shift(ci, Direction::RightUp, [2., 2.]);
-    cl.iact_density_pair_shift(cj, Direction::Up, [0., 2.])
+    for cell in [ci, cj, ck, cl] {
+        cell.end_gradient(limiter);
+    }
+    Ok(())
+}
+
+/// The force loop over the same pairs of cells as `do_pair_iact_periodic`, using `solver` and `limiter`
+fn do_force_periodic(ci: &mut Cell2D, cj: &mut Cell2D, ck: &mut Cell2D, cl: &mut Cell2D, solver: &dyn RiemannSolver, limiter: SlopeLimiter, dt: f64) -> Result<(), MiniSwiftError> {
+    ci.iact_force_self(solver, limiter, dt)?;
+    cj.iact_force_self(solver, limiter, dt)?;
+    ck.iact_force_self(solver, limiter, dt)?;
+    cl.iact_force_self(solver, limiter, dt)?;
+
+    ci.iact_force_pair_shift(cl, Direction::RIGHT_DOWN, [0., -2.], solver, limiter, dt)?;
+    ci.iact_force_pair(cj, Direction::RIGHT, solver, limiter, dt)?;
+    ci.iact_force_pair(cl, Direction::RIGHT_UP, solver, limiter, dt)?;
+    ci.iact_force_pair(ck, Direction::UP, solver, limiter, dt)?;
+
+    cj.iact_force_pair_shift(ck, Direction::RIGHT_DOWN, [2., -2.], solver, limiter, dt)?;
+    cj.iact_force_pair_shift(ci, Direction::RIGHT, [2., 0.], solver, limiter, dt)?;
+    cj.iact_force_pair_shift(ck, Direction::RIGHT_UP, [2., 0.], solver, limiter, dt)?;
+    cj.iact_force_pair(cl, Direction::UP, solver, limiter, dt)?;
+
+    ck.iact_force_pair(cj, Direction::RIGHT_DOWN, solver, limiter, dt)?;
+    ck.iact_force_pair(cl, Direction::RIGHT, solver, limiter, dt)?;
+    ck.iact_force_pair_shift(cj, Direction::RIGHT_UP, [2., 0.], solver, limiter, dt)?;
+    ck.iact_force_pair_shift(ci, Direction::UP, [0., 2.], solver, limiter, dt)?;
+
+    cl.iact_force_pair_shift(ci, Direction::RIGHT_DOWN, [2., 0.], solver, limiter, dt)?;
+    cl.iact_force_pair_shift(ck, Direction::RIGHT, [2., 0.], solver, limiter, dt)?;
+    cl.iact_force_pair_shift(ci, Direction::RIGHT_UP, [2., 2.], solver, limiter, dt)?;
+    cl.iact_force_pair_shift(cj, Direction::UP, [0., 2.], solver, limiter, dt)
+}
+
+/// The total mass, momentum and energy of the particles of the given cells
+fn conserved_totals(cells: [&Cell2D; 4]) -> [f64; 4] {
+    let mut totals = [0.; 4];
+    for cell in cells {
+        cell.for_each_particle(&mut |particle| {
+            totals[0] += particle.mass();
+            totals[1] += particle.momentum()[0];
+            totals[2] += particle.momentum()[1];
+            totals[3] += particle.energy();
+        });
+    }
+    totals
 }
 
-fn do_ghost(ci: &mut Cell, cj: &mut Cell, ck: &mut Cell, cl: &mut Cell) {
+fn do_ghost(ci: &mut Cell2D, cj: &mut Cell2D, ck: &mut Cell2D, cl: &mut Cell2D) -> Result<(), MiniSwiftError> {
     let max_iter = 100;
     let mut cur_iter = 0;
-    let mut n_updated_i = ci.update_search_radii();
-    let mut n_updated_j = cj.update_search_radii();
-    let mut n_updated_k = ck.update_search_radii();
-    let mut n_updated_l = cl.update_search_radii();
+    let mut n_updated_i = ci.update_search_radii()?;
+    let mut n_updated_j = cj.update_search_radii()?;
+    let mut n_updated_k = ck.update_search_radii()?;
+    let mut n_updated_l = cl.update_search_radii()?;
     while (n_updated_i > 0 || n_updated_j > 0 || n_updated_k > 0 || n_updated_l > 0) && cur_iter < max_iter {
-        ci.iact_density_self_ghost();
-        cj.iact_density_self_ghost();
-        ck.iact_density_self_ghost();
-        cl.iact_density_self_ghost();
+        ci.iact_density_self_ghost()?;
+        cj.iact_density_self_ghost()?;
+        ck.iact_density_self_ghost()?;
+        cl.iact_density_self_ghost()?;
 
-        do_pair_iact_periodic(ci, cj, ck, cl);
+        do_pair_iact_periodic(ci, cj, ck, cl)?;
 
-        n_updated_i = ci.update_search_radii();
-        n_updated_j = cj.update_search_radii();
-        n_updated_k = ck.update_search_radii();
-        n_updated_l = cl.update_search_radii();
+        n_updated_i = ci.update_search_radii()?;
+        n_updated_j = cj.update_search_radii()?;
+        n_updated_k = ck.update_search_radii()?;
+        n_updated_l = cl.update_search_radii()?;
         cur_iter += 1;
     }
 
-    ci.end_density();
-    cj.end_density();
-    ck.end_density();
-    cl.end_density();
+    ci.end_density()?;
+    cj.end_density()?;
+    ck.end_density()?;
+    cl.end_density()
 }
 
-fn print_tesselations(ci: &Cell, cj: &Cell, ck: &Cell, cl: &Cell) {
-    for i in 0..4 {
-        ci.progeny.as_ref().unwrap()[i].del_tess.as_ref().unwrap().to_file(&format!("output/del_i{}.txt", i));
-        ci.progeny.as_ref().unwrap()[i].vor_tess.as_ref().unwrap().to_file(&format!("output/vor_i{}.txt", i));
-    }
-    cj.del_tess.as_ref().unwrap().to_file("output/del_j.txt");
-    cj.vor_tess.as_ref().unwrap().to_file("output/vor_j.txt");
-    ck.del_tess.as_ref().unwrap().to_file("output/del_k.txt");
-    ck.vor_tess.as_ref().unwrap().to_file("output/vor_k.txt");
-    for i in 0..4 {
-        cl.progeny.as_ref().unwrap()[i].del_tess.as_ref().unwrap().to_file(&format!("output/del_l{}.txt", i));
-        cl.progeny.as_ref().unwrap()[i].vor_tess.as_ref().unwrap().to_file(&format!("output/vor_l{}.txt", i));
+/// Writes the Delaunay tessellation and voronoi grid of every leaf of `cell` to
+/// `output/del_<name>.txt` and `output/vor_<name>.txt`, with the indices of the progeny leading to
+/// the leaf appended to `name`.
+fn write_tessellations<D: Dimension>(cell: &Cell<D>, name: &str) {
+    match cell.progeny.as_ref() {
+        Some(progeny) => {
+            for (i, child) in progeny.iter().enumerate() {
+                write_tessellations(child, &format!("{}{}", name, i));
+            }
+        }
+        None => {
+            cell.del_tess.as_ref().unwrap().to_file(&format!("output/del_{}.txt", name));
+            cell.vor_tess.as_ref().unwrap().to_file(&format!("output/vor_{}.txt", name));
+        }
     }
 }
 
-pub fn do_iact_test() {
-    let (mut ci, mut cj, mut ck, mut cl) = init_cells_4_by_4();
+fn print_tesselations(ci: &Cell2D, cj: &Cell2D, ck: &Cell2D, cl: &Cell2D) {
+    write_tessellations(ci, "i");
+    write_tessellations(cj, "j");
+    write_tessellations(ck, "k");
+    write_tessellations(cl, "l");
+}
+
+pub fn do_iact_test() -> Result<(), MiniSwiftError> {
+    let (mut ci, mut cj, mut ck, mut cl) = init_cells_4_by_4()?;
 
-    do_pair_iact_periodic(&mut ci, &mut cj, &mut ck, &mut cl);
+    do_pair_iact_periodic(&mut ci, &mut cj, &mut ck, &mut cl)?;
+
+    do_ghost(&mut ci, &mut cj, &mut ck, &mut cl)?;
+
+    // uniform initial conditions, the voronoi cells of the particles tile the periodic domain
+    let (mut total_volume, mut total_mass) = (0., 0.);
+    for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+        cell.for_each_particle_mut(&mut |particle| {
+            particle.set_primitives(1., [0.1, 0.], 1.);
+            particle.update_primitives();
+            assert!((particle.pressure() - 1.).abs() < 1e-12, "Primitive quantities not recovered!");
+            total_volume += particle.volume();
+            total_mass += particle.mass();
+        });
+    }
+    assert!((total_volume - 4.).abs() < 1e-10, "Voronoi cells do not tile the domain: {}", total_volume);
+    assert!((total_mass - total_volume).abs() < 1e-12 * total_volume, "Wrong total mass: {}", total_mass);
 
-    do_ghost(&mut ci, &mut cj, &mut ck, &mut cl);
+    let solvers: [(&str, &dyn RiemannSolver); 3] = [
+        ("exact", &ExactRiemannSolver::new(HYDRO_GAMMA)),
+        ("HLLC", &HllcRiemannSolver::new(HYDRO_GAMMA)),
+        ("two-rarefaction", &TwoRarefactionRiemannSolver::new(HYDRO_GAMMA)),
+    ];
+    for (name, solver) in solvers {
+        // the net fluxes of a uniform flow vanish
+        for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+            cell.for_each_particle_mut(&mut |particle| particle.set_primitives(1., [0.1, 0.], 1.));
+        }
+        do_gradient_periodic(&mut ci, &mut cj, &mut ck, &mut cl, SlopeLimiter::Pairwise)?;
+        do_force_periodic(&mut ci, &mut cj, &mut ck, &mut cl, solver, SlopeLimiter::Pairwise, 1e-3)?;
+        for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+            cell.for_each_particle_mut(&mut |particle| {
+                particle.update_primitives();
+                assert!((particle.density() - 1.).abs() < 1e-12 && (particle.pressure() - 1.).abs() < 1e-12,
+                        "Uniform flow not preserved by the {} solver!", name);
+            });
+        }
+
+        // a density wave in pressure equilibrium, the fluxes between the reconstructed states have
+        // to conserve the totals
+        for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+            cell.for_each_particle_mut(&mut |particle| {
+                let [x, y] = particle.position();
+                particle.set_primitives(1. + 0.5 * (std::f64::consts::PI * x).sin(), [0.1, 0.05 * y], 1.);
+            });
+        }
+        do_gradient_periodic(&mut ci, &mut cj, &mut ck, &mut cl, SlopeLimiter::Pairwise)?;
+        let totals = conserved_totals([&ci, &cj, &ck, &cl]);
+        do_force_periodic(&mut ci, &mut cj, &mut ck, &mut cl, solver, SlopeLimiter::Pairwise, 1e-3)?;
+        let new_totals = conserved_totals([&ci, &cj, &ck, &cl]);
+        for (total, new_total) in totals.iter().zip(new_totals.iter()) {
+            assert!((total - new_total).abs() < 1e-12 * (1. + total.abs()),
+                    "Flux exchange with the {} solver is not conservative: {} -> {}", name, total, new_total);
+        }
+    }
 
     print_tesselations(&ci, &cj, &ck, &cl);
-}
\ No newline at end of file
+    Ok(())
+}
+
+/// Four cells covering the periodic domain [0, 2] x [0, 2] (the first and last one split in 4
+/// subcells) with `n` x `n` particles each on a lattice with spacing 1 / n, with their voronoi
+/// grids constructed. The generators coincide with the centroids of their cells, which the
+/// gradient estimate assumes: the offsets between them on irregular meshes add a first order
+/// error to the gradients.
+fn init_cells_lattice(n: usize) -> Result<(Cell2D, Cell2D, Cell2D, Cell2D), MiniSwiftError> {
+    let spacing = 1. / n as f64;
+    let init_cell = |anchor: [f64; 2], split: bool| -> Result<Cell2D, MiniSwiftError> {
+        let mut cell = Cell::from_dimensions(anchor, [1., 1.]);
+        let positions: Vec<[f64; 2]> = (0..n * n).map(|idx| {
+            let (i, j) = ((idx % n) as f64, (idx / n) as f64);
+            [anchor[0] + (i + 0.5) * spacing, anchor[1] + (j + 0.5) * spacing]
+        }).collect();
+        cell.add_particles(&positions, 1.5 * spacing)?;
+        if split {
+            cell.split()?;
+        }
+        cell.delaunay_init();
+        cell.iact_density_self()?;
+        Ok(cell)
+    };
+    let (mut ci, mut cj) = (init_cell([0., 0.], true)?, init_cell([1., 0.], false)?);
+    let (mut ck, mut cl) = (init_cell([0., 1.], false)?, init_cell([1., 1.], true)?);
+
+    do_pair_iact_periodic(&mut ci, &mut cj, &mut ck, &mut cl)?;
+    do_ghost(&mut ci, &mut cj, &mut ck, &mut cl)?;
+    Ok((ci, cj, ck, cl))
+}
+
+/// Advects a density wave in pressure equilibrium with a uniform velocity over a fixed mesh and
+/// returns the L1 error of the density at `time`. The first order scheme skips the gradient
+/// loop (`limiter` is `None`), the second order MUSCL-Hancock scheme limits the gradients with
+/// `limiter`.
+fn advection_density_error(n: usize, solver: &dyn RiemannSolver, limiter: Option<SlopeLimiter>, time: f64) -> Result<f64, MiniSwiftError> {
+    let (mut ci, mut cj, mut ck, mut cl) = init_cells_lattice(n)?;
+    let velocity = [1., 0.5];
+    let density = |x: f64, y: f64| 1. + 0.2 * (std::f64::consts::PI * (x + y)).sin();
+    for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+        cell.for_each_particle_mut(&mut |particle| {
+            let [x, y] = particle.centroid();
+            particle.set_primitives(density(x, y), velocity, 1.);
+            particle.set_generator_velocity([0., 0.]);
+        });
+    }
+
+    // the fastest signal is a sound wave in the least dense part of the wave
+    let max_speed = (velocity[0] * velocity[0] + velocity[1] * velocity[1]).sqrt() + (HYDRO_GAMMA / 0.8).sqrt();
+    let n_steps = (time * max_speed * n as f64 / 0.25).ceil() as usize;
+    let dt = time / n_steps as f64;
+    for _ in 0..n_steps {
+        if let Some(limiter) = limiter {
+            do_gradient_periodic(&mut ci, &mut cj, &mut ck, &mut cl, limiter)?;
+        }
+        do_force_periodic(&mut ci, &mut cj, &mut ck, &mut cl, solver, limiter.unwrap_or(SlopeLimiter::None), dt)?;
+        for cell in [&mut ci, &mut cj, &mut ck, &mut cl] {
+            cell.for_each_particle_mut(&mut |particle| particle.update_primitives());
+        }
+    }
+
+    let mut error = 0.;
+    for cell in [&ci, &cj, &ck, &cl] {
+        cell.for_each_particle(&mut |particle| {
+            let [x, y] = particle.centroid();
+            error += (particle.density() - density(x - velocity[0] * time, y - velocity[1] * time)).abs() * particle.volume();
+        });
+    }
+    Ok(error / 4.)
+}
+
+/// Runs the advection of a smooth density wave at increasing resolutions with the first order
+/// scheme and the MUSCL-Hancock scheme with each slope limiter, and reports the L1 errors and
+/// the orders of convergence. The unlimited second order scheme has to converge at second
+/// order, the limited ones close to it.
+pub fn do_advection_convergence_test() -> Result<(), MiniSwiftError> {
+    let solver = HllcRiemannSolver::new(HYDRO_GAMMA);
+    let schemes = [
+        ("first order", None),
+        ("unlimited", Some(SlopeLimiter::None)),
+        ("Barth-Jespersen", Some(SlopeLimiter::BarthJespersen)),
+        ("pairwise", Some(SlopeLimiter::Pairwise)),
+    ];
+    let resolutions = [8, 16, 32];
+    for (name, limiter) in schemes {
+        let mut errors = Vec::new();
+        for n in resolutions {
+            errors.push(advection_density_error(n, &solver, limiter, 0.5)?);
+        }
+        let orders: Vec<f64> = errors.windows(2).map(|e| (e[0] / e[1]).log2()).collect();
+        let errors: Vec<String> = errors.iter().map(|error| format!("{:.3e}", error)).collect();
+        println!("Advection with the {} scheme, L1 errors of the density: {:?}, orders: {:.2?}", name, errors, orders);
+        // the limiters clip the extrema of the wave, which costs some accuracy at low resolution
+        match limiter {
+            Some(SlopeLimiter::None) => assert!(orders[orders.len() - 1] > 1.8, "MUSCL-Hancock is not second order: {:?}", orders),
+            Some(_) => assert!(orders[orders.len() - 1] > 1.5, "The {} limiter spoils the convergence: {:?}", name, orders),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Runs the density loop on a single cell [0, 1] x [0, 1] (split in 4 subcells) with reflective
+/// walls on the left and right and open boundaries at the top and bottom. The particles near the
+/// boundary are mirrored into the ghosts of the cell, with random velocities to check that those
+/// are mirrored as well. The voronoi cells of the particles must tile the cell.
+pub fn do_iact_test_reflective() -> Result<(), MiniSwiftError> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let mut c = Cell::from_dimensions([0., 0.], [1., 1.]);
+    let positions = random_positions(100, &c, &mut rng);
+    c.add_particles(&positions, 0.1)?;
+    for particle in c.particles_mut().unwrap() {
+        particle.set_velocity([rng.gen::<f64>() - 0.5, rng.gen::<f64>() - 0.5]);
+    }
+    c.split()?;
+    c.delaunay_init();
+    c.iact_density_self()?;
+
+    let (x, y) = (BoundaryCondition::Reflective, BoundaryCondition::Open);
+    let boundaries = [
+        Direction::RIGHT_DOWN, Direction::RIGHT, Direction::RIGHT_UP, Direction::UP,
+        Direction::DOWN, Direction::LEFT_DOWN, Direction::LEFT, Direction::LEFT_UP
+    ];
+    let max_iter = 100;
+    let mut cur_iter = 0;
+    let mut n_updated = 1;
+    while n_updated > 0 && cur_iter < max_iter {
+        c.iact_density_self_ghost()?;
+        for &direction in boundaries.iter() {
+            c.iact_density_boundary(direction, &[x, y])?;
+        }
+        n_updated = c.update_search_radii()?;
+        cur_iter += 1;
+    }
+    c.end_density()?;
+
+    let mut total_volume = 0.;
+    c.for_each_particle(&mut |particle| total_volume += particle.volume());
+    assert!((total_volume - 1.).abs() < 1e-10, "Voronoi cells do not tile the walled domain: {}", total_volume);
+
+    // The velocity of a ghost mirrored in a reflective wall is reversed along the normal of that
+    // wall, its component along the wall and the velocity of ghosts at open sides are unchanged.
+    let progeny = c.progeny.as_ref().unwrap();
+    let mut n_reflected = 0;
+    for cell in progeny {
+        let ghost_origins = cell.del_tess.as_ref().unwrap().ghost_origins();
+        for (origin_idx, ghost) in cell.boundary_ghosts.iter().enumerate() {
+            let ghost = match ghost {
+                Some(ghost) => ghost,
+                None => continue,
+            };
+            let origin = &ghost_origins[origin_idx];
+            let original = &progeny.iter().find(|other| Some(other.id()) == origin.cell_id).unwrap()
+                .particles().unwrap()[origin.particle_idx];
+            assert_eq!(ghost.position(), origin.ghost_position(original.position()), "Misplaced mirrored ghost!");
+            let sign = if origin.mirrored[0] { -1. } else { 1. };
+            assert_eq!(ghost.velocity(), [sign * original.velocity()[0], original.velocity()[1]],
+                       "Wrong velocity of a mirrored ghost!");
+            n_reflected += origin.mirrored[0] as usize;
+        }
+    }
+    assert!(n_reflected > 0, "No ghosts were mirrored in the reflective walls!");
+
+    write_tessellations(&c, "reflective_");
+    Ok(())
+}
+
+/// Runs the density loop on a single cell in 3D, split in 8 subcells, with reflective walls on
+/// all sides and full consistency checks of the tetrahedralizations. The voronoi cells of the
+/// particles must tile the cell, as must the cells of the periodic voronoi grid of the particles.
+pub fn do_iact_test_3d() -> Result<(), MiniSwiftError> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let mut c = Cell::<ThreeD>::from_dimensions([0., 0., 0.], [1., 1., 1.]);
+    let positions: Vec<[f64; 3]> = (0..200).map(|_| [rng.gen(), rng.gen(), rng.gen()]).collect();
+    c.add_particles(&positions, 0.2)?;
+    c.split()?;
+    c.delaunay_init();
+    for cell in c.progeny.as_mut().unwrap() {
+        cell.del_tess.as_mut().unwrap().set_check_level(CheckLevel::Full);
+    }
+    c.iact_density_self()?;
+
+    let mut boundaries = Vec::new();
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                if (x, y, z) != (0, 0, 0) {
+                    boundaries.push(Direction::new(&[x, y, z]));
+                }
+            }
+        }
+    }
+    let max_iter = 100;
+    let mut cur_iter = 0;
+    let mut n_updated = 1;
+    while n_updated > 0 && cur_iter < max_iter {
+        c.iact_density_self_ghost()?;
+        for &direction in boundaries.iter() {
+            c.iact_density_boundary(direction, &[BoundaryCondition::Reflective; 3])?;
+        }
+        n_updated = c.update_search_radii()?;
+        cur_iter += 1;
+    }
+    c.end_density()?;
+
+    let mut total_volume = 0.;
+    c.for_each_particle(&mut |particle| total_volume += particle.volume());
+    assert!((total_volume - 1.).abs() < 1e-10, "Voronoi cells do not tile the domain: {}", total_volume);
+
+    let coordinates = |axis: usize| positions.iter().map(|p| p[axis]).collect::<Vec<f64>>();
+    let grid = VoronoiGrid3D::from_points(&coordinates(0), &coordinates(1), &coordinates(2), c.domain(), true)?;
+    assert!(matches!(VoronoiGrid3D::from_points(&coordinates(0), &coordinates(1), &coordinates(2)[1..], c.domain(), true),
+                     Err(MiniSwiftError::LengthMismatch { .. })));
+    let total_volume: f64 = (0..grid.n_cells()).map(|i| grid.cell_volume(i)).sum();
+    assert!((total_volume - 1.).abs() < 1e-10, "Periodic voronoi cells do not tile the domain: {}", total_volume);
+
+    write_tessellations(&c, "3d_");
+    Ok(())
+}
+
+/// Runs the density loop on two periodic cells in 1D: ci = [0, 1] (split in 2) and cj = [1, 2],
+/// with full consistency checks of the tessellations, and checks that the voronoi cells of the
+/// particles tile the domain.
+pub fn do_iact_test_1d() -> Result<(), MiniSwiftError> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let mut ci = Cell::<OneD>::from_dimensions([0.], [1.]);
+    let positions: Vec<[f64; 1]> = (0..20).map(|_| [rng.gen()]).collect();
+    ci.add_particles(&positions, 0.1)?;
+    ci.split()?;
+    ci.delaunay_init();
+    for cell in ci.progeny.as_mut().unwrap() {
+        cell.del_tess.as_mut().unwrap().set_check_level(CheckLevel::Full);
+    }
+    ci.iact_density_self()?;
+
+    let mut cj = Cell::<OneD>::from_dimensions([1.], [1.]);
+    let positions: Vec<[f64; 1]> = (0..10).map(|_| [1. + rng.gen::<f64>()]).collect();
+    cj.add_particles(&positions, 0.1)?;
+    cj.delaunay_init();
+    cj.del_tess.as_mut().unwrap().set_check_level(CheckLevel::Full);
+    cj.iact_density_self()?;
+
+    let max_iter = 100;
+    let mut cur_iter = 0;
+    let mut n_updated = 1;
+    while n_updated > 0 && cur_iter < max_iter {
+        ci.iact_density_self_ghost()?;
+        ci.iact_density_pair(&mut cj, Direction::RIGHT)?;
+        cj.iact_density_pair_shift(&mut ci, Direction::RIGHT, [2.])?;
+        n_updated = ci.update_search_radii()? + cj.update_search_radii()?;
+        cur_iter += 1;
+    }
+    ci.end_density()?;
+    cj.end_density()?;
+
+    let mut total_volume = 0.;
+    for cell in [&ci, &cj] {
+        cell.for_each_particle(&mut |particle| total_volume += particle.volume());
+    }
+    assert!((total_volume - 2.).abs() < 1e-12, "Voronoi cells do not tile the domain: {}", total_volume);
+
+    write_tessellations(&ci, "1d_i");
+    write_tessellations(&cj, "1d_j");
+    Ok(())
+}
@@ -1,20 +1,255 @@
-pub struct Particle {
-    x: f64,
-    y: f64,
+use crate::mini_swift::slope_limiter::SlopeLimiter;
+use crate::tessellations::{Dimension, GhostOrigin};
+
+/// The adiabatic index of the ideal gas
+pub const HYDRO_GAMMA: f64 = 5. / 3.;
+
+/// The number of primitive quantities, see `Particle::primitives`
+pub const N_PRIMITIVES: usize = 5;
+
+/// The sums over the faces of the voronoi cell of a particle accumulated by the gradient loop,
+/// see `Particle::add_gradient_contribution`
+#[derive(Clone)]
+struct GradientSums<D: Dimension> {
+    gradients: [D::Position; N_PRIMITIVES],
+    /// The range of the primitive quantities of the neighbours
+    min: [f64; N_PRIMITIVES],
+    max: [f64; N_PRIMITIVES],
+    /// The largest distance from the centroid to the midpoint of a face
+    max_distance: f64,
+}
+
+impl<D: Dimension> GradientSums<D> {
+    fn new() -> Self {
+        GradientSums {
+            gradients: [D::Position::default(); N_PRIMITIVES],
+            min: [f64::INFINITY; N_PRIMITIVES],
+            max: [f64::NEG_INFINITY; N_PRIMITIVES],
+            max_distance: 0.,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Particle<D: Dimension> {
+    position: D::Position,
+    velocity: D::Position,
     pub h: f64,
-    pub added_to_del_tess: u16
+    /// The conserved quantities: the mass, momentum and total energy of the fluid in the cell
+    mass: f64,
+    momentum: D::Position,
+    energy: f64,
+    /// The primitive quantities derived from the conserved ones (besides the velocity)
+    density: f64,
+    pressure: f64,
+    /// The volume and centroid of the voronoi cell of the particle, see `Cell::end_density`
+    volume: f64,
+    centroid: D::Position,
+    generator_velocity: D::Position,
+    /// The gradients of the primitive quantities, see `Cell::end_gradient`
+    gradients: [D::Position; N_PRIMITIVES],
+    gradient_sums: GradientSums<D>,
 }
 
-impl Particle {
-    pub fn new(x: f64, y: f64, h: f64) -> Self {
-        Particle{x, y, h, added_to_del_tess: 0}
+impl<D: Dimension> Particle<D> {
+    pub fn new(position: D::Position, h: f64) -> Self {
+        Particle{
+            position,
+            velocity: D::Position::default(),
+            h,
+            mass: 0.,
+            momentum: D::Position::default(),
+            energy: 0.,
+            density: 0.,
+            pressure: 0.,
+            volume: 0.,
+            centroid: position,
+            generator_velocity: D::Position::default(),
+            gradients: [D::Position::default(); N_PRIMITIVES],
+            gradient_sums: GradientSums::new(),
+        }
+    }
+
+    /// A copy of this particle at the position of its ghost with the given origin, with the
+    /// components of its velocities and momentum along the `reflected` axes reversed and its
+    /// gradients mirrored accordingly.
+    pub fn ghost(&self, origin: &GhostOrigin<D>, reflected: &[bool]) -> Particle<D> {
+        let mut ghost = self.clone();
+        ghost.position = origin.ghost_position(self.position);
+        ghost.centroid = origin.ghost_position(self.centroid);
+        for (i, &reflected) in reflected.iter().enumerate().take(D::DIM) {
+            if reflected {
+                ghost.velocity.as_mut()[i] = -ghost.velocity.as_ref()[i];
+                ghost.generator_velocity.as_mut()[i] = -ghost.generator_velocity.as_ref()[i];
+                ghost.momentum.as_mut()[i] = -ghost.momentum.as_ref()[i];
+            }
+        }
+        // The derivatives along mirrored axes change sign, as do those of the reflected
+        // components of the velocity
+        for (q, gradient) in ghost.gradients.iter_mut().enumerate() {
+            let reflected_component = (1..=D::DIM).contains(&q) && reflected[q - 1];
+            for i in 0..D::DIM {
+                if origin.mirrored[i] != reflected_component {
+                    gradient.as_mut()[i] = -gradient.as_ref()[i];
+                }
+            }
+        }
+        ghost
+    }
+
+    pub fn position(&self) -> D::Position {
+        self.position
+    }
+
+    /// The velocity of the fluid
+    pub fn velocity(&self) -> D::Position {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: D::Position) {
+        self.velocity = velocity;
+    }
+
+    /// The velocity of the generator of the voronoi cell, which moves with the fluid unless it is
+    /// set otherwise after `set_primitives`, e.g. to zero for a fixed mesh
+    pub fn generator_velocity(&self) -> D::Position {
+        self.generator_velocity
+    }
+
+    pub fn set_generator_velocity(&mut self, velocity: D::Position) {
+        self.generator_velocity = velocity;
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    pub fn momentum(&self) -> D::Position {
+        self.momentum
     }
 
-    pub fn x(&self) -> f64 {
-        self.x
+    /// The total (internal and kinetic) energy
+    pub fn energy(&self) -> f64 {
+        self.energy
     }
 
-    pub fn y(&self) -> f64 {
-        self.y
+    pub fn density(&self) -> f64 {
+        self.density
     }
-}
\ No newline at end of file
+
+    pub fn pressure(&self) -> f64 {
+        self.pressure
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    pub fn centroid(&self) -> D::Position {
+        self.centroid
+    }
+
+    /// The density, the components of the velocity (zero beyond `DIM`) and the pressure
+    pub fn primitives(&self) -> [f64; N_PRIMITIVES] {
+        let mut primitives = [self.density, 0., 0., 0., self.pressure];
+        primitives[1..=D::DIM].copy_from_slice(self.velocity.as_ref());
+        primitives
+    }
+
+    /// The gradients of the quantities in `primitives`
+    pub fn gradients(&self) -> &[D::Position; N_PRIMITIVES] {
+        &self.gradients
+    }
+
+    /// Adds the contribution of a face to the gradients, i.e. the difference of the primitive
+    /// quantities of the `neighbour` on the other side of the face and of this particle times
+    /// `weight`, and the distance of the face midpoint from the centroid.
+    pub fn add_gradient_contribution(&mut self, neighbour: &[f64; N_PRIMITIVES], weight: D::Position, face_distance: f64) {
+        let primitives = self.primitives();
+        let sums = &mut self.gradient_sums;
+        for q in 0..N_PRIMITIVES {
+            for (g, w) in sums.gradients[q].as_mut().iter_mut().zip(weight.as_ref()) {
+                *g += (neighbour[q] - primitives[q]) * w;
+            }
+            sums.min[q] = sums.min[q].min(neighbour[q]);
+            sums.max[q] = sums.max[q].max(neighbour[q]);
+        }
+        sums.max_distance = sums.max_distance.max(face_distance);
+    }
+
+    /// Divides the sums accumulated by the gradient loop by the volume to get the gradients,
+    /// limits them with the cell-wise `limiter` and resets the sums for the next gradient loop.
+    /// The sums only contain faces towards particles and ghosts, so the cells need to be closed
+    /// by ghosts, e.g. the mirrored ones at reflective or open sides (see
+    /// `iact_density_boundary`): the missing boundary faces of clipped cells would bias the
+    /// gradients, which is why the gradient loop rejects those cells.
+    pub fn end_gradient(&mut self, limiter: SlopeLimiter) {
+        let primitives = self.primitives();
+        let sums = std::mem::replace(&mut self.gradient_sums, GradientSums::new());
+        for (q, gradient) in self.gradients.iter_mut().enumerate() {
+            *gradient = D::Position::default();
+            // Cells without volume keep a zero gradient
+            if self.volume <= 0. {
+                continue;
+            }
+            let norm = sums.gradients[q].as_ref().iter().map(|g| g * g).sum::<f64>().sqrt() / self.volume;
+            let factor = limiter.cell_factor(primitives[q], sums.min[q].min(primitives[q]), sums.max[q].max(primitives[q]),
+                                             norm, sums.max_distance);
+            for (g, sum) in gradient.as_mut().iter_mut().zip(sums.gradients[q].as_ref()) {
+                *g = factor * sum / self.volume;
+            }
+        }
+    }
+
+    /// Sets the volume and centroid of the voronoi cell of the particle and updates the
+    /// primitive quantities accordingly.
+    pub fn set_geometry(&mut self, volume: f64, centroid: D::Position) {
+        self.volume = volume;
+        self.centroid = centroid;
+        self.update_primitives();
+    }
+
+    /// Adds the given changes (e.g. fluxes integrated over a time step) to the conserved
+    /// quantities, without updating the primitive ones.
+    pub fn add_conserved(&mut self, mass: f64, momentum: D::Position, energy: f64) {
+        self.mass += mass;
+        for (p, dp) in self.momentum.as_mut().iter_mut().zip(momentum.as_ref()) {
+            *p += dp;
+        }
+        self.energy += energy;
+    }
+
+    /// Sets the primitive quantities and derives the conserved ones from them, using the current
+    /// volume, e.g. to set up initial conditions after `Cell::end_density`. The generator moves
+    /// with the fluid.
+    pub fn set_primitives(&mut self, density: f64, velocity: D::Position, pressure: f64) {
+        self.density = density;
+        self.velocity = velocity;
+        self.generator_velocity = velocity;
+        self.pressure = pressure;
+        self.mass = density * self.volume;
+        let mut kinetic_energy = 0.;
+        for (p, &v) in self.momentum.as_mut().iter_mut().zip(velocity.as_ref()) {
+            *p = self.mass * v;
+            kinetic_energy += 0.5 * self.mass * v * v;
+        }
+        self.energy = pressure * self.volume / (HYDRO_GAMMA - 1.) + kinetic_energy;
+    }
+
+    /// Derives the density, velocity and pressure from the conserved quantities and the volume.
+    /// Particles without mass or volume keep their velocity and get a zero density and pressure.
+    pub fn update_primitives(&mut self) {
+        if self.mass <= 0. || self.volume <= 0. {
+            self.density = 0.;
+            self.pressure = 0.;
+            return;
+        }
+        self.density = self.mass / self.volume;
+        let mut kinetic_energy = 0.;
+        for (v, &p) in self.velocity.as_mut().iter_mut().zip(self.momentum.as_ref()) {
+            *v = p / self.mass;
+            kinetic_energy += 0.5 * p * *v;
+        }
+        self.pressure = (HYDRO_GAMMA - 1.) * (self.energy - kinetic_energy) / self.volume;
+    }
+}
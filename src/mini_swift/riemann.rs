@@ -0,0 +1,86 @@
+pub use exact::ExactRiemannSolver;
+pub use hllc::HllcRiemannSolver;
+pub use two_rarefaction::TwoRarefactionRiemannSolver;
+
+mod exact;
+mod hllc;
+mod two_rarefaction;
+
+
+/// The primitive state of an ideal gas on one side of a one dimensional Riemann problem, with the
+/// velocity along the axis of the problem. Vacuum has zero density and pressure.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct RiemannState {
+    pub density: f64,
+    pub velocity: f64,
+    pub pressure: f64,
+}
+
+impl RiemannState {
+    pub fn new(density: f64, velocity: f64, pressure: f64) -> RiemannState {
+        RiemannState { density, velocity, pressure }
+    }
+
+    pub fn is_vacuum(&self) -> bool {
+        self.density <= 0.
+    }
+
+    /// The adiabatic sound speed, zero in vacuum
+    pub fn sound_speed(&self, gamma: f64) -> f64 {
+        if self.is_vacuum() { 0. } else { (gamma * self.pressure / self.density).sqrt() }
+    }
+
+    /// The total energy per unit volume
+    pub fn energy(&self, gamma: f64) -> f64 {
+        self.pressure / (gamma - 1.) + 0.5 * self.density * self.velocity * self.velocity
+    }
+
+    /// The same state seen from a frame moving with the given velocity
+    pub fn boosted(&self, frame_velocity: f64) -> RiemannState {
+        RiemannState { velocity: self.velocity - frame_velocity, ..*self }
+    }
+}
+
+
+/// The fluxes of mass, momentum and total energy through a face of unit area between the two
+/// states of a one dimensional Riemann problem
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct RiemannFlux {
+    pub mass: f64,
+    pub momentum: f64,
+    pub energy: f64,
+}
+
+impl RiemannFlux {
+    /// The fluxes of the given state through a face moving with `face_velocity`
+    pub fn from_state(state: &RiemannState, gamma: f64, face_velocity: f64) -> RiemannFlux {
+        let relative_velocity = state.velocity - face_velocity;
+        RiemannFlux {
+            mass: state.density * relative_velocity,
+            momentum: state.density * state.velocity * relative_velocity + state.pressure,
+            energy: state.energy(gamma) * relative_velocity + state.pressure * state.velocity,
+        }
+    }
+
+    /// The fluxes through a face moving with `face_velocity`, given the fluxes through that face
+    /// in its own frame of reference
+    pub fn unboosted(self, face_velocity: f64) -> RiemannFlux {
+        RiemannFlux {
+            mass: self.mass,
+            momentum: self.momentum + face_velocity * self.mass,
+            energy: self.energy + face_velocity * self.momentum + 0.5 * face_velocity * face_velocity * self.mass,
+        }
+    }
+}
+
+
+/// A solver of the Riemann problem of an ideal gas, which can be selected at runtime for the
+/// force loop (see `Cell::iact_force_self`).
+pub trait RiemannSolver {
+    /// The adiabatic index of the gas
+    fn gamma(&self) -> f64;
+
+    /// The fluxes through a face moving with `face_velocity` between the given left and right
+    /// states, with the discontinuity at the face
+    fn flux(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannFlux;
+}
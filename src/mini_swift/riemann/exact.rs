@@ -0,0 +1,235 @@
+use super::{RiemannFlux, RiemannSolver, RiemannState};
+
+
+/// The relative change of the pressure in the star region below which the iteration stops
+const PRESSURE_TOLERANCE: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 100;
+
+
+/// The exact solver of the Riemann problem of an ideal gas with adiabatic index `gamma` (Toro
+/// 2009, chapter 4): the pressure in the star region between the left and right going waves is
+/// found by a Newton-Raphson iteration starting from the two-rarefaction approximation, the
+/// solution is sampled from the resulting wave structure. States with zero density are vacuum, as
+/// is the region between the two rarefactions when they do not meet.
+#[derive(Debug, Copy, Clone)]
+pub struct ExactRiemannSolver {
+    gamma: f64,
+}
+
+/// The region between the left and right going waves, separated by the contact discontinuity
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StarRegion {
+    pub pressure: f64,
+    pub velocity: f64,
+    /// The density to the left of the contact discontinuity
+    pub density_left: f64,
+    /// The density to the right of the contact discontinuity
+    pub density_right: f64,
+}
+
+/// The self-similar solution of a Riemann problem, see `sample`
+#[derive(Debug, Copy, Clone)]
+pub struct ExactRiemannSolution {
+    gamma: f64,
+    left: RiemannState,
+    right: RiemannState,
+    /// `None` if one of the states is vacuum or a vacuum is generated between them
+    star: Option<StarRegion>,
+}
+
+impl ExactRiemannSolver {
+    pub fn new(gamma: f64) -> ExactRiemannSolver {
+        ExactRiemannSolver { gamma }
+    }
+
+    pub fn solve(&self, left: RiemannState, right: RiemannState) -> ExactRiemannSolution {
+        self.solve_with_iterations(left, right, MAX_ITERATIONS)
+    }
+
+    /// The solution with the pressure in the star region found by at most `max_iterations`
+    /// Newton-Raphson steps from the two-rarefaction approximation, which is used as is without
+    /// any iteration.
+    pub(super) fn solve_with_iterations(&self, left: RiemannState, right: RiemannState, max_iterations: usize) -> ExactRiemannSolution {
+        let gamma = self.gamma;
+        let mut solution = ExactRiemannSolution { gamma, left, right, star: None };
+        if left.is_vacuum() || right.is_vacuum() {
+            return solution;
+        }
+        let (c_left, c_right) = (left.sound_speed(gamma), right.sound_speed(gamma));
+        let velocity_difference = right.velocity - left.velocity;
+        // The rarefactions do not meet when the pressure positivity condition is violated
+        if 2. * (c_left + c_right) / (gamma - 1.) <= velocity_difference {
+            return solution;
+        }
+
+        // The two-rarefaction approximation (Toro 2009, eq. 4.46) is exact when both waves are
+        // rarefactions, which makes it a safe starting point for the iteration.
+        let z = (gamma - 1.) / (2. * gamma);
+        let mut pressure = ((c_left + c_right - 0.5 * (gamma - 1.) * velocity_difference)
+            / (c_left / left.pressure.powf(z) + c_right / right.pressure.powf(z))).powf(1. / z);
+        for _ in 0..max_iterations {
+            let (f_left, df_left) = self.pressure_function(pressure, &left);
+            let (f_right, df_right) = self.pressure_function(pressure, &right);
+            let mut new_pressure = pressure - (f_left + f_right + velocity_difference) / (df_left + df_right);
+            // The iteration can overshoot towards negative pressures for strong rarefactions
+            if new_pressure < 0. {
+                new_pressure = PRESSURE_TOLERANCE * pressure;
+            }
+            let change = 2. * (new_pressure - pressure).abs() / (new_pressure + pressure);
+            pressure = new_pressure;
+            if change < PRESSURE_TOLERANCE {
+                break;
+            }
+        }
+
+        let (f_left, _) = self.pressure_function(pressure, &left);
+        let (f_right, _) = self.pressure_function(pressure, &right);
+        solution.star = Some(StarRegion {
+            pressure,
+            velocity: 0.5 * (left.velocity + right.velocity) + 0.5 * (f_right - f_left),
+            density_left: self.star_density(pressure, &left),
+            density_right: self.star_density(pressure, &right),
+        });
+        solution
+    }
+
+    /// The state at the face between the given states moving with `face_velocity`, in the frame
+    /// of the simulation
+    pub fn interface_state(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannState {
+        self.solve(left, right).sample(face_velocity)
+    }
+
+    /// The change in velocity across the wave separating the given state from the star region
+    /// with the given pressure and its derivative with respect to that pressure (Toro 2009, eq.
+    /// 4.6 and 4.7)
+    fn pressure_function(&self, pressure: f64, state: &RiemannState) -> (f64, f64) {
+        let gamma = self.gamma;
+        if pressure > state.pressure {
+            // shock
+            let a = 2. / ((gamma + 1.) * state.density);
+            let b = (gamma - 1.) / (gamma + 1.) * state.pressure;
+            let q = (a / (pressure + b)).sqrt();
+            ((pressure - state.pressure) * q, q * (1. - 0.5 * (pressure - state.pressure) / (pressure + b)))
+        } else {
+            // rarefaction
+            let c = state.sound_speed(gamma);
+            let ratio = pressure / state.pressure;
+            (2. * c / (gamma - 1.) * (ratio.powf((gamma - 1.) / (2. * gamma)) - 1.),
+             ratio.powf(-(gamma + 1.) / (2. * gamma)) / (state.density * c))
+        }
+    }
+
+    /// The density in the star region next to the given state
+    fn star_density(&self, pressure: f64, state: &RiemannState) -> f64 {
+        let gamma = self.gamma;
+        let ratio = pressure / state.pressure;
+        if pressure > state.pressure {
+            let g = (gamma - 1.) / (gamma + 1.);
+            state.density * (ratio + g) / (g * ratio + 1.)
+        } else {
+            state.density * ratio.powf(1. / gamma)
+        }
+    }
+}
+
+impl RiemannSolver for ExactRiemannSolver {
+    fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    fn flux(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannFlux {
+        RiemannFlux::from_state(&self.interface_state(left, right, face_velocity), self.gamma, face_velocity)
+    }
+}
+
+impl ExactRiemannSolution {
+    pub fn star_region(&self) -> Option<StarRegion> {
+        self.star
+    }
+
+    /// The state at x / t = `speed`, with the discontinuity initially at x = 0. Sampling at the
+    /// velocity of a moving face gives the state at that face. Vacuum regions are returned as
+    /// zero density, velocity and pressure.
+    pub fn sample(&self, speed: f64) -> RiemannState {
+        let gamma = self.gamma;
+        let (left, right) = (&self.left, &self.right);
+        let (c_left, c_right) = (left.sound_speed(gamma), right.sound_speed(gamma));
+        let star = match self.star {
+            Some(star) => star,
+            None => {
+                // The fronts of the rarefactions running into vacuum (Toro 2009, section 4.6)
+                let front_left = left.velocity + 2. * c_left / (gamma - 1.);
+                let front_right = right.velocity - 2. * c_right / (gamma - 1.);
+                return if !left.is_vacuum() && speed <= left.velocity - c_left {
+                    *left
+                } else if !left.is_vacuum() && speed < front_left {
+                    self.left_rarefaction_fan(speed)
+                } else if !right.is_vacuum() && speed >= right.velocity + c_right {
+                    *right
+                } else if !right.is_vacuum() && speed > front_right {
+                    self.right_rarefaction_fan(speed)
+                } else {
+                    RiemannState::default()
+                };
+            }
+        };
+
+        if speed <= star.velocity {
+            let star_state = RiemannState::new(star.density_left, star.velocity, star.pressure);
+            if star.pressure > left.pressure {
+                let shock_speed = left.velocity - c_left
+                    * ((gamma + 1.) / (2. * gamma) * star.pressure / left.pressure + (gamma - 1.) / (2. * gamma)).sqrt();
+                if speed <= shock_speed { *left } else { star_state }
+            } else {
+                let c_star = c_left * (star.pressure / left.pressure).powf((gamma - 1.) / (2. * gamma));
+                if speed <= left.velocity - c_left {
+                    *left
+                } else if speed >= star.velocity - c_star {
+                    star_state
+                } else {
+                    self.left_rarefaction_fan(speed)
+                }
+            }
+        } else {
+            let star_state = RiemannState::new(star.density_right, star.velocity, star.pressure);
+            if star.pressure > right.pressure {
+                let shock_speed = right.velocity + c_right
+                    * ((gamma + 1.) / (2. * gamma) * star.pressure / right.pressure + (gamma - 1.) / (2. * gamma)).sqrt();
+                if speed >= shock_speed { *right } else { star_state }
+            } else {
+                let c_star = c_right * (star.pressure / right.pressure).powf((gamma - 1.) / (2. * gamma));
+                if speed >= right.velocity + c_right {
+                    *right
+                } else if speed <= star.velocity + c_star {
+                    star_state
+                } else {
+                    self.right_rarefaction_fan(speed)
+                }
+            }
+        }
+    }
+
+    /// The state inside the left rarefaction fan (Toro 2009, eq. 4.56)
+    fn left_rarefaction_fan(&self, speed: f64) -> RiemannState {
+        let (gamma, left) = (self.gamma, &self.left);
+        let c_left = left.sound_speed(gamma);
+        let c = 2. / (gamma + 1.) * (c_left + 0.5 * (gamma - 1.) * (left.velocity - speed));
+        RiemannState {
+            density: left.density * (c / c_left).powf(2. / (gamma - 1.)),
+            velocity: 2. / (gamma + 1.) * (c_left + 0.5 * (gamma - 1.) * left.velocity + speed),
+            pressure: left.pressure * (c / c_left).powf(2. * gamma / (gamma - 1.)),
+        }
+    }
+
+    /// The state inside the right rarefaction fan (Toro 2009, eq. 4.63)
+    fn right_rarefaction_fan(&self, speed: f64) -> RiemannState {
+        let (gamma, right) = (self.gamma, &self.right);
+        let c_right = right.sound_speed(gamma);
+        let c = 2. / (gamma + 1.) * (c_right - 0.5 * (gamma - 1.) * (right.velocity - speed));
+        RiemannState {
+            density: right.density * (c / c_right).powf(2. / (gamma - 1.)),
+            velocity: 2. / (gamma + 1.) * (-c_right + 0.5 * (gamma - 1.) * right.velocity + speed),
+            pressure: right.pressure * (c / c_right).powf(2. * gamma / (gamma - 1.)),
+        }
+    }
+}
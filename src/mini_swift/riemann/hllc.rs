@@ -0,0 +1,79 @@
+use super::{ExactRiemannSolver, RiemannFlux, RiemannSolver, RiemannState};
+
+
+/// The HLLC approximate Riemann solver (Toro 2009, section 10.4): the solution is approximated by
+/// two constant states between the fastest left and right going waves, separated by the contact
+/// discontinuity. The wave speeds are estimated from the pressure of the linearised primitive
+/// variable solver (Toro 2009, section 10.5.2). Problems involving vacuum are passed on to the
+/// exact solver, which does not need to iterate for them.
+#[derive(Debug, Copy, Clone)]
+pub struct HllcRiemannSolver {
+    exact: ExactRiemannSolver,
+}
+
+impl HllcRiemannSolver {
+    pub fn new(gamma: f64) -> HllcRiemannSolver {
+        HllcRiemannSolver { exact: ExactRiemannSolver::new(gamma) }
+    }
+
+    /// The speed of the wave separating the given state from the star region with the estimated
+    /// pressure, relative to the velocity of the state (Toro 2009, eq. 10.60)
+    fn wave_speed(&self, pressure: f64, state: &RiemannState) -> f64 {
+        let gamma = self.gamma();
+        let c = state.sound_speed(gamma);
+        if pressure <= state.pressure {
+            c
+        } else {
+            c * (1. + (gamma + 1.) / (2. * gamma) * (pressure / state.pressure - 1.)).sqrt()
+        }
+    }
+
+    /// The fluxes through a face at rest
+    fn flux_at_rest(&self, left: &RiemannState, right: &RiemannState) -> RiemannFlux {
+        let gamma = self.gamma();
+        let (c_left, c_right) = (left.sound_speed(gamma), right.sound_speed(gamma));
+        let pressure = (0.5 * (left.pressure + right.pressure)
+            - 0.125 * (right.velocity - left.velocity) * (left.density + right.density) * (c_left + c_right)).max(0.);
+        let speed_left = left.velocity - self.wave_speed(pressure, left);
+        let speed_right = right.velocity + self.wave_speed(pressure, right);
+        if speed_left >= 0. {
+            return RiemannFlux::from_state(left, gamma, 0.);
+        }
+        if speed_right <= 0. {
+            return RiemannFlux::from_state(right, gamma, 0.);
+        }
+
+        // The speed of the contact discontinuity (Toro 2009, eq. 10.37)
+        let mass_left = left.density * (speed_left - left.velocity);
+        let mass_right = right.density * (speed_right - right.velocity);
+        let speed_star = (right.pressure - left.pressure + left.velocity * mass_left - right.velocity * mass_right)
+            / (mass_left - mass_right);
+        let (state, speed) = if speed_star >= 0. { (left, speed_left) } else { (right, speed_right) };
+
+        // The flux of the star state on the side of the face (Toro 2009, eq. 10.38 and 10.39)
+        let flux = RiemannFlux::from_state(state, gamma, 0.);
+        let density = state.density;
+        let energy = state.energy(gamma);
+        let density_star = density * (speed - state.velocity) / (speed - speed_star);
+        let energy_star = density_star * (energy / density
+            + (speed_star - state.velocity) * (speed_star + state.pressure / (density * (speed - state.velocity))));
+        RiemannFlux {
+            mass: flux.mass + speed * (density_star - density),
+            momentum: flux.momentum + speed * (density_star * speed_star - density * state.velocity),
+            energy: flux.energy + speed * (energy_star - energy),
+        }
+    }
+}
+
+impl RiemannSolver for HllcRiemannSolver {
+    fn gamma(&self) -> f64 {
+        self.exact.gamma()
+    }
+
+    fn flux(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannFlux {
+        if left.is_vacuum() || right.is_vacuum() {
+            return self.exact.flux(left, right, face_velocity);
+        }
+        self.flux_at_rest(&left.boosted(face_velocity), &right.boosted(face_velocity)).unboosted(face_velocity)
+    }
+}
@@ -0,0 +1,33 @@
+use super::{ExactRiemannSolver, RiemannFlux, RiemannSolver, RiemannState};
+
+
+/// The two-rarefaction approximate Riemann solver (TRRS, Toro 2009, section 9.4.1): the pressure
+/// in the star region is that of two rarefactions, without any iteration, and the solution is
+/// sampled from the wave structure it implies like in the exact solver. It is exact when both
+/// waves are rarefactions and underestimates the pressure behind shocks.
+#[derive(Debug, Copy, Clone)]
+pub struct TwoRarefactionRiemannSolver {
+    exact: ExactRiemannSolver,
+}
+
+impl TwoRarefactionRiemannSolver {
+    pub fn new(gamma: f64) -> TwoRarefactionRiemannSolver {
+        TwoRarefactionRiemannSolver { exact: ExactRiemannSolver::new(gamma) }
+    }
+
+    /// The state at the face between the given states moving with `face_velocity`, in the frame
+    /// of the simulation
+    pub fn interface_state(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannState {
+        self.exact.solve_with_iterations(left, right, 0).sample(face_velocity)
+    }
+}
+
+impl RiemannSolver for TwoRarefactionRiemannSolver {
+    fn gamma(&self) -> f64 {
+        self.exact.gamma()
+    }
+
+    fn flux(&self, left: RiemannState, right: RiemannState, face_velocity: f64) -> RiemannFlux {
+        RiemannFlux::from_state(&self.interface_state(left, right, face_velocity), self.gamma(), face_velocity)
+    }
+}
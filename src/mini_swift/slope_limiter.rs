@@ -0,0 +1,57 @@
+/// The limiters of the slopes of the linear reconstruction of the primitive quantities at the
+/// faces of the voronoi cells (see `Cell::end_gradient` and `Cell::iact_force_self`), which keep
+/// the second order scheme from creating new extrema near discontinuities.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SlopeLimiter {
+    /// The gradients are used as they are
+    None,
+    /// The cell-wise limiter of Barth & Jespersen (1989) in the form used by SWIFT: the gradient
+    /// of each quantity is scaled such that its extrapolation over the distance to the furthest
+    /// face midpoint stays within the range of the values of the cell and its neighbours.
+    BarthJespersen,
+    /// The pairwise limiter of SWIFT/Shadowfax (Hopkins 2015, appendix B), which keeps the value
+    /// extrapolated to each face close to the range of the values on both sides of that face,
+    /// instead of limiting the gradients themselves.
+    Pairwise,
+}
+
+impl SlopeLimiter {
+    /// The factor by which the cell-wise limiter scales a gradient with the given norm, for a
+    /// cell with the given value, range of the values of the cell and its neighbours and largest
+    /// distance from its centroid to the midpoint of one of its faces. Other limiters leave the
+    /// gradient as it is. Unlike `VoronoiGrid2D::limit_gradients`, this bounds the reconstruction
+    /// at the face midpoints, the only points the force loop extrapolates to, rather than at the
+    /// vertices of the cell, which the particles do not know.
+    pub fn cell_factor(&self, value: f64, min: f64, max: f64, gradient_norm: f64, max_distance: f64) -> f64 {
+        let change = gradient_norm * max_distance;
+        if *self != SlopeLimiter::BarthJespersen || change <= 0. {
+            return 1.;
+        }
+        f64::min(1., f64::min((max - value) / change, (value - min) / change))
+    }
+
+    /// The `face_value` extrapolated from a cell with `value` to a face at `distance` from its
+    /// generator, limited by the pairwise limiter with respect to the value of the neighbour at
+    /// `neighbour_distance` from the generator. Other limiters leave the value as it is.
+    pub fn limit_face(&self, value: f64, neighbour_value: f64, face_value: f64, distance: f64, neighbour_distance: f64) -> f64 {
+        if *self != SlopeLimiter::Pairwise {
+            return face_value;
+        }
+        let difference = (value - neighbour_value).abs();
+        if difference == 0. {
+            return value;
+        }
+        let (delta_1, delta_2) = (0.5 * difference, 0.25 * difference);
+        // The value interpolated linearly between the generators
+        let interpolated = value + distance / neighbour_distance * (neighbour_value - value);
+        // The range of both values, widened without changing sign
+        let widen = |bound: f64, delta: f64| {
+            if (bound + delta) * bound > 0. { bound + delta } else { bound / (1. + delta.abs() / bound.abs()) }
+        };
+        if value < neighbour_value {
+            f64::max(widen(value, -delta_1), f64::min(interpolated + delta_2, face_value))
+        } else {
+            f64::min(widen(value, delta_1), f64::max(interpolated - delta_2, face_value))
+        }
+    }
+}
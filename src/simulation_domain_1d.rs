@@ -0,0 +1,20 @@
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SimulationDomain1D {
+    anchor: [f64; 1],
+    sides: [f64; 1],
+}
+
+impl SimulationDomain1D {
+    pub fn new(a: [f64; 1], s: [f64; 1]) -> SimulationDomain1D {
+        SimulationDomain1D{anchor: a, sides: s}
+    }
+
+    pub fn anchor(&self) -> [f64; 1] {
+        self.anchor
+    }
+
+    pub fn sides(&self) -> [f64; 1] {
+        self.sides
+    }
+}
\ No newline at end of file
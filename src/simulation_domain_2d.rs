@@ -1,20 +1,68 @@
+use crate::error::MiniSwiftError;
+
+/// The treatment of one side of the simulation domain.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BoundaryCondition {
+    /// The side is connected to the opposite side of the domain.
+    #[default]
+    Periodic,
+    /// A wall: ghosts are mirrored copies of the generators near the side, with the velocity
+    /// component normal to the side reversed.
+    Reflective,
+    /// An outflow boundary: ghosts are mirrored copies of the generators near the side, with an
+    /// unchanged velocity.
+    Open,
+}
 
 #[derive(Debug, Default, Copy, Clone)]
 pub struct SimulationDomain2D {
     anchor: [f64; 2],
     sides: [f64; 2],
+    /// The boundary conditions of the lower and upper side along each axis
+    boundary_conditions: [[BoundaryCondition; 2]; 2],
 }
 
 impl SimulationDomain2D {
     pub fn new(a: [f64; 2], s: [f64; 2]) -> SimulationDomain2D {
-        SimulationDomain2D{anchor: a, sides: s}
+        SimulationDomain2D{anchor: a, sides: s, boundary_conditions: Default::default()}
+    }
+
+    /// Sets the boundary conditions of the (lower, upper) sides along the x and y axis. A periodic
+    /// side requires the opposite side to be periodic as well.
+    pub fn with_boundary_conditions(mut self,
+                                    x: [BoundaryCondition; 2],
+                                    y: [BoundaryCondition; 2]) -> Result<SimulationDomain2D, MiniSwiftError> {
+        for [lower, upper] in [x, y] {
+            if (lower == BoundaryCondition::Periodic) != (upper == BoundaryCondition::Periodic) {
+                return Err(MiniSwiftError::InvalidBoundaryConditions);
+            }
+        }
+        self.boundary_conditions = [x, y];
+        Ok(self)
     }
 
     pub fn anchor(&self) -> [f64; 2] {
-        self.anchor.clone()
+        self.anchor
     }
 
     pub fn sides(&self) -> [f64; 2] {
-        self.sides.clone()
+        self.sides
     }
-}
\ No newline at end of file
+
+    /// The boundary condition of the upper or lower side along the given axis
+    pub fn boundary_condition(&self, axis: usize, upper: bool) -> BoundaryCondition {
+        self.boundary_conditions[axis][upper as usize]
+    }
+
+    /// Maps a position outside of the domain along a periodic axis onto its periodic image inside
+    /// of the domain. Coordinates along the other axes are left unchanged.
+    pub fn wrap_periodic(&self, position: [f64; 2]) -> [f64; 2] {
+        let mut wrapped = position;
+        for (axis, coordinate) in wrapped.iter_mut().enumerate() {
+            if self.boundary_condition(axis, false) == BoundaryCondition::Periodic {
+                *coordinate = self.anchor[axis] + (*coordinate - self.anchor[axis]).rem_euclid(self.sides[axis]);
+            }
+        }
+        wrapped
+    }
+}
@@ -0,0 +1,19 @@
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SimulationDomain3D {
+    anchor: [f64; 3],
+    sides: [f64; 3],
+}
+
+impl SimulationDomain3D {
+    pub fn new(a: [f64; 3], s: [f64; 3]) -> SimulationDomain3D {
+        SimulationDomain3D{anchor: a, sides: s}
+    }
+
+    pub fn anchor(&self) -> [f64; 3] {
+        self.anchor
+    }
+
+    pub fn sides(&self) -> [f64; 3] {
+        self.sides
+    }
+}
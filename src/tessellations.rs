@@ -1,9 +1,28 @@
-pub use delaunay2d::DelaunayTriangulation2D;
+pub use delaunay1d::DelaunayTessellation1D;
+pub use voronoi1d::VoronoiGrid1D;
+pub use delaunay2d::{DelaunayTriangulation2D, VertexInsertion, CheckLevel, PointLocation};
 pub use voronoi2d::VoronoiGrid2D;
-use geometry::{Vertex2D, Triangle2D};
+pub use power_diagram2d::PowerDiagram2D;
+pub use gradients2d::GradientEstimator;
+pub use remap2d::Reconstruction;
+pub use delaunay3d::DelaunayTetrahedralization3D;
+pub use voronoi3d::VoronoiGrid3D;
+pub use spatial_sort::InsertionOrder;
+pub use dimension::{Dimension, Domain, OneD, TwoD, ThreeD};
+pub use tessellation::{Tessellation, VoronoiGrid, VoronoiFace, FaceNeighbour, GhostOrigin};
+use geometry::{Vertex1D, Vertex2D, Vertex3D, Triangle2D};
 
+mod voronoi1d;
+mod delaunay1d;
 mod voronoi2d;
 mod delaunay2d;
+mod power_diagram2d;
+mod voronoi_quality2d;
+mod gradients2d;
+mod remap2d;
+mod voronoi3d;
+mod delaunay3d;
 mod geometry;
-
-
+mod spatial_sort;
+mod dimension;
+mod tessellation;
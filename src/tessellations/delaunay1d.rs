@@ -0,0 +1,399 @@
+use crate::simulation_domain_1d::SimulationDomain1D;
+use super::{Vertex1D, Tessellation, GhostOrigin, OneD};
+use super::delaunay2d::{CheckLevel, VertexInsertion};
+use super::spatial_sort::{InsertionOrder, insertion_order, hilbert_key_1d};
+use crate::error::MiniSwiftError;
+use std::fs;
+
+
+#[derive(Debug)]
+pub struct DelaunayVertex1D {
+    pub(super) x: f64,
+    /// The indices of the neighbouring vertices on the left and on the right (-1 for none)
+    pub(super) left: i32,
+    pub(super) right: i32,
+    pub search_radius: f64
+}
+
+impl Default for DelaunayVertex1D {
+    fn default() -> DelaunayVertex1D {
+        DelaunayVertex1D {
+            x: f64::NAN,
+            left: -1,
+            right: -1,
+            search_radius: f64::INFINITY
+        }
+    }
+}
+
+impl DelaunayVertex1D {
+    pub(super) fn as_vertex1d(&self) -> Vertex1D {
+        Vertex1D{x: self.x}
+    }
+}
+
+
+/// The location of a point with respect to the vertices of a `DelaunayTessellation1D`.
+enum PointLocation {
+    /// Strictly between the two (consecutive) vertices with the given indices.
+    Between(i32, i32),
+    /// Coinciding with the vertex with the given index.
+    OnVertex(i32)
+}
+
+
+/// In 1D the Delaunay tessellation simply connects consecutive vertices, so the vertices are
+/// kept in a doubly linked list sorted on their position and the simplices (segments) are
+/// implicit. The layout mirrors `DelaunayTriangulation2D`: vertices 0 and 1 are the ends of a
+/// large segment encapsulating the domain, the actual vertices start at index 2 and are followed
+/// by the ghost vertices.
+#[derive(Debug, Default)]
+pub struct DelaunayTessellation1D {
+    pub vertices: Vec<DelaunayVertex1D>,
+    pub(super) domain: SimulationDomain1D,
+    pub(super) is_periodic: bool,
+    pub n_vertices: usize,
+    ghost_origins: Vec<GhostOrigin<OneD>>,
+    ghost_vertices_offset: Option<usize>,
+    current_vertex_idx: i32,
+    check_level: CheckLevel,
+    touched_vertices: Vec<i32>
+}
+
+impl DelaunayTessellation1D {
+    pub fn new(domain: SimulationDomain1D, vertex_size: usize) -> DelaunayTessellation1D {
+        let mut tessellation = DelaunayTessellation1D{
+            vertices: Vec::with_capacity(vertex_size + 2),
+            domain,
+            ..DelaunayTessellation1D::default()
+        };
+
+        // Create the vertices of the segment encapsulating the domain, large enough so that any
+        // ghost vertices certainly fall strictly inside it.
+        let (anchor, side) = (domain.anchor()[0], domain.sides()[0]);
+        tessellation.vertices.push(DelaunayVertex1D{x: anchor - 2. * side, right: 1, ..DelaunayVertex1D::default()});
+        tessellation.vertices.push(DelaunayVertex1D{x: anchor + 3. * side, left: 0, ..DelaunayVertex1D::default()});
+
+        tessellation.consistency_check();
+        tessellation
+    }
+
+    /// Sets how thoroughly the consistency of the tessellation is verified after every
+    /// modification, see `CheckLevel`.
+    pub fn set_check_level(&mut self, check_level: CheckLevel) {
+        self.check_level = check_level;
+        self.touched_vertices.clear();
+    }
+
+    /// Constructs the Delaunay tessellation of the given points, inserting them from left to
+    /// right. Without periodic boundary conditions, the ends of the domain are reflective and the
+    /// points must lie strictly inside the domain.
+    pub fn from_points(points_x: &[f64],
+                       simulation_domain: SimulationDomain1D,
+                       make_periodic: bool) -> Result<DelaunayTessellation1D, MiniSwiftError> {
+        DelaunayTessellation1D::from_points_with_order(points_x, simulation_domain, make_periodic,
+                                                       InsertionOrder::Hilbert)
+    }
+
+    /// Constructs the Delaunay tessellation of the given points, inserting them in the given
+    /// order. Regardless of the insertion order, the vertices are stored in the order of the input
+    /// points (skipping duplicates), i.e. vertex `i + 2` corresponds to the i-th unique point.
+    pub fn from_points_with_order(points_x: &[f64],
+                                  simulation_domain: SimulationDomain1D,
+                                  make_periodic: bool,
+                                  order: InsertionOrder) -> Result<DelaunayTessellation1D, MiniSwiftError> {
+        let mut d = DelaunayTessellation1D::new(simulation_domain, points_x.len());
+        // duplicate points are not inserted, so they do not get a cell of their own. As with
+        // insertion in input order, the vertex is assigned to the first of the duplicate points.
+        let mut vertex_idx = vec![None; points_x.len()];
+        let mut point_idx = Vec::with_capacity(points_x.len());
+        for i in insertion_order(points_x.len(), |i| hilbert_key_1d(points_x[i], &simulation_domain), order) {
+            match d.insert_vertex(points_x[i])? {
+                VertexInsertion::Inserted(idx) | VertexInsertion::Redundant(idx) => {
+                    vertex_idx[i] = Some(idx);
+                    point_idx.push(i);
+                }
+                VertexInsertion::Duplicate(idx) if idx >= 2 => {
+                    let j = point_idx[idx - 2];
+                    if i < j {
+                        vertex_idx[i] = vertex_idx[j].take();
+                        point_idx[idx - 2] = i;
+                    }
+                }
+                VertexInsertion::Duplicate(_) => {}
+            }
+        }
+        if order != InsertionOrder::Input {
+            d.restore_input_order(&vertex_idx);
+        }
+        d.n_vertices = d.vertices.len() - 2;
+        if make_periodic {
+            d.make_periodic()?;
+        } else {
+            d.make_reflective()?;
+        }
+        Ok(d)
+    }
+
+    pub fn finalize(&mut self) -> Result<(), MiniSwiftError> {
+        match self.ghost_vertices_offset{
+            Some(_) => Err(MiniSwiftError::AlreadyFinalized),
+            None => {
+                self.n_vertices = self.vertices.len() - 2;
+                self.ghost_vertices_offset = Some(self.vertices.len());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn insert_ghost_vertex(&mut self, x: f64, origin: GhostOrigin<OneD>) -> Result<VertexInsertion, MiniSwiftError> {
+        if self.ghost_vertices_offset.is_none() {
+            return Err(MiniSwiftError::NotFinalized);
+        }
+        self.insert_vertex_with_origin(x, origin)
+    }
+
+    /// Inserts a ghost vertex and records where it comes from
+    fn insert_vertex_with_origin(&mut self, x: f64, origin: GhostOrigin<OneD>) -> Result<VertexInsertion, MiniSwiftError> {
+        let insertion = self.insert_vertex(x)?;
+        if let VertexInsertion::Inserted(_) = insertion {
+            self.ghost_origins.push(origin);
+        }
+        Ok(insertion)
+    }
+
+    /// Inserts a new vertex at x between its left and right neighbour. Points coinciding with an
+    /// existing vertex are rejected.
+    pub fn insert_vertex(&mut self, x: f64) -> Result<VertexInsertion, MiniSwiftError> {
+        if !x.is_finite() || x <= self.vertices[0].x || x >= self.vertices[1].x {
+            return Err(MiniSwiftError::PointOutsideDomain { position: vec![x] });
+        }
+        let (left_idx, right_idx) = match self.find_vertices_around(x) {
+            PointLocation::Between(left_idx, right_idx) => (left_idx, right_idx),
+            PointLocation::OnVertex(vertex_idx) => return Ok(VertexInsertion::Duplicate(vertex_idx as usize))
+        };
+
+        let vertex_idx = self.vertices.len() as i32;
+        self.vertices.push(DelaunayVertex1D{x, left: left_idx, right: right_idx, ..DelaunayVertex1D::default()});
+        self.vertices[left_idx as usize].right = vertex_idx;
+        self.vertices[right_idx as usize].left = vertex_idx;
+        self.current_vertex_idx = vertex_idx;
+        self.touch_vertices([left_idx, vertex_idx, right_idx]);
+        self.consistency_check();
+        Ok(VertexInsertion::Inserted(vertex_idx as usize))
+    }
+
+    /// Walks along the vertices, starting from the last inserted one, to the position x.
+    fn find_vertices_around(&self, x: f64) -> PointLocation {
+        let mut left_idx = self.current_vertex_idx;
+        while x < self.vertices[left_idx as usize].x {
+            left_idx = self.vertices[left_idx as usize].left;
+        }
+        loop {
+            let left = &self.vertices[left_idx as usize];
+            if left.x == x {
+                return PointLocation::OnVertex(left_idx);
+            }
+            if x < self.vertices[left.right as usize].x {
+                return PointLocation::Between(left_idx, left.right);
+            }
+            left_idx = left.right;
+        }
+    }
+
+    /// Relabels the vertices such that vertex `i + 2` corresponds to the i-th input point, see
+    /// `DelaunayTriangulation2D::restore_input_order`.
+    fn restore_input_order(&mut self, vertex_idx: &[Option<usize>]) {
+        let mut new_idx: Vec<i32> = (0..self.vertices.len() as i32).collect();
+        for (new, old) in vertex_idx.iter().flatten().enumerate() {
+            new_idx[*old] = new as i32 + 2;
+        }
+        let mut vertices: Vec<DelaunayVertex1D> = Vec::with_capacity(self.vertices.capacity());
+        vertices.resize_with(self.vertices.len(), DelaunayVertex1D::default);
+        for (old, mut vertex) in self.vertices.drain(..).enumerate() {
+            if vertex.left >= 0 { vertex.left = new_idx[vertex.left as usize]; }
+            if vertex.right >= 0 { vertex.right = new_idx[vertex.right as usize]; }
+            vertices[new_idx[old] as usize] = vertex;
+        }
+        self.vertices = vertices;
+        self.current_vertex_idx = new_idx[self.current_vertex_idx as usize];
+        self.consistency_check();
+    }
+
+    /// Adds a ghost copy of the rightmost vertex shifted by the size of the domain to the left
+    /// and vice versa.
+    fn make_periodic(&mut self) -> Result<(), MiniSwiftError> {
+        if self.is_periodic {
+            return Err(MiniSwiftError::AlreadyPeriodic);
+        }
+        if self.n_vertices > 0 {
+            let side = self.domain.sides()[0];
+            let (leftmost_idx, rightmost_idx) = (self.vertices[0].right as usize, self.vertices[1].left as usize);
+            let (leftmost_x, rightmost_x) = (self.vertices[leftmost_idx].x, self.vertices[rightmost_idx].x);
+            self.insert_vertex_with_origin(
+                rightmost_x - side,
+                GhostOrigin::shifted(None, rightmost_idx - 2, [-side])
+            )?;
+            self.insert_vertex_with_origin(
+                leftmost_x + side,
+                GhostOrigin::shifted(None, leftmost_idx - 2, [side])
+            )?;
+        }
+        self.is_periodic = true;
+        Ok(())
+    }
+
+    /// Adds ghost copies of the leftmost and rightmost vertex mirrored around the corresponding
+    /// end of the domain, so that the faces between them lie on the boundary. This requires all
+    /// vertices to lie strictly inside the domain.
+    fn make_reflective(&mut self) -> Result<(), MiniSwiftError> {
+        if self.n_vertices == 0 {
+            return Ok(());
+        }
+        let (anchor, side) = (self.domain.anchor()[0], self.domain.sides()[0]);
+        let (leftmost_idx, rightmost_idx) = (self.vertices[0].right as usize, self.vertices[1].left as usize);
+        let (leftmost_x, rightmost_x) = (self.vertices[leftmost_idx].x, self.vertices[rightmost_idx].x);
+        for x in [leftmost_x, rightmost_x] {
+            if x <= anchor || x >= anchor + side {
+                return Err(MiniSwiftError::PointOutsideDomain { position: vec![x] });
+            }
+        }
+        let mirrored = [true, false, false];
+        self.insert_vertex_with_origin(2. * anchor - leftmost_x, GhostOrigin{
+            cell_id: None, particle_idx: leftmost_idx - 2, shift: [2. * anchor], mirrored
+        })?;
+        self.insert_vertex_with_origin(2. * (anchor + side) - rightmost_x, GhostOrigin{
+            cell_id: None, particle_idx: rightmost_idx - 2, shift: [2. * (anchor + side)], mirrored
+        })?;
+        Ok(())
+    }
+
+    /// The search radius of a vertex is the distance to its farthest neighbour: any point closer
+    /// than that can change its cell.
+    pub fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
+        let mut radii = Vec::<(usize, f64)>::with_capacity(previous_n_vertices_larger_radius);
+        for (i, vertex) in self.vertices[2..self.n_vertices+2].iter().enumerate() {
+            if vertex.search_radius < current_search_radius { continue; }
+            let left_distance = vertex.x - self.vertices[vertex.left as usize].x;
+            let right_distance = self.vertices[vertex.right as usize].x - vertex.x;
+            radii.push((i + 2, left_distance.max(right_distance)));
+        }
+        for (i, radius) in radii {
+            self.vertices[i].search_radius = radius;
+        }
+    }
+
+    /// The indices of the vertices from left to right
+    pub fn sorted_vertex_indices(&self) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(self.vertices.len());
+        let mut vertex_idx = 0;
+        while vertex_idx >= 0 {
+            indices.push(vertex_idx as usize);
+            vertex_idx = self.vertices[vertex_idx as usize].right;
+        }
+        indices
+    }
+
+    fn touch_vertices(&mut self, vertex_indices: impl IntoIterator<Item=i32>) {
+        if self.check_level == CheckLevel::Local {
+            self.touched_vertices.extend(vertex_indices);
+        }
+    }
+
+    fn consistency_check(&mut self) {
+        match self.check_level {
+            CheckLevel::None => {}
+            CheckLevel::Local => {
+                let mut touched = std::mem::take(&mut self.touched_vertices);
+                touched.sort_unstable();
+                touched.dedup();
+                for &vertex_idx in touched.iter() {
+                    self.check_vertex(vertex_idx as usize);
+                }
+                touched.clear();
+                self.touched_vertices = touched;
+            }
+            CheckLevel::Full => {
+                for vertex_idx in 0..self.vertices.len() {
+                    self.check_vertex(vertex_idx);
+                }
+                assert_eq!(self.sorted_vertex_indices().len(), self.vertices.len(),
+                           "Not all vertices can be reached from the leftmost vertex!");
+            }
+        }
+    }
+
+    /// Checks the links of a vertex with its neighbours and their order.
+    fn check_vertex(&self, vertex_idx: usize) {
+        let vertex = &self.vertices[vertex_idx];
+        assert_eq!(vertex.left < 0, vertex_idx == 0, "Only the first vertex has no left neighbour!");
+        assert_eq!(vertex.right < 0, vertex_idx == 1, "Only the second vertex has no right neighbour!");
+        if vertex.left >= 0 {
+            let left = &self.vertices[vertex.left as usize];
+            assert_eq!(left.right, vertex_idx as i32, "Vertex is not the right neighbour of its left neighbour!");
+            assert!(left.x < vertex.x, "Vertices are not sorted!");
+        }
+        if vertex.right >= 0 {
+            let right = &self.vertices[vertex.right as usize];
+            assert_eq!(right.left, vertex_idx as i32, "Vertex is not the left neighbour of its right neighbour!");
+            assert!(vertex.x < right.x, "Vertices are not sorted!");
+        }
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut result = String::from("# Vertices #\n");
+        for (i, v) in self.vertices.iter().enumerate() {
+            result += &format!("{}\t({})\n", i, v.x);
+        }
+
+        result += "\n# Segments #\n";
+        let sorted_vertex_indices = self.sorted_vertex_indices();
+        for (i, segment) in sorted_vertex_indices.windows(2).enumerate() {
+            result += &format!("{}\t({}, {})\n", i, segment[0], segment[1]);
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl Tessellation<OneD> for DelaunayTessellation1D {
+    fn new(domain: SimulationDomain1D, vertex_size: usize, _simplex_size: usize) -> Self {
+        DelaunayTessellation1D::new(domain, vertex_size)
+    }
+
+    fn insert_vertex(&mut self, position: [f64; 1]) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTessellation1D::insert_vertex(self, position[0])
+    }
+
+    fn insert_ghost_vertex(&mut self, position: [f64; 1], origin: GhostOrigin<OneD>) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTessellation1D::insert_ghost_vertex(self, position[0], origin)
+    }
+
+    fn ghost_origins(&self) -> &[GhostOrigin<OneD>] {
+        &self.ghost_origins
+    }
+
+    fn finalize(&mut self) -> Result<(), MiniSwiftError> {
+        DelaunayTessellation1D::finalize(self)
+    }
+
+    fn n_vertices(&self) -> usize {
+        self.n_vertices
+    }
+
+    fn search_radius(&self, vertex_idx: usize) -> f64 {
+        self.vertices[vertex_idx + 2].search_radius
+    }
+
+    fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
+        DelaunayTessellation1D::update_vertex_search_radii(self, current_search_radius, previous_n_vertices_larger_radius)
+    }
+
+    fn to_file(&self, filename: &str) {
+        DelaunayTessellation1D::to_file(self, filename)
+    }
+}
@@ -1,9 +1,11 @@
-use crate::simulation_domain_2d::SimulationDomain2D;
-use crate::tessellations::geometry::{orient_2d, in_circle_2d, circumcenter_2d, circumradius_2d};
-use super::Vertex2D;
+use crate::simulation_domain_2d::{SimulationDomain2D, BoundaryCondition};
+use crate::tessellations::geometry::{orient_2d, in_circle_2d, power_test_2d, circumcenter_2d, circumradius_2d, power_center_2d};
+use super::{Vertex2D, Tessellation, GhostOrigin, TwoD};
+use super::spatial_sort::{InsertionOrder, insertion_order, hilbert_key};
 use crate::utils::random_choose;
 use crate::mini_swift::direction::Direction;
-use std::collections::VecDeque;
+use crate::error::MiniSwiftError;
+use std::collections::{VecDeque, HashMap};
 use std::fs;
 use permutation::Permutation;
 
@@ -14,6 +16,8 @@ pub struct DelaunayVertex2D {
     pub(super) y: f64,
     pub(super) x_scaled: f64,
     pub(super) y_scaled: f64,
+    pub(super) weight: f64,
+    pub(super) weight_scaled: f64,
     pub(super) triangle: i32,
     pub(super) index_in_triangle: i8,
     pub search_radius: f64
@@ -26,6 +30,8 @@ impl Default for DelaunayVertex2D {
             y: f64::NAN,
             x_scaled: f64::NAN,
             y_scaled: f64::NAN,
+            weight: 0.,
+            weight_scaled: 0.,
             triangle: -1,
             index_in_triangle: -1,
             search_radius: f64::INFINITY
@@ -38,6 +44,75 @@ impl DelaunayVertex2D {
         self.triangle = triangle;
         self.index_in_triangle = triangle_index;
     }
+
+    /// Redundant vertices are not part of any triangle, see `VertexInsertion::Redundant`.
+    pub(super) fn is_redundant(&self) -> bool {
+        self.triangle < 0
+    }
+}
+
+
+/// The outcome of inserting a vertex in a `DelaunayTriangulation2D`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VertexInsertion {
+    /// The vertex was inserted and has the given index.
+    Inserted(usize),
+    /// The vertex coincides with the existing vertex with the given index and was not inserted.
+    Duplicate(usize),
+    /// The weighted vertex was stored with the given index, but its power cell is empty: its
+    /// lifted point lies above the lower convex hull of the lifted vertices. It is not part of
+    /// any triangle of the (regular) triangulation.
+    Redundant(usize)
+}
+
+
+/// How thoroughly the consistency of a `DelaunayTriangulation2D` is verified after every
+/// modification.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CheckLevel {
+    /// No checks.
+    None,
+    /// Only check the triangles created or modified by the last modification, and their vertices.
+    Local,
+    /// Check all triangles and vertices. This makes constructing a triangulation quadratic in the
+    /// number of vertices.
+    Full
+}
+
+impl Default for CheckLevel {
+    /// Full checks when the `full-consistency-checks` feature is enabled, local checks in debug
+    /// builds and no checks in release builds otherwise.
+    fn default() -> CheckLevel {
+        if cfg!(feature = "full-consistency-checks") {
+            CheckLevel::Full
+        } else if cfg!(debug_assertions) {
+            CheckLevel::Local
+        } else {
+            CheckLevel::None
+        }
+    }
+}
+
+
+/// The triangles resulting from restoring the regularity of an edge.
+#[derive(Debug, Copy, Clone)]
+enum Flip {
+    /// The edge shared by two triangles was replaced by the other diagonal of their quadrilateral.
+    TwoToTwo(i32, i32),
+    /// The three triangles around a vertex that became redundant were merged.
+    ThreeToOne(i32)
+}
+
+
+/// The location of a point with respect to the triangles of a `DelaunayTriangulation2D`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PointLocation {
+    /// Strictly inside the given triangle.
+    InTriangle(i32),
+    /// On the edge of the given triangle opposite of the vertex with the given index.
+    OnEdge(i32, usize),
+    /// Coinciding with the vertex with the given index.
+    OnVertex(i32)
 }
 
 
@@ -80,6 +155,16 @@ impl DelaunayTriangle2D {
         )
     }
 
+    /// The orthocenter of the weighted vertices of the triangle, i.e. the corresponding vertex of
+    /// the power diagram. This is the circumcenter for unweighted triangulations.
+    pub(super) fn orthocenter(&self, triangulation: &DelaunayTriangulation2D) -> Vertex2D {
+        if !triangulation.is_weighted {
+            return self.circumcenter(triangulation);
+        }
+        let [a, b, c] = self.vertices.map(|v| &triangulation.vertices[v as usize]);
+        power_center_2d(a.x, a.y, a.weight, b.x, b.y, b.weight, c.x, c.y, c.weight)
+    }
+
     fn circumradius(&self, triangulation: &DelaunayTriangulation2D) -> f64 {
         circumradius_2d(
             triangulation.vertices[self.vertices[0] as usize].x,
@@ -98,16 +183,23 @@ pub struct DelaunayTriangulation2D {
     pub vertices: Vec<DelaunayVertex2D>,
     pub(super) triangles: Vec<DelaunayTriangle2D>,
     pub(super) domain: SimulationDomain2D,
-    pub(super) is_periodic: bool,
+    pub(super) has_boundary_ghosts: bool,
+    /// Whether any vertex has a non-zero weight, making this a regular triangulation
+    pub(super) is_weighted: bool,
+    max_weight: f64,
     pub n_vertices: usize,
-    ghost_vertices_cell_directions: Vec<Direction>,
+    pub(super) ghost_origins: Vec<GhostOrigin<TwoD>>,
     ghost_vertices_offset: Option<usize>,
     anchor: [f64; 2],
     side: f64,
     inverse_side: f64,
     current_triangle_idx: i32,
     current_vertex_idx: i32,
-    triangles_to_check: VecDeque<i32>
+    triangles_to_check: VecDeque<i32>,
+    /// Slots of the triangles removed by 3-1 flips during the current insertion
+    freed_triangles: Vec<i32>,
+    check_level: CheckLevel,
+    touched_triangles: Vec<i32>
 }
 
 impl DelaunayTriangulation2D {
@@ -119,22 +211,23 @@ impl DelaunayTriangulation2D {
         };
 
         /* Setup the domain and side of the triangulation box large enough so that any
-        ghost particles certainly fall into the domain.
+        ghost particles certainly fall strictly inside the domain, even those of particles lying
+        on the boundary of the simulation domain.
          */
         triangulation.anchor = [
-            domain.anchor()[0] - domain.sides()[0],
-            domain.anchor()[1] - domain.sides()[1]
+            domain.anchor()[0] - 2. * domain.sides()[0],
+            domain.anchor()[1] - 2. * domain.sides()[1]
         ];
-        triangulation.side = 6. * f64::max(domain.sides()[0], domain.sides()[1]);
+        triangulation.side = 10. * f64::max(domain.sides()[0], domain.sides()[1]);
         triangulation.inverse_side = 1. / triangulation.side;
         triangulation.domain = domain;
 
         // Create vertices for the first triangle that encapsulates the entire domain
-        let v0 = triangulation.new_vertex(triangulation.anchor[0], triangulation.anchor[1]);
+        let v0 = triangulation.new_vertex(triangulation.anchor[0], triangulation.anchor[1], 0.);
         let v1 = triangulation.new_vertex(triangulation.anchor[0] + triangulation.side,
-                                          triangulation.anchor[1]);
+                                          triangulation.anchor[1], 0.);
         let v2 = triangulation.new_vertex(triangulation.anchor[0],
-                                          triangulation.anchor[1] + triangulation.side);
+                                          triangulation.anchor[1] + triangulation.side, 0.);
 
         // Create first large triangle and 3 dummy triangles
         let dummy0 = triangulation.new_triangle(v1, -1, v2);
@@ -158,46 +251,213 @@ impl DelaunayTriangulation2D {
         triangulation
     }
 
-    pub fn from_points(points_x: &Vec<f64>,
-                       points_y: &Vec<f64>,
+    /// Sets how thoroughly the consistency of the triangulation is verified after every
+    /// modification, see `CheckLevel`.
+    pub fn set_check_level(&mut self, check_level: CheckLevel) {
+        self.check_level = check_level;
+        self.touched_triangles.clear();
+    }
+
+    /// Constructs the Delaunay triangulation of the given points, inserting them along a Hilbert
+    /// curve. With `apply_boundary_conditions`, ghost vertices are added around the domain
+    /// according to its boundary conditions (see `SimulationDomain2D::with_boundary_conditions`),
+    /// otherwise the triangulation only contains the given points.
+    pub fn from_points(points_x: &[f64],
+                       points_y: &[f64],
                        simulation_domain: SimulationDomain2D,
-                       make_periodic: bool) -> DelaunayTriangulation2D {
-        assert_eq!(points_x.len(), points_y.len(), "points_x and points_y must have the same length!");
+                       apply_boundary_conditions: bool) -> Result<DelaunayTriangulation2D, MiniSwiftError> {
+        DelaunayTriangulation2D::from_points_with_order(points_x, points_y, simulation_domain,
+                                                        apply_boundary_conditions, InsertionOrder::Hilbert)
+    }
+
+    /// Constructs the Delaunay triangulation of the given points, inserting them in the given
+    /// order. Regardless of the insertion order, the vertices are stored in the order of the input
+    /// points (skipping duplicates), i.e. vertex `i + 3` corresponds to the i-th unique point.
+    pub fn from_points_with_order(points_x: &[f64],
+                                  points_y: &[f64],
+                                  simulation_domain: SimulationDomain2D,
+                                  apply_boundary_conditions: bool,
+                                  order: InsertionOrder) -> Result<DelaunayTriangulation2D, MiniSwiftError> {
+        DelaunayTriangulation2D::from_optionally_weighted_points(points_x, points_y, None, simulation_domain,
+                                                                 apply_boundary_conditions, order)
+    }
+
+    /// Constructs the regular triangulation of the given weighted points, the dual of their power
+    /// diagram, inserting them along a Hilbert curve. As for `from_points_with_order`, vertex
+    /// `i + 3` corresponds to the i-th unique point, even if it is redundant (see
+    /// `VertexInsertion::Redundant`). Boundary ghosts have the weight of their original vertex.
+    pub fn from_weighted_points(points_x: &[f64],
+                                points_y: &[f64],
+                                weights: &[f64],
+                                simulation_domain: SimulationDomain2D,
+                                apply_boundary_conditions: bool) -> Result<DelaunayTriangulation2D, MiniSwiftError> {
+        if weights.len() != points_x.len() {
+            return Err(MiniSwiftError::LengthMismatch { expected: points_x.len(), found: weights.len() });
+        }
+        DelaunayTriangulation2D::from_optionally_weighted_points(points_x, points_y, Some(weights), simulation_domain,
+                                                                 apply_boundary_conditions, InsertionOrder::Hilbert)
+    }
+
+    fn from_optionally_weighted_points(points_x: &[f64],
+                                       points_y: &[f64],
+                                       weights: Option<&[f64]>,
+                                       simulation_domain: SimulationDomain2D,
+                                       apply_boundary_conditions: bool,
+                                       order: InsertionOrder) -> Result<DelaunayTriangulation2D, MiniSwiftError> {
+        if points_y.len() != points_x.len() {
+            return Err(MiniSwiftError::LengthMismatch { expected: points_x.len(), found: points_y.len() });
+        }
         let mut d = DelaunayTriangulation2D::new(simulation_domain, points_x.len(), points_y.len() * 2);
-        for (i, &x) in points_x.iter().enumerate() {
-            d.insert_vertex(x, points_y[i]);
+        // duplicate points are not inserted, so they do not get a cell of their own. As with
+        // insertion in input order, the vertex is assigned to the first of the duplicate points.
+        let mut vertex_idx = vec![None; points_x.len()];
+        let mut point_idx = Vec::with_capacity(points_x.len());
+        for i in insertion_order(points_x.len(), |i| hilbert_key(points_x[i], points_y[i], &simulation_domain), order) {
+            let weight = weights.map_or(0., |weights| weights[i]);
+            match d.insert_weighted_vertex(points_x[i], points_y[i], weight)? {
+                VertexInsertion::Inserted(idx) | VertexInsertion::Redundant(idx) => {
+                    vertex_idx[i] = Some(idx);
+                    point_idx.push(i);
+                }
+                VertexInsertion::Duplicate(idx) if idx >= 3 => {
+                    let j = point_idx[idx - 3];
+                    if i < j {
+                        vertex_idx[i] = vertex_idx[j].take();
+                        point_idx[idx - 3] = i;
+                    }
+                }
+                VertexInsertion::Duplicate(_) => {}
+            }
+        }
+        if order != InsertionOrder::Input {
+            d.restore_input_order(&vertex_idx);
         }
         d.n_vertices = d.vertices.len() - 3;
-        if make_periodic {
-            d.make_periodic();
+        if apply_boundary_conditions {
+            d.add_boundary_ghost_vertices()?;
         }
-        d
+        Ok(d)
     }
 
-    pub fn finalize(&mut self) {
+    pub fn finalize(&mut self) -> Result<(), MiniSwiftError> {
         match self.ghost_vertices_offset{
-            Some(offset) => panic!("Delaunay triangulation was already finalized!"),
+            Some(_) => Err(MiniSwiftError::AlreadyFinalized),
             None => {
                 self.n_vertices = self.vertices.len() - 3;
-                self.ghost_vertices_offset = Some(self.vertices.len())
+                self.ghost_vertices_offset = Some(self.vertices.len());
+                Ok(())
             }
         }
     }
 
-    pub fn insert_ghost_vertex(&mut self, x: f64, y: f64, dir: Direction) {
-        if let Some(_) = self.ghost_vertices_offset {
-            self.ghost_vertices_cell_directions.push(dir);
-            self.insert_vertex(x, y);
+    pub fn insert_ghost_vertex(&mut self, x: f64, y: f64, origin: GhostOrigin<TwoD>) -> Result<VertexInsertion, MiniSwiftError> {
+        if self.ghost_vertices_offset.is_none() {
+            return Err(MiniSwiftError::NotFinalized);
         }
-        else { panic!("Trying to add ghost vertex to non-finalized Delaunay triangulation!"); }
+        self.insert_vertex_with_origin(x, y, 0., origin)
+    }
+
+    /// Inserts a ghost vertex and records where it comes from
+    fn insert_vertex_with_origin(&mut self, x: f64, y: f64, weight: f64, origin: GhostOrigin<TwoD>) -> Result<VertexInsertion, MiniSwiftError> {
+        let insertion = self.insert_weighted_vertex(x, y, weight)?;
+        if let VertexInsertion::Inserted(_) | VertexInsertion::Redundant(_) = insertion {
+            self.ghost_origins.push(origin);
+        }
+        Ok(insertion)
+    }
+
+    /// Inserts a new vertex at (x, y) and restores the Delaunay criterion. Points lying exactly on
+    /// an existing edge split both adjacent triangles, points coinciding with an existing vertex
+    /// are rejected.
+    pub fn insert_vertex(&mut self, x: f64, y: f64) -> Result<VertexInsertion, MiniSwiftError> {
+        self.insert_weighted_vertex(x, y, 0.)
     }
 
-    pub fn insert_vertex(&mut self, x: f64, y: f64) {
+    /// Inserts a new vertex at (x, y) with the given weight, see `insert_vertex`. Once a vertex has
+    /// a non-zero weight, this is a regular triangulation: the power test (see `power_test_2d`)
+    /// replaces the in-circle test and the triangulation is the dual of the power diagram of the
+    /// vertices. The new vertex is not triangulated if its power cell is empty (see
+    /// `VertexInsertion::Redundant`), existing vertices whose power cell becomes empty are removed
+    /// from the triangulation, but keep their index.
+    pub fn insert_weighted_vertex(&mut self, x: f64, y: f64, weight: f64) -> Result<VertexInsertion, MiniSwiftError> {
+        if !x.is_finite() || !y.is_finite() || !weight.is_finite() {
+            return Err(MiniSwiftError::PointOutsideDomain { position: vec![x, y] });
+        }
+        if weight != 0. {
+            self.is_weighted = true;
+            self.max_weight = self.max_weight.max(weight);
+        }
+
         // add vertex
-        self.new_vertex(x, y);
+        self.new_vertex(x, y, weight);
 
         // Find triangle in which (x, y) is positioned
-        self.current_triangle_idx = self.find_triangle_containing_current_vertex();
+        let location = match self.find_triangle_containing_current_vertex() {
+            Ok(location) => location,
+            Err(error) => {
+                self.vertices.pop();
+                return Err(error);
+            }
+        };
+        match location {
+            PointLocation::InTriangle(triangle_idx) | PointLocation::OnEdge(triangle_idx, _)
+                if self.is_weighted && self.is_above_lifted_triangle(triangle_idx) => {
+                self.current_triangle_idx = triangle_idx;
+                return Ok(VertexInsertion::Redundant(self.current_vertex_idx as usize));
+            }
+            PointLocation::InTriangle(triangle_idx) => {
+                self.current_triangle_idx = triangle_idx;
+                self.split_triangle();
+            }
+            PointLocation::OnEdge(triangle_idx, idx_in_triangle) => {
+                self.current_triangle_idx = triangle_idx;
+                self.split_edge(idx_in_triangle);
+            }
+            PointLocation::OnVertex(vertex_idx) => {
+                self.vertices.pop();
+                self.current_vertex_idx = vertex_idx;
+                return Ok(VertexInsertion::Duplicate(vertex_idx as usize));
+            }
+        }
+
+        self.fix_delaunayness();
+        self.compact_freed_triangles();
+
+        self.consistency_check();
+
+        Ok(VertexInsertion::Inserted(self.current_vertex_idx as usize))
+    }
+
+    /// Whether the lifted current vertex lies above the plane through the lifted vertices of the
+    /// given triangle containing it, i.e. whether it is redundant.
+    fn is_above_lifted_triangle(&self, triangle_idx: i32) -> bool {
+        let [a, b, c] = self.triangles[triangle_idx as usize].vertices;
+        self.in_circle(a, b, c, self.current_vertex_idx) > 0.
+    }
+
+    /// Fills the slots of the triangles removed by 3-1 flips with the last triangles.
+    fn compact_freed_triangles(&mut self) {
+        if self.freed_triangles.is_empty() {
+            return;
+        }
+        let mut freed = std::mem::take(&mut self.freed_triangles);
+        freed.sort_unstable_by(|a, b| b.cmp(a));
+        for &free_idx in freed.iter() {
+            let last_idx = self.triangles.len() - 1;
+            if free_idx as usize != last_idx {
+                self.move_triangle(last_idx, free_idx as usize);
+            }
+            self.triangles.pop();
+        }
+        self.current_triangle_idx = self.vertices[self.current_vertex_idx as usize].triangle;
+        // Reuse the allocation
+        freed.clear();
+        self.freed_triangles = freed;
+    }
+
+    /// Replaces the current triangle by 3 new triangles connecting its vertices with the current
+    /// vertex, which lies strictly inside it.
+    fn split_triangle(&mut self) {
         let triangle = &self.triangles[self.current_triangle_idx as usize];
 
         // Create 3 new triangles
@@ -228,32 +488,446 @@ impl DelaunayTriangulation2D {
         self.triangles_to_check.push_back(triangle0);
         self.triangles_to_check.push_back(triangle1);
         self.triangles_to_check.push_back(triangle2);
+    }
 
-        self.fix_delaunayness();
+    /// Replaces the current triangle and its neighbour across the edge opposite of its vertex at
+    /// `idx_in_triangle` by 4 new triangles, for a current vertex lying exactly on that edge.
+    fn split_edge(&mut self, idx_in_triangle: usize) {
+        let triangle = &self.triangles[self.current_triangle_idx as usize];
+        let neighbour_idx = triangle.neighbours[idx_in_triangle];
+        let neighbour = &self.triangles[neighbour_idx as usize];
+        let idx_in_neighbour = triangle.index_in_neighbours[idx_in_triangle] as usize;
+
+        // The current vertex lies on the edge b-c of the triangle (a, b, c) and the neighbouring
+        // triangle (w, c, b).
+        let a = triangle.vertices[idx_in_triangle];
+        let b = triangle.vertices[(idx_in_triangle + 1) % 3];
+        let c = triangle.vertices[(idx_in_triangle + 2) % 3];
+        let w = neighbour.vertices[idx_in_neighbour];
+        let nb = triangle.neighbours[(idx_in_triangle + 1) % 3];
+        let nc = triangle.neighbours[(idx_in_triangle + 2) % 3];
+        let idx_in_nb = triangle.index_in_neighbours[(idx_in_triangle + 1) % 3];
+        let idx_in_nc = triangle.index_in_neighbours[(idx_in_triangle + 2) % 3];
+        let nc_w = neighbour.neighbours[(idx_in_neighbour + 1) % 3];
+        let nb_w = neighbour.neighbours[(idx_in_neighbour + 2) % 3];
+        let idx_in_nc_w = neighbour.index_in_neighbours[(idx_in_neighbour + 1) % 3];
+        let idx_in_nb_w = neighbour.index_in_neighbours[(idx_in_neighbour + 2) % 3];
+
+        // Create 4 new triangles
+        let p = self.current_vertex_idx;
+        let triangle0 = self.new_triangle_at(a, b, p, self.current_triangle_idx);
+        let triangle1 = self.new_triangle(c, a, p);
+        let triangle2 = self.new_triangle_at(b, w, p, neighbour_idx);
+        let triangle3 = self.new_triangle(w, c, p);
+
+        // Update neighbours
+        self.triangles[triangle0 as usize].update_neighbours(triangle2, triangle1, nc,
+                                                             1, 0, idx_in_nc);
+        self.triangles[triangle1 as usize].update_neighbours(triangle0, triangle3, nb,
+                                                             1, 0, idx_in_nb);
+        self.triangles[triangle2 as usize].update_neighbours(triangle3, triangle0, nc_w,
+                                                             1, 0, idx_in_nc_w);
+        self.triangles[triangle3 as usize].update_neighbours(triangle1, triangle2, nb_w,
+                                                             1, 0, idx_in_nb_w);
+        self.triangles[nc as usize].update_neighbour(triangle0, 2, idx_in_nc);
+        self.triangles[nb as usize].update_neighbour(triangle1, 2, idx_in_nb);
+        self.triangles[nc_w as usize].update_neighbour(triangle2, 2, idx_in_nc_w);
+        self.triangles[nb_w as usize].update_neighbour(triangle3, 2, idx_in_nb_w);
+
+        // Add new triangles to queue to check for delaunayness and check the criterion
+        self.triangles_to_check.push_back(triangle0);
+        self.triangles_to_check.push_back(triangle1);
+        self.triangles_to_check.push_back(triangle2);
+        self.triangles_to_check.push_back(triangle3);
+    }
+
+    /// Removes the vertex with index `vertex_idx` from the triangulation and retriangulates the
+    /// resulting star-shaped hole.
+    ///
+    /// Like `Vec::swap_remove`, the last vertex of the triangulation is moved into the freed
+    /// slot. Returns the previous index of that vertex, or `None` when the removed vertex was the
+    /// last one. Vertices can only be removed from unweighted triangulations without ghost
+    /// vertices.
+    pub fn remove_vertex(&mut self, vertex_idx: usize) -> Result<Option<usize>, MiniSwiftError> {
+        if vertex_idx < 3 || vertex_idx >= self.vertices.len() {
+            return Err(MiniSwiftError::InvalidVertex(vertex_idx));
+        }
+        if self.has_ghost_vertices() {
+            return Err(MiniSwiftError::GhostVerticesPresent);
+        }
+        if self.is_weighted {
+            return Err(MiniSwiftError::WeightedVerticesPresent);
+        }
+
+        // Collect the triangles around the vertex. The vertices opposite of the removed vertex
+        // form a counterclockwise polygon, of which we also store the outside neighbours of
+        // each edge (p_k, p_k+1).
+        let star = self.get_triangle_idx_around_vertex(vertex_idx);
+        let mut polygon = Vec::<i32>::with_capacity(star.len());
+        let mut boundary = HashMap::<(i32, i32), (i32, i8)>::with_capacity(star.len());
+        for &triangle_idx in star.iter() {
+            let triangle = &self.triangles[triangle_idx];
+            let idx_in_triangle = triangle.vertices.iter().position(|&v| v == vertex_idx as i32).unwrap();
+            let (p, q) = (triangle.vertices[(idx_in_triangle + 1) % 3], triangle.vertices[(idx_in_triangle + 2) % 3]);
+            polygon.push(p);
+            boundary.insert((p, q), (triangle.neighbours[idx_in_triangle], triangle.index_in_neighbours[idx_in_triangle]));
+        }
+
+        // Retriangulate the polygon by clipping Delaunay ears: convex ears whose circumcircle
+        // does not contain any other vertex of the polygon. The slots of the old triangles are
+        // reused.
+        let mut new_triangles = Vec::<i32>::with_capacity(star.len() - 2);
+        while polygon.len() > 3 {
+            let n = polygon.len();
+            let ear = (0..n).find(|&k| {
+                let (a, b, c) = (polygon[k], polygon[(k + 1) % n], polygon[(k + 2) % n]);
+                let (va, vb, vc) = (&self.vertices[a as usize], &self.vertices[b as usize], &self.vertices[c as usize]);
+                orient_2d(va.x_scaled, va.y_scaled, vb.x_scaled, vb.y_scaled, vc.x_scaled, vc.y_scaled) > 0.
+                    && (3..n).all(|l| self.in_circle(a, b, c, polygon[(k + l) % n]) > 0.)
+            }).expect("A polygon always has at least one Delaunay ear!");
+            let (a, b, c) = (polygon[ear], polygon[(ear + 1) % n], polygon[(ear + 2) % n]);
+            new_triangles.push(self.new_triangle_at(a, b, c, star[new_triangles.len()] as i32));
+            polygon.remove((ear + 1) % n);
+        }
+        new_triangles.push(self.new_triangle_at(polygon[0], polygon[1], polygon[2], star[new_triangles.len()] as i32));
+
+        // Restore the neighbour relations, both between the new triangles and with the triangles
+        // outside of the polygon.
+        let mut edges = HashMap::<(i32, i32), (i32, i8)>::with_capacity(3 * new_triangles.len());
+        for &triangle_idx in new_triangles.iter() {
+            let vertices = self.triangles[triangle_idx as usize].vertices;
+            for i in 0..3 {
+                edges.insert((vertices[(i + 1) % 3], vertices[(i + 2) % 3]), (triangle_idx, i as i8));
+            }
+        }
+        for (&(u, w), &(triangle_idx, i)) in edges.iter() {
+            let (neighbour_idx, idx_in_neighbour) = match boundary.get(&(u, w)) {
+                Some(&outside) => {
+                    self.triangles[outside.0 as usize].update_neighbour(triangle_idx, i, outside.1);
+                    outside
+                }
+                None => edges[&(w, u)]
+            };
+            self.triangles[triangle_idx as usize].update_neighbour(neighbour_idx, idx_in_neighbour, i);
+        }
+
+        // Compact the triangles: move the last triangles into the two freed slots. Keep track of
+        // one of the new triangles to start the next point location walk from.
+        let mut near_triangle_idx = new_triangles[0] as usize;
+        let mut freed = star[new_triangles.len()..].to_vec();
+        freed.sort_unstable_by(|a, b| b.cmp(a));
+        for free_idx in freed {
+            let last_idx = self.triangles.len() - 1;
+            if free_idx != last_idx {
+                self.move_triangle(last_idx, free_idx);
+                if near_triangle_idx == last_idx {
+                    near_triangle_idx = free_idx;
+                }
+            }
+            self.triangles.pop();
+        }
+
+        // Compact the vertices: move the last vertex into the freed slot.
+        let last_vertex_idx = self.vertices.len() - 1;
+        let moved = if vertex_idx != last_vertex_idx {
+            let last_vertex_star = self.get_triangle_idx_around_vertex(last_vertex_idx);
+            self.touch_triangles(last_vertex_star.iter().map(|&triangle_idx| triangle_idx as i32));
+            for triangle_idx in last_vertex_star {
+                let triangle = &mut self.triangles[triangle_idx];
+                let idx_in_triangle = triangle.vertices.iter().position(|&v| v == last_vertex_idx as i32).unwrap();
+                triangle.vertices[idx_in_triangle] = vertex_idx as i32;
+            }
+            Some(last_vertex_idx)
+        } else {
+            None
+        };
+        self.vertices.swap_remove(vertex_idx);
+        if self.n_vertices + 3 > self.vertices.len() {
+            self.n_vertices = self.vertices.len() - 3;
+        }
+        if self.ghost_vertices_offset.is_some() {
+            self.ghost_vertices_offset = Some(self.vertices.len());
+        }
+        self.current_triangle_idx = near_triangle_idx as i32;
 
         self.consistency_check();
+
+        Ok(moved)
     }
 
-    fn new_vertex(&mut self, x: f64, y: f64) -> i32 {
+    /// Moves the vertex with index `vertex_idx` to (x, y) and restores the Delaunay criterion.
+    ///
+    /// As long as the vertex stays within the polygon formed by its neighbours, it is moved in
+    /// place and the triangulation is repaired with local edge flips. Otherwise the vertex is
+    /// removed and reinserted at its new position, keeping its index. Vertices can only be moved
+    /// in unweighted triangulations without ghost vertices. Positions outside of the triangulation
+    /// or at another vertex are rejected, leaving the triangulation unchanged (unless putting the
+    /// vertex back fails as well, which leaves it removed as by `remove_vertex`).
+    pub fn move_vertex(&mut self, vertex_idx: usize, x: f64, y: f64) -> Result<(), MiniSwiftError> {
+        if vertex_idx < 3 || vertex_idx >= self.vertices.len() {
+            return Err(MiniSwiftError::InvalidVertex(vertex_idx));
+        }
+        if self.has_ghost_vertices() {
+            return Err(MiniSwiftError::GhostVerticesPresent);
+        }
+        if self.is_weighted {
+            return Err(MiniSwiftError::WeightedVerticesPresent);
+        }
+        if !x.is_finite() || !y.is_finite() {
+            return Err(MiniSwiftError::PointOutsideDomain { position: vec![x, y] });
+        }
+
+        let (x_scaled, y_scaled) = (x * self.inverse_side, y * self.inverse_side);
+        let star = self.get_triangle_idx_around_vertex(vertex_idx);
+        let stays_in_star = star.iter().all(|&triangle_idx| {
+            let triangle = &self.triangles[triangle_idx];
+            let idx_in_triangle = triangle.vertices.iter().position(|&v| v == vertex_idx as i32).unwrap();
+            let p = &self.vertices[triangle.vertices[(idx_in_triangle + 1) % 3] as usize];
+            let q = &self.vertices[triangle.vertices[(idx_in_triangle + 2) % 3] as usize];
+            orient_2d(p.x_scaled, p.y_scaled, q.x_scaled, q.y_scaled, x_scaled, y_scaled) > 0.
+        });
+
+        if stays_in_star {
+            let vertex = &mut self.vertices[vertex_idx];
+            vertex.x = x;
+            vertex.y = y;
+            vertex.x_scaled = x_scaled;
+            vertex.y_scaled = y_scaled;
+            self.touch_triangles(star.iter().map(|&triangle_idx| triangle_idx as i32));
+            // All triangles around the vertex remain positively oriented, but all their edges
+            // might now violate the Delaunay criterion. Every triangle contributes its outer edge
+            // and one of the two edges incident to the vertex, such that all edges are checked once.
+            let edges_to_check = star.iter()
+                .flat_map(|&triangle_idx| {
+                    let idx_in_triangle = self.triangles[triangle_idx].vertices.iter()
+                        .position(|&v| v == vertex_idx as i32).unwrap();
+                    [(triangle_idx as i32, idx_in_triangle), (triangle_idx as i32, (idx_in_triangle + 1) % 3)]
+                })
+                .collect();
+            self.fix_edges(edges_to_check);
+        } else {
+            // Locate the new position first, such that invalid positions leave the triangulation
+            // untouched
+            if let PointLocation::OnVertex(_) = self.locate(x, y)? {
+                return Err(MiniSwiftError::DuplicateVertex { position: vec![x, y] });
+            }
+            let (old_x, old_y) = (self.vertices[vertex_idx].x, self.vertices[vertex_idx].y);
+            let (n_vertices, ghost_vertices_offset) = (self.n_vertices, self.ghost_vertices_offset);
+            let moved = self.remove_vertex(vertex_idx)?;
+            let result = match self.insert_vertex(x, y) {
+                Ok(VertexInsertion::Inserted(_)) | Ok(VertexInsertion::Redundant(_)) => Ok(()),
+                Ok(VertexInsertion::Duplicate(_)) => Err(MiniSwiftError::DuplicateVertex { position: vec![x, y] }),
+                Err(error) => Err(error),
+            };
+            // Put the vertex back at its original position, which can only fail due to numerical
+            // trouble. The vertex then stays removed, with the bookkeeping left consistent by
+            // `remove_vertex`, rather than swapping or counting a vertex that is not there.
+            let restored = match result {
+                Ok(()) => Ok(()),
+                Err(_) => match self.insert_vertex(old_x, old_y) {
+                    Ok(VertexInsertion::Inserted(_)) | Ok(VertexInsertion::Redundant(_)) => Ok(()),
+                    Ok(VertexInsertion::Duplicate(_)) => Err(MiniSwiftError::DuplicateVertex { position: vec![old_x, old_y] }),
+                    Err(error) => Err(error),
+                },
+            };
+            restored?;
+            // The reinserted vertex was appended, restore the original ordering
+            if let Some(moved_idx) = moved {
+                self.swap_vertices(vertex_idx, moved_idx);
+            }
+            // The number of vertices is unchanged
+            self.n_vertices = n_vertices;
+            self.ghost_vertices_offset = ghost_vertices_offset;
+            result?;
+        }
+
+        self.consistency_check();
+
+        Ok(())
+    }
+
+    /// Moves all non-ghost vertices to new positions, see `move_vertex`. The vertices are moved
+    /// in the order of their new positions along a Hilbert curve, to keep the point location
+    /// walks of reinserted vertices short. Vertices whose new position is still occupied by a
+    /// vertex that has not moved yet are moved afterwards, if that does not resolve all conflicts
+    /// (e.g. when vertices swap places), the triangulation is rebuilt from the new positions.
+    /// Fails without changing the triangulation if there is not exactly one new position per
+    /// vertex, or if the new positions lie outside the triangulation or contain duplicates.
+    pub fn update_positions(&mut self, points_x: &[f64], points_y: &[f64]) -> Result<(), MiniSwiftError> {
+        for n_positions in [points_x.len(), points_y.len()] {
+            if n_positions != self.vertices.len() - 3 {
+                return Err(MiniSwiftError::LengthMismatch { expected: self.vertices.len() - 3, found: n_positions });
+            }
+        }
+        if self.has_ghost_vertices() {
+            return Err(MiniSwiftError::GhostVerticesPresent);
+        }
+        if self.is_weighted {
+            return Err(MiniSwiftError::WeightedVerticesPresent);
+        }
+        // The region covered by the triangulation is the triangle of the three dummy vertices
+        let corners: Vec<(f64, f64)> = self.vertices[..3].iter().map(|v| (v.x_scaled, v.y_scaled)).collect();
+        for (&x, &y) in points_x.iter().zip(points_y) {
+            let (x_scaled, y_scaled) = (x * self.inverse_side, y * self.inverse_side);
+            let covered = x.is_finite() && y.is_finite() && (0..3).all(|i| {
+                let ((ax, ay), (bx, by)) = (corners[i], corners[(i + 1) % 3]);
+                orient_2d(ax, ay, bx, by, x_scaled, y_scaled) > 0.
+            });
+            if !covered {
+                return Err(MiniSwiftError::PointOutsideDomain { position: vec![x, y] });
+            }
+        }
+        let mut sorted: Vec<usize> = (0..points_x.len()).collect();
+        sorted.sort_unstable_by(|&i, &j| points_x[i].total_cmp(&points_x[j]).then(points_y[i].total_cmp(&points_y[j])));
+        for pair in sorted.windows(2) {
+            if points_x[pair[0]] == points_x[pair[1]] && points_y[pair[0]] == points_y[pair[1]] {
+                return Err(MiniSwiftError::DuplicateVertex { position: vec![points_x[pair[0]], points_y[pair[0]]] });
+            }
+        }
+
+        let mut pending: Vec<usize> = insertion_order(points_x.len(), |i| hilbert_key(points_x[i], points_y[i], &self.domain),
+                                                      InsertionOrder::Hilbert);
+        while !pending.is_empty() {
+            let n_pending = pending.len();
+            let mut blocked = Vec::new();
+            for i in pending {
+                match self.move_vertex(i + 3, points_x[i], points_y[i]) {
+                    Ok(()) => {}
+                    // the new position is the old position of a vertex that has not moved yet
+                    Err(MiniSwiftError::DuplicateVertex { .. }) => blocked.push(i),
+                    Err(error) => return Err(error),
+                }
+            }
+            if blocked.len() == n_pending {
+                return self.rebuild(points_x, points_y);
+            }
+            pending = blocked;
+        }
+        Ok(())
+    }
+
+    /// Replaces this triangulation by the triangulation of the given points, keeping the check
+    /// level and whether it is finalized.
+    fn rebuild(&mut self, points_x: &[f64], points_y: &[f64]) -> Result<(), MiniSwiftError> {
+        let mut rebuilt = DelaunayTriangulation2D::from_points(points_x, points_y, self.domain, false)?;
+        rebuilt.ghost_vertices_offset = self.ghost_vertices_offset;
+        rebuilt.set_check_level(self.check_level);
+        rebuilt.consistency_check();
+        *self = rebuilt;
+        Ok(())
+    }
+
+    /// Exchanges the indices of two vertices.
+    fn swap_vertices(&mut self, vertex_i: usize, vertex_j: usize) {
+        let mut triangle_indices = self.get_triangle_idx_around_vertex(vertex_i);
+        triangle_indices.extend(self.get_triangle_idx_around_vertex(vertex_j));
+        triangle_indices.sort_unstable();
+        triangle_indices.dedup();
+        self.touch_triangles(triangle_indices.iter().map(|&triangle_idx| triangle_idx as i32));
+        for triangle_idx in triangle_indices {
+            for v in self.triangles[triangle_idx].vertices.iter_mut() {
+                if *v == vertex_i as i32 {
+                    *v = vertex_j as i32;
+                } else if *v == vertex_j as i32 {
+                    *v = vertex_i as i32;
+                }
+            }
+        }
+        self.vertices.swap(vertex_i, vertex_j);
+    }
+
+    /// Relabels the vertices such that they are stored in the order of the input points.
+    /// `vertex_idx[i]` is the current index of the vertex of the i-th input point, or `None` if
+    /// that point was a duplicate.
+    fn restore_input_order(&mut self, vertex_idx: &[Option<usize>]) {
+        // new index of the vertex currently at each index, the encapsulating vertices stay in place
+        let mut new_idx: Vec<i32> = (0..self.vertices.len() as i32).collect();
+        for (new, old) in vertex_idx.iter().flatten().enumerate() {
+            new_idx[*old] = new as i32 + 3;
+        }
+        let mut vertices: Vec<DelaunayVertex2D> = Vec::with_capacity(self.vertices.capacity());
+        vertices.resize_with(self.vertices.len(), DelaunayVertex2D::default);
+        for (old, vertex) in self.vertices.drain(..).enumerate() {
+            vertices[new_idx[old] as usize] = vertex;
+        }
+        self.vertices = vertices;
+        for triangle in self.triangles.iter_mut() {
+            for v in triangle.vertices.iter_mut() {
+                if *v >= 0 {
+                    *v = new_idx[*v as usize];
+                }
+            }
+        }
+        self.current_vertex_idx = new_idx[self.current_vertex_idx as usize];
+        self.consistency_check();
+    }
+
+    pub(super) fn has_ghost_vertices(&self) -> bool {
+        self.has_boundary_ghosts || self.ghost_vertices_offset.is_some_and(|offset| self.vertices.len() > offset)
+    }
+
+    /// Moves the triangle at index `from` to index `to`, updating all references to it.
+    fn move_triangle(&mut self, from: usize, to: usize) {
+        let triangle = std::mem::take(&mut self.triangles[from]);
+        for i in 0..3 {
+            let neighbour_idx = triangle.neighbours[i];
+            if neighbour_idx >= 0 {
+                self.triangles[neighbour_idx as usize].neighbours[triangle.index_in_neighbours[i] as usize] = to as i32;
+            }
+            let vertex_idx = triangle.vertices[i];
+            if vertex_idx >= 0 && self.vertices[vertex_idx as usize].triangle == from as i32 {
+                self.vertices[vertex_idx as usize].update_triangle(to as i32, i as i8);
+            }
+        }
+        self.triangles[to] = triangle;
+        self.touch_triangles([to as i32]);
+    }
+
+    /// Records triangles to be verified by the next local consistency check.
+    fn touch_triangles(&mut self, triangle_indices: impl IntoIterator<Item=i32>) {
+        if self.check_level == CheckLevel::Local {
+            self.touched_triangles.extend(triangle_indices);
+        }
+    }
+
+    fn new_vertex(&mut self, x: f64, y: f64, weight: f64) -> i32 {
         // TODO possibly manage the size of self.vertices more intelligently.
         let (x_scaled, y_scaled) = (x * self.inverse_side, y* self.inverse_side);
+        // the weights have the dimension of a squared length
+        let weight_scaled = weight * self.inverse_side * self.inverse_side;
         self.current_vertex_idx = self.vertices.len() as i32;
-        self.vertices.push(DelaunayVertex2D{ x, y, x_scaled, y_scaled, ..DelaunayVertex2D::default()});
+        self.vertices.push(DelaunayVertex2D{ x, y, x_scaled, y_scaled, weight, weight_scaled, ..DelaunayVertex2D::default()});
 
         self.current_vertex_idx
     }
 
-    fn make_periodic(&mut self) {
+    /// Adds ghost vertices around the domain according to its boundary conditions: periodic
+    /// copies of the vertices near the opposite side, or copies of the vertices near a reflective
+    /// or open side mirrored around that side. The latter requires those vertices to lie strictly
+    /// inside the domain.
+    fn add_boundary_ghost_vertices(&mut self) -> Result<(), MiniSwiftError> {
         use ordered_float::OrderedFloat;
-        assert!(!self.is_periodic, "Delaunay triangulation is already periodic!");
-        let arg_sort_x = permutation::sort_by_key(&self.vertices[3..],
-                                                  |v| OrderedFloat(v.x_scaled));
-        let arg_sort_y = permutation::sort_by_key(&self.vertices[3..],
-                                                  |v| OrderedFloat(v.y_scaled));
-        let arg_sort_xpy = permutation::sort_by_key(&self.vertices[3..],
-                                                  |v| OrderedFloat(v.x_scaled + v.y_scaled));
-        let arg_sort_xmy = permutation::sort_by_key(&self.vertices[3..],
-                                                  |v| OrderedFloat(v.x_scaled - v.y_scaled));
+        if self.has_boundary_ghosts {
+            return Err(MiniSwiftError::AlreadyPeriodic);
+        }
+        for vertex_idx in 3..self.n_vertices + 3 {
+            for &direction in [Direction::RIGHT, Direction::LEFT, Direction::UP, Direction::DOWN].iter() {
+                let (distance, origin) = self.boundary_ghost(vertex_idx, direction);
+                if distance <= 0. && origin.mirrored.iter().any(|&mirrored| mirrored) {
+                    let vertex = &self.vertices[vertex_idx];
+                    return Err(MiniSwiftError::PointOutsideDomain { position: vec![vertex.x, vertex.y] });
+                }
+            }
+        }
+        let directions = [
+            Direction::RIGHT, Direction::LEFT, Direction::UP, Direction::DOWN,
+            Direction::RIGHT_UP, Direction::LEFT_DOWN, Direction::RIGHT_DOWN, Direction::LEFT_UP
+        ];
+        let vertex_indices: Vec<usize> = (3..self.n_vertices + 3).collect();
+        let orderings: Vec<Permutation> = directions.iter()
+            .map(|&direction| permutation::sort_by_key(&vertex_indices[..],
+                                                       |&i| OrderedFloat(self.boundary_ghost(i, direction).0)))
+            .collect();
 
         // initial value of search radius: the average inter-particle distance for uniform distribution of particles
         let mut search_radius = self.domain.sides()[0] / f64::sqrt(self.n_vertices as f64);
@@ -262,109 +936,99 @@ impl DelaunayTriangulation2D {
         let mut n_vertices_larger_search_radius = self.n_vertices;
 
         while n_vertices_larger_search_radius > 0 {
-            self.add_ghost_vertices(search_radius, old_search_radius, &arg_sort_x, &arg_sort_y, &arg_sort_xpy, &arg_sort_xmy);
+            for (&direction, ordering) in directions.iter().zip(orderings.iter()) {
+                self.add_ghost_vertices_in_direction(ordering, direction, old_search_radius, search_radius)?;
+            }
             self.update_vertex_search_radii(search_radius, n_vertices_larger_search_radius);
             n_vertices_larger_search_radius = self.vertices[3..self.n_vertices+3].iter()
-                .filter(|v| (*v).search_radius > search_radius).count();
+                .filter(|v| v.search_radius > search_radius).count();
             let new_search_radius = 1.5 * search_radius;
             old_search_radius = search_radius;
             search_radius = new_search_radius;
         }
 
-        self.is_periodic = true;
+        self.has_boundary_ghosts = true;
+        Ok(())
     }
 
-    fn add_ghost_vertices(&mut self, search_radius: f64, old_search_radius: f64,
-                          arg_sort_x: &Permutation, arg_sort_y: &Permutation,
-                          arg_sort_xpy: &Permutation, arg_sort_xmy: &Permutation) {
-        let sides = self.domain.sides();
-        // add ghost particles in positive x direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_x, |v| v.x, 1,
-            old_search_radius, search_radius, |v| (v.x + sides[0], v.y)
-        );
-        // add ghost particles in negative x direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_x, |v| sides[0] - v.x, -1,
-            old_search_radius, search_radius, |v| (v.x - sides[0], v.y)
-        );
-        // add ghost particles in positive y direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_y, |v| v.y, 1,
-            old_search_radius, search_radius, |v| (v.x, v.y + sides[1])
-        );
-        // add ghost particles in negative y direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_y, |v| sides[1] - v.y, -1,
-            old_search_radius, search_radius, |v| (v.x, v.y - sides[1])
-        );
-        // In order to search for all particles up to r away from the corner in the diagonal
-        // directions, we must compensate with a factor 1/sqrt(2); without it we could miss some
-        // particles between sqrt(2)/2*r and r away from the corner...
-        let sqrt2 = f64::sqrt(2.);
-        // add ghost particles in positive xpy direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_xpy, |v| (v.x + v.y) / sqrt2, 1,
-            old_search_radius, search_radius, |v| (v.x + sides[0], v.y + sides[1])
-        );
-        // add ghost particles in negative xpy direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_xpy, |v| ((sides[0] - v.x) + (sides[1] - v.y)) / sqrt2, -1,
-            old_search_radius, search_radius, |v| (v.x - sides[0], v.y - sides[1])
-        );
-        // add ghost particles in positive xmy direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_xmy, |v| (v.x + (sides[1] - v.y)) / sqrt2, 1,
-            old_search_radius, search_radius, |v| (v.x + sides[0], v.y - sides[1])
-        );
-        // add ghost particles in negative xmy direction
-        self.add_ghost_vertices_along_axis(
-            &arg_sort_xmy, |v| ((sides[0] - v.x) + v.y) / sqrt2, -1,
-            old_search_radius, search_radius, |v| (v.x - sides[0], v.y + sides[1])
-        );
+    /// Adds the ghost vertices in the given direction of the domain whose distance to the domain
+    /// (as given by `boundary_ghost`) lies between `old_search_radius` and `search_radius`.
+    /// `ordering` sorts the vertices by that distance.
+    fn add_ghost_vertices_in_direction(&mut self,
+                                       ordering: &Permutation,
+                                       direction: Direction,
+                                       old_search_radius: f64,
+                                       search_radius: f64) -> Result<(), MiniSwiftError> {
+        for i in 0..self.n_vertices {
+            let vertex_idx = ordering.apply_inv_idx(i) + 3;
+            let (distance, origin) = self.boundary_ghost(vertex_idx, direction);
+            if distance >= search_radius { break; }
+            if old_search_radius <= distance {
+                let vertex = &self.vertices[vertex_idx];
+                let [x, y] = origin.ghost_position([vertex.x, vertex.y]);
+                self.insert_vertex_with_origin(x, y, vertex.weight, origin)?;
+            }
+        }
+        Ok(())
     }
 
-    fn add_ghost_vertices_along_axis(&mut self,
-                                     ordering: &Permutation,
-                                     comp_f: impl Fn(&DelaunayVertex2D)->f64,
-                                     direction: i8,
-                                     old_search_radius: f64,
-                                     search_radius: f64,
-                                     insert_f: impl Fn(&DelaunayVertex2D)->(f64, f64)) {
-        let mut i: usize;
-        if direction == 1 { i = 0; }
-        else if direction == -1 { i = self.n_vertices - 1; }
-        else { panic!("The only alowed values for direction are 1 or -1!"); }
-
-        let mut vertex = &self.vertices[ordering.apply_inv_idx(i) + 3];
-        let mut comp_value = comp_f(vertex);
-        while comp_value < search_radius {
-            if old_search_radius <= comp_value {
-                let (x, y) = insert_f(vertex);
-                self.insert_vertex(x, y);
-            }
-            if direction == 1 {
-                if i == self.n_vertices - 1 {break;}
-                i += 1;
-            } else {
-                if i == 0 {break;}
-                i -= 1;
-            }
-            vertex = &self.vertices[ordering.apply_inv_idx(i) + 3];
-            comp_value = comp_f(vertex);
+    /// Returns the origin of the ghost of the given vertex in the given direction of the domain,
+    /// together with the distance of that ghost to the domain, or rather a lower bound for it: for
+    /// the diagonal directions, we use the distance to the corner projected onto the diagonal, i.e.
+    /// the sum of the distances along both axes divided by sqrt(2).
+    fn boundary_ghost(&self, vertex_idx: usize, direction: Direction) -> (f64, GhostOrigin<TwoD>) {
+        let (anchor, sides) = (self.domain.anchor(), self.domain.sides());
+        let vertex = &self.vertices[vertex_idx];
+        let position = [vertex.x, vertex.y];
+        let mut origin: GhostOrigin<TwoD> = GhostOrigin::shifted(None, vertex_idx - 3, [0., 0.]);
+        let mut distance = 0.;
+        let mut n_axes = 0;
+        for (axis, &c) in direction.components()[..2].iter().enumerate() {
+            if c == 0 { continue; }
+            let upper = c > 0;
+            let (lower_side, upper_side) = (anchor[axis], anchor[axis] + sides[axis]);
+            let coordinate = position[axis];
+            let (side_distance, shift, mirrored) = match (self.domain.boundary_condition(axis, upper), upper) {
+                // copy of a vertex near the opposite side
+                (BoundaryCondition::Periodic, true) => (coordinate - lower_side, sides[axis], false),
+                (BoundaryCondition::Periodic, false) => (upper_side - coordinate, -sides[axis], false),
+                // mirror image of a vertex near this side
+                (_, true) => (upper_side - coordinate, 2. * upper_side, true),
+                (_, false) => (coordinate - lower_side, 2. * lower_side, true),
+            };
+            distance += side_distance;
+            origin.shift[axis] = shift;
+            origin.mirrored[axis] = mirrored;
+            n_axes += 1;
         }
+        (distance / f64::sqrt(n_axes as f64), origin)
     }
 
     pub fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
         let mut radii = Vec::<(usize, f64)>::with_capacity(previous_n_vertices_larger_radius);
         for (i, vertex) in self.vertices[3..self.n_vertices+3].iter().enumerate() {
             if vertex.search_radius < current_search_radius { continue; }
+            // redundant vertices have no triangles to be affected by new vertices
+            if vertex.is_redundant() {
+                radii.push((i+3, 0.));
+                continue;
+            }
             let mut max_radius: f64 = 0.;
             for triangle_idx in self.get_triangle_idx_around_vertex(i + 3) {
                 // any point within a circle of radius 2*current circumradius around current vertex
                 // CAN violate the delaunay criterion for the current triangle. In most cases
                 // however this rather is unlikely...
-                max_radius = max_radius.max(2. * self.triangles[triangle_idx].circumradius(self));
+                let radius = if self.is_weighted {
+                    // A vertex with weight w violates the regularity of the triangle if its
+                    // distance to the orthocenter is smaller than sqrt(R^2 + w), with R^2 the
+                    // power of the triangle's vertices w.r.t. the orthocenter.
+                    let distance = (self.triangles[triangle_idx].orthocenter(self) - Vertex2D{x: vertex.x, y: vertex.y}).norm();
+                    let power = distance * distance - vertex.weight;
+                    distance + (power + self.max_weight).max(0.).sqrt()
+                } else {
+                    2. * self.triangles[triangle_idx].circumradius(self)
+                };
+                max_radius = max_radius.max(radius);
             }
             radii.push((i+3, max_radius));
         }
@@ -373,6 +1037,27 @@ impl DelaunayTriangulation2D {
         }
     }
 
+    /// Locates the point (x, y) in the triangulation, walking from the triangle of the most recent
+    /// insertion. Points outside of the domain but inside the encapsulating triangle lie in a
+    /// triangle with one or more dummy vertices.
+    pub fn locate(&self, x: f64, y: f64) -> Result<PointLocation, MiniSwiftError> {
+        if !x.is_finite() || !y.is_finite() {
+            return Err(MiniSwiftError::PointOutsideDomain { position: vec![x, y] });
+        }
+        self.find_triangle_containing(x, y, self.current_triangle_idx)
+    }
+
+    /// The indices of the vertices of the triangle with the given index, in counterclockwise order
+    pub fn triangle_vertices(&self, triangle_idx: usize) -> [usize; 3] {
+        self.triangles[triangle_idx].vertices.map(|v| v as usize)
+    }
+
+    /// The number of triangles, including the three dummy triangles at indices 0, 1 and 2 which
+    /// border the convex hull of the triangulation.
+    pub fn n_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
     pub fn get_triangle_idx_around_vertex(&self, vertex_idx: usize) -> Vec::<usize> {
         let vertex = &self.vertices[vertex_idx];
         let start_triangle_idx_in_d = vertex.triangle;
@@ -405,6 +1090,9 @@ impl DelaunayTriangulation2D {
     }
 
     pub fn is_connected_to_non_dummy_non_ghost_vertex(&self, vertex_idx: usize) -> bool {
+        if self.vertices[vertex_idx].is_redundant() {
+            return false;
+        }
         if vertex_idx < self.n_vertices + 3 && vertex_idx > 2 {
             return true;
         }
@@ -442,6 +1130,8 @@ impl DelaunayTriangulation2D {
             };
         }
 
+        self.touch_triangles([at]);
+
         if v0 >= 0 {
             self.vertices[v0 as usize].update_triangle(at, 0);
         }
@@ -455,52 +1145,118 @@ impl DelaunayTriangulation2D {
         at
     }
 
-    fn find_triangle_containing_current_vertex(&self) -> i32 {
+    fn find_triangle_containing_current_vertex(&self) -> Result<PointLocation, MiniSwiftError> {
         let vertex = &self.vertices[self.current_vertex_idx as usize];
+        self.find_triangle_containing(vertex.x, vertex.y, self.current_triangle_idx)
+    }
+
+    /// Walks from the triangle with index `start_triangle_idx` to the triangle containing the
+    /// point (x, y).
+    fn find_triangle_containing(&self, x: f64, y: f64, start_triangle_idx: i32) -> Result<PointLocation, MiniSwiftError> {
+        let (x_scaled, y_scaled) = (x * self.inverse_side, y * self.inverse_side);
         let mut v0: &DelaunayVertex2D;
         let mut v1: &DelaunayVertex2D;
         let mut v2: &DelaunayVertex2D;
         let mut current_triangle: &DelaunayTriangle2D;
-        let mut current_triangle_idx = self.current_triangle_idx;
-        let mut found = false;
+        let mut current_triangle_idx = start_triangle_idx;
         let mut test0: f64;
         let mut test1: f64;
         let mut test2: f64;
+        let location_error = MiniSwiftError::LocationWalkFailure { position: vec![x, y] };
+        let outside_error = MiniSwiftError::PointOutsideDomain { position: vec![x, y] };
 
-        while !found {
+        // The walk through a Delaunay triangulation never visits a triangle twice
+        for _ in 0..self.triangles.len() {
             current_triangle = &self.triangles[current_triangle_idx as usize];
             v0 = &self.vertices[current_triangle.vertices[0] as usize];
             v1 = &self.vertices[current_triangle.vertices[1] as usize];
             v2 = &self.vertices[current_triangle.vertices[2] as usize];
 
-            test2 = orient_2d(v0.x_scaled, v0.y_scaled, v1.x_scaled, v1.y_scaled, vertex.x_scaled, vertex.y_scaled);
-            test0 = orient_2d(v1.x_scaled, v1.y_scaled, v2.x_scaled, v2.y_scaled, vertex.x_scaled, vertex.y_scaled);
-            test1 = orient_2d(v2.x_scaled, v2.y_scaled, v0.x_scaled, v0.y_scaled, vertex.x_scaled, vertex.y_scaled);
+            test2 = orient_2d(v0.x_scaled, v0.y_scaled, v1.x_scaled, v1.y_scaled, x_scaled, y_scaled);
+            test0 = orient_2d(v1.x_scaled, v1.y_scaled, v2.x_scaled, v2.y_scaled, x_scaled, y_scaled);
+            test1 = orient_2d(v2.x_scaled, v2.y_scaled, v0.x_scaled, v0.y_scaled, x_scaled, y_scaled);
 
-            if (test0 > 0.) && (test1 > 0.) && (test2 > 0.) {
-                found = true;
-            } else if (test0 <= 0.) && (test1 <= 0.) {
+            if (test0 < 0.) && (test1 < 0.) {
                 current_triangle_idx = random_choose(current_triangle.neighbours[0],
                                                      current_triangle.neighbours[1]);
-            } else if (test1 <= 0.) && (test2 <= 0.) {
+            } else if (test1 < 0.) && (test2 < 0.) {
                 current_triangle_idx = random_choose(current_triangle.neighbours[1],
                                                      current_triangle.neighbours[2]);
-            } else if (test0 <= 0.) && (test2 <= 0.) {
+            } else if (test0 < 0.) && (test2 < 0.) {
                 current_triangle_idx = random_choose(current_triangle.neighbours[0],
                                                      current_triangle.neighbours[2]);
-            } else if test0 <= 0. {
+            } else if test0 < 0. {
                 current_triangle_idx = current_triangle.neighbours[0];
-            } else if test1 <= 0.{
+            } else if test1 < 0. {
                 current_triangle_idx = current_triangle.neighbours[1];
-            } else if test2 <= 0.{
+            } else if test2 < 0. {
                 current_triangle_idx = current_triangle.neighbours[2];
             } else {
-                panic!("Error! This scenario should not be possible?")
+                // The point lies inside or on the boundary of the current triangle. Since the
+                // predicates are exact, a zero test means that the point lies exactly on the line
+                // through the corresponding edge.
+                let location = match (test0 == 0., test1 == 0., test2 == 0.) {
+                    (false, false, false) => PointLocation::InTriangle(current_triangle_idx),
+                    (true, false, false) => PointLocation::OnEdge(current_triangle_idx, 0),
+                    (false, true, false) => PointLocation::OnEdge(current_triangle_idx, 1),
+                    (false, false, true) => PointLocation::OnEdge(current_triangle_idx, 2),
+                    (false, true, true) => PointLocation::OnVertex(current_triangle.vertices[0]),
+                    (true, false, true) => PointLocation::OnVertex(current_triangle.vertices[1]),
+                    (true, true, false) => PointLocation::OnVertex(current_triangle.vertices[2]),
+                    (true, true, true) => return Err(location_error)
+                };
+                // Points on the boundary of the encapsulating triangle cannot be inserted
+                if let PointLocation::OnEdge(_, idx) = location {
+                    if current_triangle.neighbours[idx] < 3 {
+                        return Err(outside_error);
+                    }
+                }
+                return Ok(location);
             }
 
-            assert!(current_triangle_idx > 2, "Ended up with dummy triangle!");
+            if current_triangle_idx < 3 {
+                return Err(outside_error);
+            }
         }
-        current_triangle_idx
+        Err(location_error)
+    }
+
+    /// In-circle test for the vertices with indices a, b, c and d, see `in_circle_2d`, or the
+    /// power test (see `power_test_2d`) for weighted triangulations.
+    ///
+    /// Ties for cocircular vertices are broken deterministically by symbolically perturbing the
+    /// vertices (simulation of simplicity): the lifted coordinate x^2 + y^2 of the vertex of rank i
+    /// is raised by eps^i, for an infinitesimally small eps. Vertices are ranked lexicographically
+    /// by their coordinates, so that the perturbation does not depend on the insertion order or
+    /// on vertex indices. The sign of the test is then decided by the first nonvanishing term.
+    fn in_circle(&self, a: i32, b: i32, c: i32, d: i32) -> f64 {
+        let (va, vb, vc, vd) = (&self.vertices[a as usize], &self.vertices[b as usize],
+                                &self.vertices[c as usize], &self.vertices[d as usize]);
+        let test = if self.is_weighted {
+            power_test_2d(va.x_scaled, va.y_scaled, va.weight_scaled, vb.x_scaled, vb.y_scaled, vb.weight_scaled,
+                          vc.x_scaled, vc.y_scaled, vc.weight_scaled, vd.x_scaled, vd.y_scaled, vd.weight_scaled)
+        } else {
+            in_circle_2d(va.x_scaled, va.y_scaled, vb.x_scaled, vb.y_scaled,
+                         vc.x_scaled, vc.y_scaled, vd.x_scaled, vd.y_scaled)
+        };
+        if test != 0. {
+            return test;
+        }
+
+        let orient = |p: &DelaunayVertex2D, q: &DelaunayVertex2D, r: &DelaunayVertex2D| {
+            orient_2d(p.x_scaled, p.y_scaled, q.x_scaled, q.y_scaled, r.x_scaled, r.y_scaled)
+        };
+        // Partial derivatives of the in-circle test w.r.t. the lifted coordinate of each vertex.
+        let mut terms = [
+            (va, -orient(vb, vc, vd)),
+            (vb, orient(va, vc, vd)),
+            (vc, -orient(va, vb, vd)),
+            (vd, orient(va, vb, vc))
+        ];
+        terms.sort_by(|(v, _), (w, _)| {
+            v.x_scaled.total_cmp(&w.x_scaled).then(v.y_scaled.total_cmp(&w.y_scaled))
+        });
+        terms.iter().map(|&(_, term)| term).find(|&term| term != 0.).unwrap_or(0.)
     }
 
     fn fix_delaunayness(&mut self) {
@@ -511,37 +1267,141 @@ impl DelaunayTriangulation2D {
     }
 
     fn fix_triangle(&mut self, triangle_idx: i32) {
+        // skip triangles removed by a 3-1 flip
+        if self.triangles[triangle_idx as usize].vertices[0] < 0 {
+            return;
+        }
+        // the newly inserted vertex is always at index 2, so we only need to check the opposite edge
+        match self.fix_edge(triangle_idx, 2) {
+            Some(Flip::TwoToTwo(t0, t1)) => {
+                self.triangles_to_check.push_back(t0);
+                self.triangles_to_check.push_back(t1);
+            }
+            Some(Flip::ThreeToOne(t)) => self.triangles_to_check.push_back(t),
+            None => {}
+        }
+    }
+
+    /// Flips the edge of a triangle opposite of its vertex at `idx_in_triangle` if it violates the
+    /// Delaunay criterion (power criterion for weighted triangulations) and returns the resulting
+    /// triangles in that case.
+    ///
+    /// In a regular triangulation, the quadrilateral formed by both triangles is not necessarily
+    /// convex. If the vertex at its reflex corner has only three neighbours, it becomes redundant
+    /// and is removed with a 3-1 flip. Otherwise the edge is left as is: it is fixed by the flips
+    /// of other edges (Edelsbrunner and Shah, 1996).
+    fn fix_edge(&mut self, triangle_idx: i32, idx_in_triangle: usize) -> Option<Flip> {
         let triangle = &self.triangles[triangle_idx as usize];
-        let neighbour_idx = triangle.neighbours[2];
+        let neighbour_idx = triangle.neighbours[idx_in_triangle];
         if neighbour_idx < 3 {
-            return
+            return None
         }
 
-        let a = &self.vertices[triangle.vertices[0] as usize];
-        let b = &self.vertices[triangle.vertices[1] as usize];
-        let c = &self.vertices[triangle.vertices[2] as usize];
-
         let neighbour = &self.triangles[neighbour_idx as usize];
-        let d = &self.vertices[neighbour.vertices[triangle.index_in_neighbours[2] as usize] as usize];
+        let d = neighbour.vertices[triangle.index_in_neighbours[idx_in_triangle] as usize];
 
-        let test = in_circle_2d(a.x_scaled, a.y_scaled, b.x_scaled, b.y_scaled, c.x_scaled, c.y_scaled, d.x_scaled, d.y_scaled);
+        let test = self.in_circle(triangle.vertices[0], triangle.vertices[1], triangle.vertices[2], d);
 
-        if test < 0. {
-            self.flip_triangles(triangle_idx, neighbour_idx);
+        if test >= 0. {
+            return None;
+        }
+        if !self.is_weighted {
+            let (t0, t1) = self.flip_triangles(triangle_idx, idx_in_triangle);
+            return Some(Flip::TwoToTwo(t0, t1));
+        }
+        let (ia, ib) = ((idx_in_triangle + 1) % 3, (idx_in_triangle + 2) % 3);
+        let (a, b, c) = (triangle.vertices[ia], triangle.vertices[ib], triangle.vertices[idx_in_triangle]);
+        if self.orient(a, d, c) <= 0. {
+            self.flip_3_to_1(triangle_idx, idx_in_triangle, ia).map(Flip::ThreeToOne)
+        } else if self.orient(d, b, c) <= 0. {
+            self.flip_3_to_1(triangle_idx, idx_in_triangle, ib).map(Flip::ThreeToOne)
+        } else {
+            let (t0, t1) = self.flip_triangles(triangle_idx, idx_in_triangle);
+            Some(Flip::TwoToTwo(t0, t1))
         }
     }
 
-    fn flip_triangles(&mut self, triangle_idx: i32, neighbour_idx: i32) {
+    /// Orientation test for the vertices with indices a, b and c, see `orient_2d`.
+    fn orient(&self, a: i32, b: i32, c: i32) -> f64 {
+        let (va, vb, vc) = (&self.vertices[a as usize], &self.vertices[b as usize], &self.vertices[c as usize]);
+        orient_2d(va.x_scaled, va.y_scaled, vb.x_scaled, vb.y_scaled, vc.x_scaled, vc.y_scaled)
+    }
+
+    /// Removes the vertex r at `idx_of_removed` in the given triangle (a, b, c) from the
+    /// triangulation, where c is the vertex at `idx_in_triangle`, provided that r has exactly
+    /// three neighbours: the other vertex of the edge (a, b), c and the vertex d opposite of the
+    /// edge (a, b) in the neighbouring triangle. The three triangles around r are merged into the
+    /// triangle in which r is replaced by d, stored in the slot of the given triangle. The slots
+    /// of the other two are freed, see `compact_freed_triangles`. Returns the merged triangle.
+    fn flip_3_to_1(&mut self, triangle_idx: i32, idx_in_triangle: usize, idx_of_removed: usize) -> Option<i32> {
         let triangle = &self.triangles[triangle_idx as usize];
+        let removed = triangle.vertices[idx_of_removed];
+        let idx_of_other = 3 - idx_in_triangle - idx_of_removed;
+        let neighbour_idx = triangle.neighbours[idx_in_triangle];
+        let third_idx = triangle.neighbours[idx_of_other];
+        // the vertices of the encapsulating triangle are never removed
+        if removed < 3 || third_idx < 3 {
+            return None;
+        }
+        let d = self.triangles[neighbour_idx as usize].vertices[triangle.index_in_neighbours[idx_in_triangle] as usize];
+        if self.triangles[third_idx as usize].vertices[triangle.index_in_neighbours[idx_of_other] as usize] != d {
+            return None;
+        }
+
+        let mut vertices = triangle.vertices;
+        vertices[idx_of_removed] = d;
+        // The outside neighbours of the merged triangle: each edge of it is the edge opposite of
+        // the removed vertex in one of the three triangles.
+        let mut outside = [(triangle.neighbours[idx_of_removed], triangle.index_in_neighbours[idx_of_removed]); 3];
+        for (i, other_idx) in [(idx_in_triangle, neighbour_idx), (idx_of_other, third_idx)] {
+            let other = &self.triangles[other_idx as usize];
+            let idx_of_removed_in_other = other.vertices.iter().position(|&v| v == removed).unwrap();
+            outside[i] = (other.neighbours[idx_of_removed_in_other], other.index_in_neighbours[idx_of_removed_in_other]);
+        }
+
+        let merged = self.new_triangle_at(vertices[0], vertices[1], vertices[2], triangle_idx);
+        for (i, &(outside_idx, idx_in_outside)) in outside.iter().enumerate() {
+            self.triangles[merged as usize].update_neighbour(outside_idx, idx_in_outside, i as i8);
+            self.triangles[outside_idx as usize].update_neighbour(merged, i as i8, idx_in_outside);
+        }
+        for freed_idx in [neighbour_idx, third_idx] {
+            self.triangles[freed_idx as usize] = DelaunayTriangle2D::default();
+            self.freed_triangles.push(freed_idx);
+        }
+        self.vertices[removed as usize].update_triangle(-1, -1);
+        self.current_triangle_idx = merged;
+        Some(merged)
+    }
+
+    /// Restores the Delaunay criterion by flipping edges (Lawson's algorithm), starting from the
+    /// given queue of edges (triangle index and index of the vertex opposite of the edge).
+    fn fix_edges(&mut self, mut edges_to_check: VecDeque<(i32, usize)>) {
+        while let Some((triangle_idx, idx_in_triangle)) = edges_to_check.pop_front() {
+            match self.fix_edge(triangle_idx, idx_in_triangle) {
+                // check the 4 edges of the quadrilateral formed by both triangles
+                Some(Flip::TwoToTwo(t0, t1)) => edges_to_check.extend([(t0, 1), (t0, 2), (t1, 0), (t1, 2)]),
+                // only used for unweighted triangulations
+                Some(Flip::ThreeToOne(_)) => unreachable!("3-1 flip in a Delaunay triangulation!"),
+                None => {}
+            }
+        }
+    }
+
+    /// Flips the edge of the triangle (a, b, c) opposite of c, where c is its vertex at
+    /// `idx_in_triangle`, and its neighbour (f, b, a) into the triangles (a, f, c) and (f, b, c).
+    fn flip_triangles(&mut self, triangle_idx: i32, idx_in_triangle: usize) -> (i32, i32) {
+        let triangle = &self.triangles[triangle_idx as usize];
+        let neighbour_idx = triangle.neighbours[idx_in_triangle];
         let neighbour = &self.triangles[neighbour_idx as usize];
+        let (ia, ib) = ((idx_in_triangle + 1) % 3, (idx_in_triangle + 2) % 3);
 
         // read necessary info
-        let (ai, bi, ci) = (triangle.vertices[0], triangle.vertices[1], triangle.vertices[2]);
-        let (na, nb) = (triangle.neighbours[0], triangle.neighbours[1]);
-        let idx_in_na = triangle.index_in_neighbours[0];
-        let idx_in_nb = triangle.index_in_neighbours[1];
+        let (ai, bi, ci) = (triangle.vertices[ia], triangle.vertices[ib], triangle.vertices[idx_in_triangle]);
+        let (na, nb) = (triangle.neighbours[ia], triangle.neighbours[ib]);
+        let idx_in_na = triangle.index_in_neighbours[ia];
+        let idx_in_nb = triangle.index_in_neighbours[ib];
 
-        let idx_in_neighbour = triangle.index_in_neighbours[2] as usize;
+        let idx_in_neighbour = triangle.index_in_neighbours[idx_in_triangle] as usize;
         let fi = neighbour.vertices[idx_in_neighbour];
         let nd = neighbour.neighbours[(idx_in_neighbour + 1) % 3];
         let ne = neighbour.neighbours[(idx_in_neighbour + 2) % 3];
@@ -561,57 +1421,86 @@ impl DelaunayTriangulation2D {
         self.triangles[nb as usize].update_neighbour(t0, 1, idx_in_nb);
         self.triangles[ne as usize].update_neighbour(t1, 2, idx_in_ne);
 
-        self.triangles_to_check.push_back(t0);
-        self.triangles_to_check.push_back(t1);
-
         // update current_triangle
         self.current_triangle_idx = t1;
+
+        (t0, t1)
     }
 
-    fn consistency_check(&self) {
-        if ! cfg!(debug_assertions) {
-            return
-        }
-        // for each triangle check neighbour symmetry and check delaunay criterion
-        // for each vertex check triangle information
-        for (i, triangle) in self.triangles[3..].iter().enumerate() {
-            let triangle_idx = (i + 3) as i32;
-            for (j, &ngbr) in triangle.neighbours.iter().enumerate() {
-                // check neighbouring relation symmetry
-                let idx_in_ngbr = triangle.index_in_neighbours[j] as usize;
-                assert_eq!(triangle_idx,
-                           self.triangles[ngbr as usize].neighbours[idx_in_ngbr],
-                           "Testing neighbour symmetry");
-
-                // check overlapping vertices
-                let neighbour = &self.triangles[ngbr as usize];
-                let mut n_overlap = 0;
-                for i in triangle.vertices.iter() {
-                    if neighbour.vertices.contains(i) {
-                        n_overlap += 1;
+    /// Verifies the consistency of the triangulation according to its `CheckLevel`.
+    fn consistency_check(&mut self) {
+        match self.check_level {
+            CheckLevel::None => {}
+            CheckLevel::Local => {
+                let mut touched = std::mem::take(&mut self.touched_triangles);
+                touched.sort_unstable();
+                touched.dedup();
+                // Triangles might have been removed after being touched
+                touched.retain(|&triangle_idx| triangle_idx >= 3 && (triangle_idx as usize) < self.triangles.len());
+                for &triangle_idx in touched.iter() {
+                    self.check_triangle(triangle_idx as usize);
+                    for &vertex_idx in self.triangles[triangle_idx as usize].vertices.iter() {
+                        self.check_vertex(vertex_idx as usize);
                     }
                 }
-                assert_eq!(n_overlap, 2, "Neighbours should have exactly 2 vertices in common!");
+                // Reuse the allocation
+                touched.clear();
+                self.touched_triangles = touched;
+            }
+            CheckLevel::Full => {
+                for triangle_idx in 3..self.triangles.len() {
+                    self.check_triangle(triangle_idx);
+                }
+                for vertex_idx in 0..self.vertices.len() {
+                    self.check_vertex(vertex_idx);
+                }
+            }
+        }
+    }
 
-                // check Delaunay criterion
-                // skip dummy triangles
-                if ngbr < 3 {
-                    continue;
+    /// Checks that a (non dummy) triangle is counterclockwise and not degenerate, its neighbour
+    /// relations and the Delaunay criterion with respect to its neighbours.
+    fn check_triangle(&self, triangle_idx: usize) {
+        let triangle = &self.triangles[triangle_idx];
+        let [v0, v1, v2] = triangle.vertices;
+        assert!(self.orient(v0, v1, v2) > 0., "Triangle {} has zero or negative area!", triangle_idx);
+        for (j, &ngbr) in triangle.neighbours.iter().enumerate() {
+            // check neighbouring relation symmetry
+            let idx_in_ngbr = triangle.index_in_neighbours[j] as usize;
+            assert_eq!(triangle_idx as i32,
+                       self.triangles[ngbr as usize].neighbours[idx_in_ngbr],
+                       "Testing neighbour symmetry");
+
+            // check overlapping vertices
+            let neighbour = &self.triangles[ngbr as usize];
+            let mut n_overlap = 0;
+            for i in triangle.vertices.iter() {
+                if neighbour.vertices.contains(i) {
+                    n_overlap += 1;
                 }
-                let (a, b, c) = (&self.vertices[triangle.vertices[0] as usize],
-                                 &self.vertices[triangle.vertices[1] as usize],
-                                 &self.vertices[triangle.vertices[2] as usize]);
-                let d = &self.vertices[neighbour.vertices[idx_in_ngbr] as usize];
-                let in_circle = in_circle_2d(a.x_scaled, a.y_scaled, b.x_scaled, b.y_scaled, c.x_scaled, c.y_scaled, d.x_scaled, d.y_scaled);
-                assert!(in_circle >= 0., "in_circle test gave {:?}", in_circle)
             }
+            assert_eq!(n_overlap, 2, "Neighbours should have exactly 2 vertices in common!");
+
+            // check Delaunay criterion
+            // skip dummy triangles
+            if ngbr < 3 {
+                continue;
+            }
+            let d = neighbour.vertices[idx_in_ngbr];
+            let in_circle = self.in_circle(triangle.vertices[0], triangle.vertices[1], triangle.vertices[2], d);
+            assert!(in_circle >= 0., "in_circle test gave {:?}", in_circle)
         }
-        for (i, vertex) in self.vertices.iter().enumerate() {
-            assert_eq!(i as i32,
-                       self.triangles[vertex.triangle as usize].vertices[vertex.index_in_triangle as usize],
-                       "Testing vertex-triangle correspondence");
+    }
+
+    /// Checks that the triangle stored with a vertex contains that vertex.
+    fn check_vertex(&self, vertex_idx: usize) {
+        let vertex = &self.vertices[vertex_idx];
+        if vertex.is_redundant() {
+            return;
         }
-        // println!("Consistency checks passed!");
+        assert_eq!(vertex_idx as i32,
+                   self.triangles[vertex.triangle as usize].vertices[vertex.index_in_triangle as usize],
+                   "Testing vertex-triangle correspondence");
     }
 
     pub fn to_str(&self) -> String {
@@ -630,4 +1519,43 @@ impl DelaunayTriangulation2D {
     pub fn to_file(&self, filename: &str) {
         fs::write(filename, self.to_str()).expect("Unable to write to file!");
     }
-}
\ No newline at end of file
+}
+
+
+impl Tessellation<TwoD> for DelaunayTriangulation2D {
+    fn new(domain: SimulationDomain2D, vertex_size: usize, simplex_size: usize) -> Self {
+        DelaunayTriangulation2D::new(domain, vertex_size, simplex_size)
+    }
+
+    fn insert_vertex(&mut self, position: [f64; 2]) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTriangulation2D::insert_vertex(self, position[0], position[1])
+    }
+
+    fn insert_ghost_vertex(&mut self, position: [f64; 2], origin: GhostOrigin<TwoD>) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTriangulation2D::insert_ghost_vertex(self, position[0], position[1], origin)
+    }
+
+    fn ghost_origins(&self) -> &[GhostOrigin<TwoD>] {
+        &self.ghost_origins
+    }
+
+    fn finalize(&mut self) -> Result<(), MiniSwiftError> {
+        DelaunayTriangulation2D::finalize(self)
+    }
+
+    fn n_vertices(&self) -> usize {
+        self.n_vertices
+    }
+
+    fn search_radius(&self, vertex_idx: usize) -> f64 {
+        self.vertices[vertex_idx + 3].search_radius
+    }
+
+    fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
+        DelaunayTriangulation2D::update_vertex_search_radii(self, current_search_radius, previous_n_vertices_larger_radius)
+    }
+
+    fn to_file(&self, filename: &str) {
+        DelaunayTriangulation2D::to_file(self, filename)
+    }
+}
@@ -0,0 +1,815 @@
+use crate::simulation_domain_3d::SimulationDomain3D;
+use crate::tessellations::geometry::{orient_3d, in_sphere_3d, circumcenter_3d, circumradius_3d};
+use super::{Vertex3D, Tessellation, GhostOrigin, ThreeD};
+use super::delaunay2d::{CheckLevel, VertexInsertion};
+use super::spatial_sort::{InsertionOrder, insertion_order, hilbert_key_3d};
+use crate::error::MiniSwiftError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+
+
+#[derive(Debug)]
+pub struct DelaunayVertex3D {
+    pub(super) x: f64,
+    pub(super) y: f64,
+    pub(super) z: f64,
+    pub(super) x_scaled: f64,
+    pub(super) y_scaled: f64,
+    pub(super) z_scaled: f64,
+    pub(super) tetrahedron: i32,
+    pub(super) index_in_tetrahedron: i8,
+    pub search_radius: f64
+}
+
+impl Default for DelaunayVertex3D {
+    fn default() -> DelaunayVertex3D {
+        DelaunayVertex3D {
+            x: f64::NAN,
+            y: f64::NAN,
+            z: f64::NAN,
+            x_scaled: f64::NAN,
+            y_scaled: f64::NAN,
+            z_scaled: f64::NAN,
+            tetrahedron: -1,
+            index_in_tetrahedron: -1,
+            search_radius: f64::INFINITY
+        }
+    }
+}
+
+impl DelaunayVertex3D {
+    fn update_tetrahedron(&mut self, tetrahedron: i32, tetrahedron_index: i8) {
+        self.tetrahedron = tetrahedron;
+        self.index_in_tetrahedron = tetrahedron_index;
+    }
+
+    pub(super) fn as_vertex3d(&self) -> Vertex3D {
+        Vertex3D{x: self.x, y: self.y, z: self.z}
+    }
+}
+
+
+/// The location of a point with respect to the tetrahedra of a `DelaunayTetrahedralization3D`.
+enum PointLocation {
+    /// The point lies inside or on the boundary of the tetrahedron with the given index.
+    InTetrahedron(i32),
+    /// The point coincides with the vertex with the given index.
+    OnVertex(i32)
+}
+
+
+/// A positively oriented tetrahedron (see `orient_3d`). The neighbour at index i lies across the
+/// face opposite of the vertex at index i.
+#[derive(Debug)]
+pub(super) struct DelaunayTetrahedron3D {
+    pub(super) vertices: [i32; 4],
+    pub(super) neighbours: [i32; 4],
+    pub(super) index_in_neighbours: [i8; 4]
+}
+
+impl Default for DelaunayTetrahedron3D {
+    fn default() -> DelaunayTetrahedron3D {
+        DelaunayTetrahedron3D {
+            vertices: [-1, -1, -1, -1],
+            neighbours: [-1, -1, -1, -1],
+            index_in_neighbours: [-1, -1, -1, -1]
+        }
+    }
+}
+
+impl DelaunayTetrahedron3D {
+    fn update_neighbour(&mut self, n: i32, idx_in_n: i8, i: i8) {
+        self.neighbours[i as usize] = n;
+        self.index_in_neighbours[i as usize] = idx_in_n;
+    }
+
+    pub(super) fn circumcenter(&self, tetrahedralization: &DelaunayTetrahedralization3D) -> Vertex3D {
+        let [a, b, c, d] = self.vertices.map(|v| tetrahedralization.vertices[v as usize].as_vertex3d());
+        circumcenter_3d(a, b, c, d)
+    }
+
+    fn circumradius(&self, tetrahedralization: &DelaunayTetrahedralization3D) -> f64 {
+        let [a, b, c, d] = self.vertices.map(|v| tetrahedralization.vertices[v as usize].as_vertex3d());
+        circumradius_3d(a, b, c, d)
+    }
+}
+
+
+/// Delaunay tetrahedralization constructed with the Bowyer-Watson algorithm.
+///
+/// The layout mirrors `DelaunayTriangulation2D`: vertices 0 to 3 are the vertices of a large
+/// tetrahedron encapsulating the domain, tetrahedra 0 to 3 are dummy tetrahedra outside of it
+/// (they contain the vertex -1), the actual vertices start at index 4 and are followed by the
+/// ghost vertices.
+#[derive(Debug, Default)]
+pub struct DelaunayTetrahedralization3D {
+    pub vertices: Vec<DelaunayVertex3D>,
+    pub(super) tetrahedra: Vec<DelaunayTetrahedron3D>,
+    pub(super) domain: SimulationDomain3D,
+    pub(super) is_periodic: bool,
+    pub n_vertices: usize,
+    ghost_origins: Vec<GhostOrigin<ThreeD>>,
+    ghost_vertices_offset: Option<usize>,
+    anchor: [f64; 3],
+    side: f64,
+    inverse_side: f64,
+    current_tetrahedron_idx: i32,
+    current_vertex_idx: i32,
+    check_level: CheckLevel,
+    touched_tetrahedra: Vec<i32>,
+    marks: Vec<u32>,
+    mark_stamp: u32
+}
+
+impl DelaunayTetrahedralization3D {
+    pub fn new(domain: SimulationDomain3D, vertex_size: usize, tetrahedron_size: usize) -> DelaunayTetrahedralization3D {
+        let mut tetrahedralization = DelaunayTetrahedralization3D{
+            vertices: Vec::with_capacity(vertex_size + 4),
+            tetrahedra: Vec::with_capacity(tetrahedron_size + 4),
+            ..DelaunayTetrahedralization3D::default()
+        };
+
+        // Like in 2D, the encapsulating tetrahedron is large enough such that ghost particles
+        // certainly fall strictly inside it.
+        let sides = domain.sides();
+        tetrahedralization.anchor = [
+            domain.anchor()[0] - 2. * sides[0],
+            domain.anchor()[1] - 2. * sides[1],
+            domain.anchor()[2] - 2. * sides[2]
+        ];
+        tetrahedralization.side = 20. * sides[0].max(sides[1]).max(sides[2]);
+        tetrahedralization.inverse_side = 1. / tetrahedralization.side;
+        tetrahedralization.domain = domain;
+
+        // Create the vertices of the tetrahedron that encapsulates the entire domain
+        let [ax, ay, az] = tetrahedralization.anchor;
+        let side = tetrahedralization.side;
+        let v0 = tetrahedralization.new_vertex(ax, ay, az);
+        let v1 = tetrahedralization.new_vertex(ax + side, ay, az);
+        let v2 = tetrahedralization.new_vertex(ax, ay + side, az);
+        let v3 = tetrahedralization.new_vertex(ax, ay, az + side);
+        let vertices = [v0, v1, v2, v3];
+
+        // Create the 4 dummy tetrahedra, the dummy at index i replaces the vertex at index i of
+        // the first tetrahedron by -1.
+        for i in 0..4 {
+            let mut dummy_vertices = vertices;
+            dummy_vertices[i] = -1;
+            tetrahedralization.new_tetrahedron(dummy_vertices);
+        }
+        let first_tetrahedron = tetrahedralization.new_tetrahedron(vertices);
+        // Set neighbour relations: the first tetrahedron has the 4 dummies as neighbours. Each
+        // dummy has the first tetrahedron opposite of its vertex -1 and shares the faces
+        // containing -1 with the other dummies.
+        for i in 0..4 {
+            tetrahedralization.tetrahedra[first_tetrahedron as usize].update_neighbour(i as i32, i as i8, i as i8);
+            tetrahedralization.tetrahedra[i].update_neighbour(first_tetrahedron, i as i8, i as i8);
+            for j in 0..4 {
+                if j != i {
+                    tetrahedralization.tetrahedra[i].update_neighbour(j as i32, i as i8, j as i8);
+                }
+            }
+        }
+
+        tetrahedralization.consistency_check();
+
+        tetrahedralization.current_tetrahedron_idx = first_tetrahedron;
+        tetrahedralization
+    }
+
+    /// Sets how thoroughly the consistency of the tetrahedralization is verified after every
+    /// modification, see `CheckLevel`.
+    pub fn set_check_level(&mut self, check_level: CheckLevel) {
+        self.check_level = check_level;
+        self.touched_tetrahedra.clear();
+    }
+
+    /// Constructs the Delaunay tetrahedralization of the given points, inserting them along a
+    /// Hilbert curve.
+    pub fn from_points(points_x: &[f64],
+                       points_y: &[f64],
+                       points_z: &[f64],
+                       simulation_domain: SimulationDomain3D,
+                       make_periodic: bool) -> Result<DelaunayTetrahedralization3D, MiniSwiftError> {
+        DelaunayTetrahedralization3D::from_points_with_order(points_x, points_y, points_z, simulation_domain,
+                                                             make_periodic, InsertionOrder::Hilbert)
+    }
+
+    /// Constructs the Delaunay tetrahedralization of the given points, inserting them in the given
+    /// order. Like in 2D, the vertices are stored in the order of the input points (skipping
+    /// duplicates).
+    pub fn from_points_with_order(points_x: &[f64],
+                                  points_y: &[f64],
+                                  points_z: &[f64],
+                                  simulation_domain: SimulationDomain3D,
+                                  make_periodic: bool,
+                                  order: InsertionOrder) -> Result<DelaunayTetrahedralization3D, MiniSwiftError> {
+        for n_coordinates in [points_y.len(), points_z.len()] {
+            if n_coordinates != points_x.len() {
+                return Err(MiniSwiftError::LengthMismatch { expected: points_x.len(), found: n_coordinates });
+            }
+        }
+        let mut d = DelaunayTetrahedralization3D::new(simulation_domain, points_x.len(), 7 * points_x.len());
+        // duplicate points are not inserted, the vertex is assigned to the first of them.
+        let mut vertex_idx = vec![None; points_x.len()];
+        let mut point_idx = Vec::with_capacity(points_x.len());
+        let key = |i: usize| hilbert_key_3d(points_x[i], points_y[i], points_z[i], &simulation_domain);
+        for i in insertion_order(points_x.len(), key, order) {
+            match d.insert_vertex(points_x[i], points_y[i], points_z[i])? {
+                VertexInsertion::Inserted(idx) | VertexInsertion::Redundant(idx) => {
+                    vertex_idx[i] = Some(idx);
+                    point_idx.push(i);
+                }
+                VertexInsertion::Duplicate(idx) if idx >= 4 => {
+                    let j = point_idx[idx - 4];
+                    if i < j {
+                        vertex_idx[i] = vertex_idx[j].take();
+                        point_idx[idx - 4] = i;
+                    }
+                }
+                VertexInsertion::Duplicate(_) => {}
+            }
+        }
+        if order != InsertionOrder::Input {
+            d.restore_input_order(&vertex_idx);
+        }
+        d.n_vertices = d.vertices.len() - 4;
+        if make_periodic {
+            d.make_periodic()?;
+        }
+        Ok(d)
+    }
+
+    pub fn finalize(&mut self) -> Result<(), MiniSwiftError> {
+        match self.ghost_vertices_offset{
+            Some(_) => Err(MiniSwiftError::AlreadyFinalized),
+            None => {
+                self.n_vertices = self.vertices.len() - 4;
+                self.ghost_vertices_offset = Some(self.vertices.len());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn insert_ghost_vertex(&mut self, x: f64, y: f64, z: f64, origin: GhostOrigin<ThreeD>) -> Result<VertexInsertion, MiniSwiftError> {
+        if self.ghost_vertices_offset.is_none() {
+            return Err(MiniSwiftError::NotFinalized);
+        }
+        self.insert_vertex_with_origin(x, y, z, origin)
+    }
+
+    /// Inserts a ghost vertex and records where it comes from
+    fn insert_vertex_with_origin(&mut self, x: f64, y: f64, z: f64, origin: GhostOrigin<ThreeD>) -> Result<VertexInsertion, MiniSwiftError> {
+        let insertion = self.insert_vertex(x, y, z)?;
+        if let VertexInsertion::Inserted(_) = insertion {
+            self.ghost_origins.push(origin);
+        }
+        Ok(insertion)
+    }
+
+    /// Inserts a new vertex at (x, y, z) using the Bowyer-Watson algorithm: all tetrahedra whose
+    /// circumsphere contains the new vertex are removed and the resulting cavity is filled with
+    /// tetrahedra connecting its boundary faces to the new vertex. Points coinciding with an
+    /// existing vertex are rejected.
+    pub fn insert_vertex(&mut self, x: f64, y: f64, z: f64) -> Result<VertexInsertion, MiniSwiftError> {
+        if !x.is_finite() || !y.is_finite() || !z.is_finite() {
+            return Err(MiniSwiftError::PointOutsideDomain { position: vec![x, y, z] });
+        }
+
+        // add vertex
+        self.new_vertex(x, y, z);
+
+        // Find tetrahedron in which (x, y, z) is positioned
+        let location = match self.find_tetrahedron_containing_current_vertex() {
+            Ok(location) => location,
+            Err(error) => {
+                self.vertices.pop();
+                return Err(error);
+            }
+        };
+        match location {
+            PointLocation::InTetrahedron(tetrahedron_idx) => {
+                self.current_tetrahedron_idx = tetrahedron_idx;
+            }
+            PointLocation::OnVertex(vertex_idx) => {
+                self.vertices.pop();
+                self.current_vertex_idx = vertex_idx;
+                return Ok(VertexInsertion::Duplicate(vertex_idx as usize));
+            }
+        }
+
+        let (cavity, boundary) = self.find_cavity();
+        self.fill_cavity(cavity, boundary);
+
+        self.consistency_check();
+
+        Ok(VertexInsertion::Inserted(self.current_vertex_idx as usize))
+    }
+
+    /// Collects the tetrahedra whose circumsphere contains the current vertex, starting from the
+    /// current tetrahedron, which contains it. Returns the indices of these tetrahedra and the
+    /// faces on the boundary of their union (tetrahedron index and index of the vertex opposite of
+    /// the face).
+    fn find_cavity(&mut self) -> (Vec<i32>, Vec<(i32, usize)>) {
+        let p = self.current_vertex_idx;
+        // Visited tetrahedra are marked with the current stamp (in the cavity) or the current
+        // stamp + 1 (outside of the cavity), which avoids clearing the marks between insertions.
+        self.mark_stamp += 2;
+        let (in_cavity_mark, outside_mark) = (self.mark_stamp, self.mark_stamp + 1);
+        self.marks.resize(self.tetrahedra.len(), 0);
+
+        let mut cavity = vec![self.current_tetrahedron_idx];
+        let mut boundary = Vec::<(i32, usize)>::new();
+        self.marks[self.current_tetrahedron_idx as usize] = in_cavity_mark;
+        let mut n_visited = 0;
+        while n_visited < cavity.len() {
+            let tetrahedron_idx = cavity[n_visited];
+            n_visited += 1;
+            for i in 0..4 {
+                let neighbour_idx = self.tetrahedra[tetrahedron_idx as usize].neighbours[i];
+                let mark = self.marks[neighbour_idx as usize];
+                if mark == in_cavity_mark {
+                    continue;
+                }
+                let conflict = mark != outside_mark && neighbour_idx >= 4 && {
+                    let [a, b, c, d] = self.tetrahedra[neighbour_idx as usize].vertices;
+                    self.in_sphere(a, b, c, d, p) < 0.
+                };
+                if conflict {
+                    self.marks[neighbour_idx as usize] = in_cavity_mark;
+                    cavity.push(neighbour_idx);
+                } else {
+                    self.marks[neighbour_idx as usize] = outside_mark;
+                    boundary.push((tetrahedron_idx, i));
+                }
+            }
+        }
+        (cavity, boundary)
+    }
+
+    /// Replaces the tetrahedra of the cavity by new tetrahedra connecting the boundary faces of the
+    /// cavity with the current vertex. The slots of the old tetrahedra are reused.
+    fn fill_cavity(&mut self, cavity: Vec<i32>, boundary: Vec<(i32, usize)>) {
+        let p = self.current_vertex_idx;
+        // Read all information of the boundary faces before the old tetrahedra are overwritten.
+        let faces: Vec<([i32; 4], usize, i32, i8)> = boundary.iter().map(|&(tetrahedron_idx, i)| {
+            let tetrahedron = &self.tetrahedra[tetrahedron_idx as usize];
+            let mut vertices = tetrahedron.vertices;
+            vertices[i] = p;
+            (vertices, i, tetrahedron.neighbours[i], tetrahedron.index_in_neighbours[i])
+        }).collect();
+
+        // The faces of the new tetrahedra containing the new vertex are shared by two new
+        // tetrahedra, which we match by the edge of the boundary face they contain.
+        let mut edges = Vec::<((i32, i32), i32, i8)>::with_capacity(3 * faces.len());
+        let mut new_tetrahedra = Vec::<i32>::with_capacity(faces.len());
+        for (k, &(vertices, i, neighbour_idx, idx_in_neighbour)) in faces.iter().enumerate() {
+            let at = if k < cavity.len() { cavity[k] } else { -1 };
+            let tetrahedron_idx = self.new_tetrahedron_at(vertices, at);
+            new_tetrahedra.push(tetrahedron_idx);
+            self.tetrahedra[tetrahedron_idx as usize].update_neighbour(neighbour_idx, idx_in_neighbour, i as i8);
+            self.tetrahedra[neighbour_idx as usize].update_neighbour(tetrahedron_idx, i as i8, idx_in_neighbour);
+            for j in (0..4).filter(|&j| j != i) {
+                // the other two vertices of the face opposite of j
+                let (l, m) = ((j + 1..j + 4).map(|l| l % 4).find(|&l| l != i).unwrap(),
+                              (j + 1..j + 4).map(|l| l % 4).rfind(|&l| l != i).unwrap());
+                let edge = (vertices[l].min(vertices[m]), vertices[l].max(vertices[m]));
+                edges.push((edge, tetrahedron_idx, j as i8));
+            }
+        }
+        edges.sort_unstable_by_key(|&(edge, _, _)| edge);
+        for pair in edges.chunks_exact(2) {
+            let (edge0, tetrahedron0, idx0) = pair[0];
+            let (edge1, tetrahedron1, idx1) = pair[1];
+            debug_assert_eq!(edge0, edge1, "The boundary of the cavity is not closed!");
+            self.tetrahedra[tetrahedron0 as usize].update_neighbour(tetrahedron1, idx1, idx0);
+            self.tetrahedra[tetrahedron1 as usize].update_neighbour(tetrahedron0, idx0, idx1);
+        }
+        self.current_tetrahedron_idx = new_tetrahedra[0];
+
+        // Compact the tetrahedra if the cavity contained more tetrahedra than its boundary faces.
+        let mut freed = cavity[new_tetrahedra.len().min(cavity.len())..].to_vec();
+        freed.sort_unstable_by(|a, b| b.cmp(a));
+        for free_idx in freed {
+            let last_idx = self.tetrahedra.len() - 1;
+            if free_idx as usize != last_idx {
+                self.move_tetrahedron(last_idx, free_idx as usize);
+                if self.current_tetrahedron_idx == last_idx as i32 {
+                    self.current_tetrahedron_idx = free_idx;
+                }
+            }
+            self.tetrahedra.pop();
+        }
+    }
+
+    /// Relabels the vertices such that they are stored in the order of the input points, see
+    /// `DelaunayTriangulation2D::restore_input_order`.
+    fn restore_input_order(&mut self, vertex_idx: &[Option<usize>]) {
+        let mut new_idx: Vec<i32> = (0..self.vertices.len() as i32).collect();
+        for (new, old) in vertex_idx.iter().flatten().enumerate() {
+            new_idx[*old] = new as i32 + 4;
+        }
+        let mut vertices: Vec<DelaunayVertex3D> = Vec::with_capacity(self.vertices.capacity());
+        vertices.resize_with(self.vertices.len(), DelaunayVertex3D::default);
+        for (old, vertex) in self.vertices.drain(..).enumerate() {
+            vertices[new_idx[old] as usize] = vertex;
+        }
+        self.vertices = vertices;
+        for tetrahedron in self.tetrahedra.iter_mut() {
+            for v in tetrahedron.vertices.iter_mut() {
+                if *v >= 0 {
+                    *v = new_idx[*v as usize];
+                }
+            }
+        }
+        self.current_vertex_idx = new_idx[self.current_vertex_idx as usize];
+        self.consistency_check();
+    }
+
+    /// Moves the tetrahedron at index `from` to index `to`, updating all references to it.
+    fn move_tetrahedron(&mut self, from: usize, to: usize) {
+        let tetrahedron = std::mem::take(&mut self.tetrahedra[from]);
+        for i in 0..4 {
+            let neighbour_idx = tetrahedron.neighbours[i];
+            if neighbour_idx >= 0 {
+                self.tetrahedra[neighbour_idx as usize].neighbours[tetrahedron.index_in_neighbours[i] as usize] = to as i32;
+            }
+            let vertex_idx = tetrahedron.vertices[i];
+            if vertex_idx >= 0 && self.vertices[vertex_idx as usize].tetrahedron == from as i32 {
+                self.vertices[vertex_idx as usize].update_tetrahedron(to as i32, i as i8);
+            }
+        }
+        self.tetrahedra[to] = tetrahedron;
+        self.touch_tetrahedra([to as i32]);
+    }
+
+    /// Records tetrahedra to be verified by the next local consistency check.
+    fn touch_tetrahedra(&mut self, tetrahedron_indices: impl IntoIterator<Item=i32>) {
+        if self.check_level == CheckLevel::Local {
+            self.touched_tetrahedra.extend(tetrahedron_indices);
+        }
+    }
+
+    fn new_vertex(&mut self, x: f64, y: f64, z: f64) -> i32 {
+        let inverse_side = self.inverse_side;
+        self.current_vertex_idx = self.vertices.len() as i32;
+        self.vertices.push(DelaunayVertex3D{
+            x, y, z,
+            x_scaled: x * inverse_side,
+            y_scaled: y * inverse_side,
+            z_scaled: z * inverse_side,
+            ..DelaunayVertex3D::default()
+        });
+
+        self.current_vertex_idx
+    }
+
+    /// Adds periodic copies of the vertices as ghost vertices until the tetrahedra around all
+    /// vertices are guaranteed to be final, see `DelaunayTriangulation2D::add_boundary_ghost_vertices`.
+    fn make_periodic(&mut self) -> Result<(), MiniSwiftError> {
+        if self.is_periodic {
+            return Err(MiniSwiftError::AlreadyPeriodic);
+        }
+        // initial value of search radius: the average inter-particle distance for uniform distribution of particles
+        let mut search_radius = self.domain.sides()[0] / f64::cbrt(self.n_vertices as f64);
+
+        let mut old_search_radius = 0.;
+        let mut n_vertices_larger_search_radius = self.n_vertices;
+
+        while n_vertices_larger_search_radius > 0 {
+            self.add_ghost_vertices(search_radius, old_search_radius)?;
+            self.update_vertex_search_radii(search_radius, n_vertices_larger_search_radius);
+            n_vertices_larger_search_radius = self.vertices[4..self.n_vertices+4].iter()
+                .filter(|v| v.search_radius > search_radius).count();
+            let new_search_radius = 1.5 * search_radius;
+            old_search_radius = search_radius;
+            search_radius = new_search_radius;
+        }
+
+        self.is_periodic = true;
+        Ok(())
+    }
+
+    /// Adds the periodic copies, in each of the 26 neighbouring copies of the domain, of all
+    /// vertices lying between `old_search_radius` and `search_radius` from that copy.
+    fn add_ghost_vertices(&mut self, search_radius: f64, old_search_radius: f64) -> Result<(), MiniSwiftError> {
+        let anchor = self.domain.anchor();
+        let sides = self.domain.sides();
+        let mut shifts = Vec::with_capacity(26);
+        for sx in -1..=1 {
+            for sy in -1..=1 {
+                for sz in -1..=1 {
+                    if (sx, sy, sz) != (0, 0, 0) {
+                        shifts.push([sx, sy, sz]);
+                    }
+                }
+            }
+        }
+
+        let mut ghosts: Vec<([f64; 3], GhostOrigin<ThreeD>)> = Vec::new();
+        for shift in shifts {
+            for (i, vertex) in self.vertices[4..self.n_vertices+4].iter().enumerate() {
+                // distance from the shifted copy of the vertex to the domain
+                let position = [vertex.x, vertex.y, vertex.z];
+                let distance = (0..3).map(|k| match shift[k] {
+                    1 => position[k] - anchor[k],
+                    -1 => anchor[k] + sides[k] - position[k],
+                    _ => 0.
+                }).map(|d| d * d).sum::<f64>().sqrt();
+                if old_search_radius <= distance && distance < search_radius {
+                    let shift = [shift[0] as f64 * sides[0], shift[1] as f64 * sides[1], shift[2] as f64 * sides[2]];
+                    let origin = GhostOrigin::shifted(None, i, shift);
+                    ghosts.push((origin.ghost_position(position), origin));
+                }
+            }
+        }
+        // Insert the ghosts along a Hilbert curve covering the domain and its periodic copies
+        let ghost_domain = SimulationDomain3D::new(
+            [anchor[0] - sides[0], anchor[1] - sides[1], anchor[2] - sides[2]],
+            [3. * sides[0], 3. * sides[1], 3. * sides[2]]
+        );
+        let key = |i: usize| hilbert_key_3d(ghosts[i].0[0], ghosts[i].0[1], ghosts[i].0[2], &ghost_domain);
+        for i in insertion_order(ghosts.len(), key, InsertionOrder::Hilbert) {
+            let ([x, y, z], origin) = ghosts[i];
+            self.insert_vertex_with_origin(x, y, z, origin)?;
+        }
+        Ok(())
+    }
+
+    pub fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
+        let mut radii = Vec::<(usize, f64)>::with_capacity(previous_n_vertices_larger_radius);
+        for (i, vertex) in self.vertices[4..self.n_vertices+4].iter().enumerate() {
+            if vertex.search_radius < current_search_radius { continue; }
+            let mut max_radius: f64 = 0.;
+            for tetrahedron_idx in self.get_tetrahedron_idx_around_vertex(i + 4) {
+                // any point within a sphere of radius 2*current circumradius around current vertex
+                // CAN violate the delaunay criterion for the current tetrahedron.
+                max_radius = max_radius.max(2. * self.tetrahedra[tetrahedron_idx].circumradius(self));
+            }
+            radii.push((i+4, max_radius));
+        }
+        for (i, radius) in radii {
+            self.vertices[i].search_radius = radius;
+        }
+    }
+
+    /// Returns the indices of all tetrahedra containing the given vertex.
+    pub fn get_tetrahedron_idx_around_vertex(&self, vertex_idx: usize) -> Vec::<usize> {
+        let vertex = &self.vertices[vertex_idx];
+        let mut tetrahedron_indices = vec![vertex.tetrahedron as usize];
+        let mut i = 0;
+        // Breadth first search across the faces containing the vertex
+        while i < tetrahedron_indices.len() {
+            let tetrahedron = &self.tetrahedra[tetrahedron_indices[i]];
+            for j in 0..4 {
+                if tetrahedron.vertices[j] == vertex_idx as i32 {
+                    continue;
+                }
+                let neighbour_idx = tetrahedron.neighbours[j] as usize;
+                if !tetrahedron_indices.contains(&neighbour_idx) {
+                    tetrahedron_indices.push(neighbour_idx);
+                }
+            }
+            i += 1;
+        }
+        tetrahedron_indices
+    }
+
+    fn new_tetrahedron(&mut self, vertices: [i32; 4]) -> i32 {
+        self.new_tetrahedron_at(vertices, -1)
+    }
+
+    fn new_tetrahedron_at(&mut self, vertices: [i32; 4], mut at: i32) -> i32 {
+        if at < 0 {
+            at = self.tetrahedra.len() as i32;
+            self.tetrahedra.push(DelaunayTetrahedron3D{
+                vertices,
+                ..DelaunayTetrahedron3D::default()
+            });
+        } else {
+            self.tetrahedra[at as usize] = DelaunayTetrahedron3D{
+                vertices,
+                ..DelaunayTetrahedron3D::default()
+            };
+        }
+
+        self.touch_tetrahedra([at]);
+
+        for (i, &v) in vertices.iter().enumerate() {
+            if v >= 0 {
+                self.vertices[v as usize].update_tetrahedron(at, i as i8);
+            }
+        }
+
+        at
+    }
+
+    /// Visibility walk towards the current vertex: from the current tetrahedron, repeatedly step
+    /// to a (randomly chosen) neighbour across a face separating the tetrahedron from the vertex.
+    /// The random choices are seeded with the index of the vertex, such that the walk, and hence
+    /// the tetrahedralization, is reproducible.
+    fn find_tetrahedron_containing_current_vertex(&self) -> Result<PointLocation, MiniSwiftError> {
+        let p = self.current_vertex_idx;
+        let vertex = &self.vertices[p as usize];
+        let location_error = MiniSwiftError::LocationWalkFailure { position: vec![vertex.x, vertex.y, vertex.z] };
+        let outside_error = MiniSwiftError::PointOutsideDomain { position: vec![vertex.x, vertex.y, vertex.z] };
+        let mut rng = StdRng::seed_from_u64(p as u64);
+        let mut current_tetrahedron_idx = self.current_tetrahedron_idx;
+
+        for _ in 0..self.tetrahedra.len() {
+            let current_tetrahedron = &self.tetrahedra[current_tetrahedron_idx as usize];
+            // tests[i] is the orientation of the tetrahedron with its vertex i replaced by p
+            let mut tests = [0.; 4];
+            for (i, test) in tests.iter_mut().enumerate() {
+                let mut vertices = current_tetrahedron.vertices;
+                vertices[i] = p;
+                *test = self.orient(vertices[0], vertices[1], vertices[2], vertices[3]);
+            }
+            let n_candidates = tests.iter().filter(|&&test| test < 0.).count();
+            if n_candidates == 0 {
+                // The vertex lies inside or on the boundary of the current tetrahedron.
+                for &v in current_tetrahedron.vertices.iter() {
+                    let other = &self.vertices[v as usize];
+                    if (other.x, other.y, other.z) == (vertex.x, vertex.y, vertex.z) {
+                        return Ok(PointLocation::OnVertex(v));
+                    }
+                }
+                // Points on the boundary of the encapsulating tetrahedron cannot be inserted
+                if (0..4).any(|i| tests[i] == 0. && current_tetrahedron.neighbours[i] < 4) {
+                    return Err(outside_error);
+                }
+                return Ok(PointLocation::InTetrahedron(current_tetrahedron_idx));
+            }
+            let choice = if n_candidates == 1 { 0 } else { rng.gen_range(0..n_candidates) };
+            let next_idx = (0..4).filter(|&i| tests[i] < 0.).nth(choice).unwrap();
+            current_tetrahedron_idx = current_tetrahedron.neighbours[next_idx];
+
+            if current_tetrahedron_idx < 4 {
+                return Err(outside_error);
+            }
+        }
+        Err(location_error)
+    }
+
+    /// Orientation test for the vertices with indices a, b, c and d, see `orient_3d`.
+    fn orient(&self, a: i32, b: i32, c: i32, d: i32) -> f64 {
+        let (va, vb, vc, vd) = (&self.vertices[a as usize], &self.vertices[b as usize],
+                                &self.vertices[c as usize], &self.vertices[d as usize]);
+        orient_3d(va.x_scaled, va.y_scaled, va.z_scaled, vb.x_scaled, vb.y_scaled, vb.z_scaled,
+                  vc.x_scaled, vc.y_scaled, vc.z_scaled, vd.x_scaled, vd.y_scaled, vd.z_scaled)
+    }
+
+    /// In-sphere test for the vertices with indices a, b, c, d and e, see `in_sphere_3d`. Ties
+    /// for cospherical vertices are broken by symbolic perturbation of the lifted coordinates,
+    /// exactly like in `DelaunayTriangulation2D::in_circle`.
+    fn in_sphere(&self, a: i32, b: i32, c: i32, d: i32, e: i32) -> f64 {
+        let (va, vb, vc, vd, ve) = (&self.vertices[a as usize], &self.vertices[b as usize],
+                                    &self.vertices[c as usize], &self.vertices[d as usize],
+                                    &self.vertices[e as usize]);
+        let test = in_sphere_3d(va.x_scaled, va.y_scaled, va.z_scaled, vb.x_scaled, vb.y_scaled, vb.z_scaled,
+                                vc.x_scaled, vc.y_scaled, vc.z_scaled, vd.x_scaled, vd.y_scaled, vd.z_scaled,
+                                ve.x_scaled, ve.y_scaled, ve.z_scaled);
+        if test != 0. {
+            return test;
+        }
+
+        // Partial derivatives of the in-sphere test w.r.t. the lifted coordinate of each vertex.
+        let mut terms = [
+            (va, self.orient(b, c, d, e)),
+            (vb, -self.orient(a, c, d, e)),
+            (vc, self.orient(a, b, d, e)),
+            (vd, -self.orient(a, b, c, e)),
+            (ve, self.orient(a, b, c, d))
+        ];
+        terms.sort_by(|(v, _), (w, _)| {
+            v.x_scaled.total_cmp(&w.x_scaled)
+                .then(v.y_scaled.total_cmp(&w.y_scaled))
+                .then(v.z_scaled.total_cmp(&w.z_scaled))
+        });
+        terms.iter().map(|&(_, term)| term).find(|&term| term != 0.).unwrap_or(0.)
+    }
+
+    /// Verifies the consistency of the tetrahedralization according to its `CheckLevel`.
+    fn consistency_check(&mut self) {
+        match self.check_level {
+            CheckLevel::None => {}
+            CheckLevel::Local => {
+                let mut touched = std::mem::take(&mut self.touched_tetrahedra);
+                touched.sort_unstable();
+                touched.dedup();
+                // Tetrahedra might have been removed after being touched
+                touched.retain(|&tetrahedron_idx| tetrahedron_idx >= 4 && (tetrahedron_idx as usize) < self.tetrahedra.len());
+                for &tetrahedron_idx in touched.iter() {
+                    self.check_tetrahedron(tetrahedron_idx as usize);
+                    for &vertex_idx in self.tetrahedra[tetrahedron_idx as usize].vertices.iter() {
+                        self.check_vertex(vertex_idx as usize);
+                    }
+                }
+                touched.clear();
+                self.touched_tetrahedra = touched;
+            }
+            CheckLevel::Full => {
+                for tetrahedron_idx in 4..self.tetrahedra.len() {
+                    self.check_tetrahedron(tetrahedron_idx);
+                }
+                for vertex_idx in 0..self.vertices.len() {
+                    self.check_vertex(vertex_idx);
+                }
+            }
+        }
+    }
+
+    /// Checks the orientation and neighbour relations of a (non dummy) tetrahedron and the
+    /// Delaunay criterion with respect to its neighbours.
+    fn check_tetrahedron(&self, tetrahedron_idx: usize) {
+        let tetrahedron = &self.tetrahedra[tetrahedron_idx];
+        let [a, b, c, d] = tetrahedron.vertices;
+        assert!(self.orient(a, b, c, d) > 0., "Tetrahedron {} is not positively oriented!", tetrahedron_idx);
+        for (j, &ngbr) in tetrahedron.neighbours.iter().enumerate() {
+            // check neighbouring relation symmetry
+            let idx_in_ngbr = tetrahedron.index_in_neighbours[j] as usize;
+            assert_eq!(tetrahedron_idx as i32,
+                       self.tetrahedra[ngbr as usize].neighbours[idx_in_ngbr],
+                       "Testing neighbour symmetry");
+
+            // check overlapping vertices
+            let neighbour = &self.tetrahedra[ngbr as usize];
+            let n_overlap = tetrahedron.vertices.iter().filter(|v| neighbour.vertices.contains(v)).count();
+            assert_eq!(n_overlap, 3, "Neighbours should have exactly 3 vertices in common!");
+
+            // check Delaunay criterion, skipping dummy tetrahedra
+            if ngbr < 4 {
+                continue;
+            }
+            let e = neighbour.vertices[idx_in_ngbr];
+            let in_sphere = self.in_sphere(a, b, c, d, e);
+            assert!(in_sphere >= 0., "in_sphere test gave {:?}", in_sphere)
+        }
+    }
+
+    /// Checks that the tetrahedron stored with a vertex contains that vertex.
+    fn check_vertex(&self, vertex_idx: usize) {
+        let vertex = &self.vertices[vertex_idx];
+        assert_eq!(vertex_idx as i32,
+                   self.tetrahedra[vertex.tetrahedron as usize].vertices[vertex.index_in_tetrahedron as usize],
+                   "Testing vertex-tetrahedron correspondence");
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut result = String::from("# Vertices #\n");
+        for (i, v) in self.vertices.iter().enumerate() {
+            result += &format!("{}\t({}, {}, {})\n", i, v.x, v.y, v.z);
+        }
+
+        result += "\n# Tetrahedra #\n";
+        for (i, tetrahedron) in self.tetrahedra[4..].iter().enumerate() {
+            let [a, b, c, d] = tetrahedron.vertices;
+            result += &format!("{}\t({}, {}, {}, {})\n", i, a, b, c, d);
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl Tessellation<ThreeD> for DelaunayTetrahedralization3D {
+    fn new(domain: SimulationDomain3D, vertex_size: usize, simplex_size: usize) -> Self {
+        DelaunayTetrahedralization3D::new(domain, vertex_size, simplex_size)
+    }
+
+    fn insert_vertex(&mut self, position: [f64; 3]) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTetrahedralization3D::insert_vertex(self, position[0], position[1], position[2])
+    }
+
+    fn insert_ghost_vertex(&mut self, position: [f64; 3], origin: GhostOrigin<ThreeD>) -> Result<VertexInsertion, MiniSwiftError> {
+        DelaunayTetrahedralization3D::insert_ghost_vertex(self, position[0], position[1], position[2], origin)
+    }
+
+    fn ghost_origins(&self) -> &[GhostOrigin<ThreeD>] {
+        &self.ghost_origins
+    }
+
+    fn finalize(&mut self) -> Result<(), MiniSwiftError> {
+        DelaunayTetrahedralization3D::finalize(self)
+    }
+
+    fn n_vertices(&self) -> usize {
+        self.n_vertices
+    }
+
+    fn search_radius(&self, vertex_idx: usize) -> f64 {
+        self.vertices[vertex_idx + 4].search_radius
+    }
+
+    fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize) {
+        DelaunayTetrahedralization3D::update_vertex_search_radii(self, current_search_radius, previous_n_vertices_larger_radius)
+    }
+
+    fn to_file(&self, filename: &str) {
+        DelaunayTetrahedralization3D::to_file(self, filename)
+    }
+}
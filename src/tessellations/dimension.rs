@@ -0,0 +1,121 @@
+use super::{Tessellation, VoronoiGrid, VoronoiFace, DelaunayTessellation1D, VoronoiGrid1D, DelaunayTriangulation2D,
+            VoronoiGrid2D, DelaunayTetrahedralization3D, VoronoiGrid3D, Vertex1D, Vertex2D, Vertex3D};
+use super::voronoi1d::VoronoiFace1D;
+use super::voronoi2d::VoronoiFace2D;
+use super::voronoi3d::VoronoiFace3D;
+use crate::simulation_domain_1d::SimulationDomain1D;
+use crate::simulation_domain_2d::SimulationDomain2D;
+use crate::simulation_domain_3d::SimulationDomain3D;
+use std::fmt::Debug;
+
+
+/// Ties together the types used to describe space, tessellations and voronoi grids in a given
+/// number of dimensions, so that code which only needs their common functionality (e.g.
+/// `mini_swift::Cell`) can be written once for 1D, 2D and 3D.
+pub trait Dimension: Copy + Default + Debug + 'static {
+    /// The number of spatial dimensions
+    const DIM: usize;
+    /// The coordinates of a point
+    type Position: Copy + Default + Debug + PartialEq + AsRef<[f64]> + AsMut<[f64]>;
+    /// A point of a tessellation or grid
+    type Vertex: Copy + Debug;
+    /// A face between two cells of a voronoi grid
+    type Face: VoronoiFace<Self>;
+    type Domain: Domain<Self>;
+    type Delaunay: Tessellation<Self>;
+    type Voronoi: VoronoiGrid<Self>;
+}
+
+
+/// A rectangular region of space, given by its lower left corner and its sides
+pub trait Domain<D: Dimension>: Copy + Default + Debug {
+    fn new(anchor: D::Position, sides: D::Position) -> Self;
+
+    fn anchor(&self) -> D::Position;
+
+    fn sides(&self) -> D::Position;
+}
+
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OneD;
+
+impl Dimension for OneD {
+    const DIM: usize = 1;
+    type Position = [f64; 1];
+    type Vertex = Vertex1D;
+    type Face = VoronoiFace1D;
+    type Domain = SimulationDomain1D;
+    type Delaunay = DelaunayTessellation1D;
+    type Voronoi = VoronoiGrid1D;
+}
+
+impl Domain<OneD> for SimulationDomain1D {
+    fn new(anchor: [f64; 1], sides: [f64; 1]) -> Self {
+        SimulationDomain1D::new(anchor, sides)
+    }
+
+    fn anchor(&self) -> [f64; 1] {
+        SimulationDomain1D::anchor(self)
+    }
+
+    fn sides(&self) -> [f64; 1] {
+        SimulationDomain1D::sides(self)
+    }
+}
+
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TwoD;
+
+impl Dimension for TwoD {
+    const DIM: usize = 2;
+    type Position = [f64; 2];
+    type Vertex = Vertex2D;
+    type Face = VoronoiFace2D;
+    type Domain = SimulationDomain2D;
+    type Delaunay = DelaunayTriangulation2D;
+    type Voronoi = VoronoiGrid2D;
+}
+
+impl Domain<TwoD> for SimulationDomain2D {
+    fn new(anchor: [f64; 2], sides: [f64; 2]) -> Self {
+        SimulationDomain2D::new(anchor, sides)
+    }
+
+    fn anchor(&self) -> [f64; 2] {
+        SimulationDomain2D::anchor(self)
+    }
+
+    fn sides(&self) -> [f64; 2] {
+        SimulationDomain2D::sides(self)
+    }
+}
+
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ThreeD;
+
+impl Dimension for ThreeD {
+    const DIM: usize = 3;
+    type Position = [f64; 3];
+    type Vertex = Vertex3D;
+    type Face = VoronoiFace3D;
+    type Domain = SimulationDomain3D;
+    type Delaunay = DelaunayTetrahedralization3D;
+    type Voronoi = VoronoiGrid3D;
+}
+
+impl Domain<ThreeD> for SimulationDomain3D {
+    fn new(anchor: [f64; 3], sides: [f64; 3]) -> Self {
+        SimulationDomain3D::new(anchor, sides)
+    }
+
+    fn anchor(&self) -> [f64; 3] {
+        SimulationDomain3D::anchor(self)
+    }
+
+    fn sides(&self) -> [f64; 3] {
+        SimulationDomain3D::sides(self)
+    }
+}
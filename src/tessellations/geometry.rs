@@ -1,21 +1,62 @@
+mod vertex_1d;
 mod vertex_2d;
+mod vertex_3d;
 mod triangle_2d;
+mod expansion;
 
+pub(super) use vertex_1d::Vertex1D;
 pub(super) use vertex_2d::Vertex2D;
+pub(super) use vertex_3d::Vertex3D;
 pub(super) use triangle_2d::Triangle2D;
 
+/// Relative error bounds of the floating point evaluations of the determinants in `orient_2d`,
+/// `in_circle_2d`, `orient_3d` and `in_sphere_3d`, see Shewchuk (1997).
+const ORIENT_2D_ERROR_BOUND: f64 = (3. + 16. * expansion::EPSILON) * expansion::EPSILON;
+const IN_CIRCLE_2D_ERROR_BOUND: f64 = (10. + 96. * expansion::EPSILON) * expansion::EPSILON;
+const ORIENT_3D_ERROR_BOUND: f64 = (7. + 56. * expansion::EPSILON) * expansion::EPSILON;
+const IN_SPHERE_3D_ERROR_BOUND: f64 = (16. + 224. * expansion::EPSILON) * expansion::EPSILON;
+/// The in-circle bound, widened for the two additional roundings in the lifted coordinates of
+/// `power_test_2d`.
+const POWER_TEST_2D_ERROR_BOUND: f64 = (12. + 128. * expansion::EPSILON) * expansion::EPSILON;
 
 
 /// Returns a positive value when the triangle formed by (ax, ay), (bx, by) and (cx, cy) is
-/// positively oriented.
+/// positively oriented, a negative value when it is negatively oriented and zero when the three
+/// points are collinear.
+///
+/// The sign of the result is always exact: the determinant is first evaluated in plain floating
+/// point arithmetic and only recomputed exactly when it is too close to zero to be trusted.
 pub(super) fn orient_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64) -> f64 {
-    let s1x = ax - cx;
-    let s1y = ay - cy;
+    let det_left = (ax - cx) * (by - cy);
+    let det_right = (ay - cy) * (bx - cx);
+    let det = det_left - det_right;
 
-    let s2x = bx - cx;
-    let s2y = by - cy;
+    let det_sum = if det_left > 0. {
+        if det_right <= 0. { return det; }
+        det_left + det_right
+    } else if det_left < 0. {
+        if det_right >= 0. { return det; }
+        -det_left - det_right
+    } else {
+        return det;
+    };
 
-    s1x*s2y - s1y*s2x
+    if det.abs() >= ORIENT_2D_ERROR_BOUND * det_sum {
+        return det;
+    }
+    orient_2d_exact(ax, ay, bx, by, cx, cy)
+}
+
+fn orient_2d_exact(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64) -> f64 {
+    let acx = expansion::difference(ax, cx);
+    let acy = expansion::difference(ay, cy);
+    let bcx = expansion::difference(bx, cx);
+    let bcy = expansion::difference(by, cy);
+
+    expansion::estimate(&expansion::diff(
+        &expansion::product(&acx, &bcy),
+        &expansion::product(&acy, &bcx)
+    ))
 }
 
 
@@ -23,28 +64,353 @@ pub(super) fn orient_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64) ->
 /// formed by (ax, ay), (bx, by) and (cx, cy), provided that this triangle is positively oriented,
 /// a positive value when the point d lies outside and zero when the point d lies on this
 /// circumscribed sphere.
+///
+/// Like `orient_2d`, the sign of the result is exact.
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
 pub(super) fn in_circle_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64, dx: f64, dy: f64) -> f64 {
-    let s1x = bx - ax;
-    let s1y = by - ay;
-    let s2x = cx - ax;
-    let s2y = cy - ay;
-    let s3x = dx - ax;
-    let s3y = dy - ay;
-    let (mut tmp1, mut tmp2, mut result): (f64, f64, f64);
+    let adx = ax - dx;
+    let bdx = bx - dx;
+    let cdx = cx - dx;
+    let ady = ay - dy;
+    let bdy = by - dy;
+    let cdy = cy - dy;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady;
+
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy;
+
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy)
+        + blift * (cdxady - adxcdy)
+        + clift * (adxbdy - bdxady);
+
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+
+    // The determinant above is positive for points inside the circle, flip the sign to stick to
+    // the convention of this function.
+    if det.abs() > IN_CIRCLE_2D_ERROR_BOUND * permanent {
+        return -det;
+    }
+    -in_circle_2d_exact(ax, ay, bx, by, cx, cy, dx, dy)
+}
+
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+fn in_circle_2d_exact(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64, dx: f64, dy: f64) -> f64 {
+    let adx = expansion::difference(ax, dx);
+    let bdx = expansion::difference(bx, dx);
+    let cdx = expansion::difference(cx, dx);
+    let ady = expansion::difference(ay, dy);
+    let bdy = expansion::difference(by, dy);
+    let cdy = expansion::difference(cy, dy);
+
+    let lift = |x: &[f64], y: &[f64]| expansion::sum(
+        &expansion::product(x, x),
+        &expansion::product(y, y)
+    );
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| expansion::diff(
+        &expansion::product(x1, y2),
+        &expansion::product(x2, y1)
+    );
+
+    let a_term = expansion::product(&lift(&adx, &ady), &cross(&bdx, &cdy, &cdx, &bdy));
+    let b_term = expansion::product(&lift(&bdx, &bdy), &cross(&cdx, &ady, &adx, &cdy));
+    let c_term = expansion::product(&lift(&cdx, &cdy), &cross(&adx, &bdy, &bdx, &ady));
+
+    expansion::estimate(&expansion::sum(&expansion::sum(&a_term, &b_term), &c_term))
+}
+
+
+/// The weighted version of `in_circle_2d` for the points a, b, c and d with weights aw, bw, cw and
+/// dw: returns a negative value when the power distance |d - o|^2 - dw of d to the orthocenter o
+/// of the (positively oriented) triangle a, b, c is smaller than the power distance of its
+/// vertices to o, a positive value when it is larger and zero when they are equal. Equivalently,
+/// the test is negative when the lifted point (dx, dy, dx^2 + dy^2 - dw) lies below the plane
+/// through the lifted vertices of the triangle. For zero weights, this is the in-circle test.
+///
+/// Like `orient_2d`, the sign of the result is exact.
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+pub(super) fn power_test_2d(ax: f64, ay: f64, aw: f64, bx: f64, by: f64, bw: f64,
+                            cx: f64, cy: f64, cw: f64, dx: f64, dy: f64, dw: f64) -> f64 {
+    let adx = ax - dx;
+    let bdx = bx - dx;
+    let cdx = cx - dx;
+    let ady = ay - dy;
+    let bdy = by - dy;
+    let cdy = cy - dy;
+    let adw = aw - dw;
+    let bdw = bw - dw;
+    let cdw = cw - dw;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady - adw;
+
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy - bdw;
 
-    tmp1 = s2x*s2x +s2y*s2y;
-    tmp2 = s3x*s3x +s3y*s3y;
-    result = s1x * (s2y * tmp2 - s3y*tmp1);
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy - cdw;
 
-    tmp1 = s1x*s1x +s1y*s1y;
-    tmp2 = s3x*s3x +s3y*s3y;
-    result -= s2x * (s1y * tmp2 - s3y*tmp1);
+    let det = alift * (bdxcdy - cdxbdy)
+        + blift * (cdxady - adxcdy)
+        + clift * (adxbdy - bdxady);
 
-    tmp1 = s1x*s1x +s1y*s1y;
-    tmp2 = s2x*s2x +s2y*s2y;
-    result += s3x * (s1y * tmp2 - s2y*tmp1);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * (adx * adx + ady * ady + adw.abs())
+        + (cdxady.abs() + adxcdy.abs()) * (bdx * bdx + bdy * bdy + bdw.abs())
+        + (adxbdy.abs() + bdxady.abs()) * (cdx * cdx + cdy * cdy + cdw.abs());
 
-    result
+    // Same sign convention as in_circle_2d
+    if det.abs() > POWER_TEST_2D_ERROR_BOUND * permanent {
+        return -det;
+    }
+    -power_test_2d_exact(ax, ay, aw, bx, by, bw, cx, cy, cw, dx, dy, dw)
+}
+
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+fn power_test_2d_exact(ax: f64, ay: f64, aw: f64, bx: f64, by: f64, bw: f64,
+                       cx: f64, cy: f64, cw: f64, dx: f64, dy: f64, dw: f64) -> f64 {
+    let adx = expansion::difference(ax, dx);
+    let bdx = expansion::difference(bx, dx);
+    let cdx = expansion::difference(cx, dx);
+    let ady = expansion::difference(ay, dy);
+    let bdy = expansion::difference(by, dy);
+    let cdy = expansion::difference(cy, dy);
+    let adw = expansion::difference(aw, dw);
+    let bdw = expansion::difference(bw, dw);
+    let cdw = expansion::difference(cw, dw);
+
+    let lift = |x: &[f64], y: &[f64], w: &[f64]| expansion::diff(
+        &expansion::sum(&expansion::product(x, x), &expansion::product(y, y)),
+        w
+    );
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| expansion::diff(
+        &expansion::product(x1, y2),
+        &expansion::product(x2, y1)
+    );
+
+    let a_term = expansion::product(&lift(&adx, &ady, &adw), &cross(&bdx, &cdy, &cdx, &bdy));
+    let b_term = expansion::product(&lift(&bdx, &bdy, &bdw), &cross(&cdx, &ady, &adx, &cdy));
+    let c_term = expansion::product(&lift(&cdx, &cdy, &cdw), &cross(&adx, &bdy, &bdx, &ady));
+
+    expansion::estimate(&expansion::sum(&expansion::sum(&a_term, &b_term), &c_term))
+}
+
+
+/// Returns a positive value when the tetrahedron formed by a, b, c and d is positively oriented,
+/// i.e. when d lies on the side of the plane through a, b and c towards which (b - a) x (c - a)
+/// points, a negative value when it is negatively oriented and zero when the four points are
+/// coplanar.
+///
+/// Like `orient_2d`, the sign of the result is exact.
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+pub(super) fn orient_3d(ax: f64, ay: f64, az: f64, bx: f64, by: f64, bz: f64,
+                        cx: f64, cy: f64, cz: f64, dx: f64, dy: f64, dz: f64) -> f64 {
+    let adx = ax - dx;
+    let bdx = bx - dx;
+    let cdx = cx - dx;
+    let ady = ay - dy;
+    let bdy = by - dy;
+    let cdy = cy - dy;
+    let adz = az - dz;
+    let bdz = bz - dz;
+    let cdz = cz - dz;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+
+    let det = adz * (bdxcdy - cdxbdy)
+        + bdz * (cdxady - adxcdy)
+        + cdz * (adxbdy - bdxady);
+
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * adz.abs()
+        + (cdxady.abs() + adxcdy.abs()) * bdz.abs()
+        + (adxbdy.abs() + bdxady.abs()) * cdz.abs();
+
+    // The determinant above is positive for negatively oriented tetrahedra, flip the sign to
+    // stick to the convention of this function.
+    if det.abs() > ORIENT_3D_ERROR_BOUND * permanent {
+        return -det;
+    }
+    -orient_3d_exact(ax, ay, az, bx, by, bz, cx, cy, cz, dx, dy, dz)
+}
+
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+fn orient_3d_exact(ax: f64, ay: f64, az: f64, bx: f64, by: f64, bz: f64,
+                   cx: f64, cy: f64, cz: f64, dx: f64, dy: f64, dz: f64) -> f64 {
+    let adx = expansion::difference(ax, dx);
+    let bdx = expansion::difference(bx, dx);
+    let cdx = expansion::difference(cx, dx);
+    let ady = expansion::difference(ay, dy);
+    let bdy = expansion::difference(by, dy);
+    let cdy = expansion::difference(cy, dy);
+    let adz = expansion::difference(az, dz);
+    let bdz = expansion::difference(bz, dz);
+    let cdz = expansion::difference(cz, dz);
+
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| expansion::diff(
+        &expansion::product(x1, y2),
+        &expansion::product(x2, y1)
+    );
+
+    let a_term = expansion::product(&adz, &cross(&bdx, &cdy, &cdx, &bdy));
+    let b_term = expansion::product(&bdz, &cross(&cdx, &ady, &adx, &cdy));
+    let c_term = expansion::product(&cdz, &cross(&adx, &bdy, &bdx, &ady));
+
+    expansion::estimate(&expansion::sum(&expansion::sum(&a_term, &b_term), &c_term))
+}
+
+
+/// Returns a negative value when e lies in the circumscribed sphere around the tetrahedron formed
+/// by a, b, c and d, provided that this tetrahedron is positively oriented (see `orient_3d`), a
+/// positive value when the point e lies outside and zero when the point e lies on this
+/// circumscribed sphere.
+///
+/// Like `orient_2d`, the sign of the result is exact.
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+pub(super) fn in_sphere_3d(ax: f64, ay: f64, az: f64, bx: f64, by: f64, bz: f64,
+                           cx: f64, cy: f64, cz: f64, dx: f64, dy: f64, dz: f64,
+                           ex: f64, ey: f64, ez: f64) -> f64 {
+    let aex = ax - ex;
+    let bex = bx - ex;
+    let cex = cx - ex;
+    let dex = dx - ex;
+    let aey = ay - ey;
+    let bey = by - ey;
+    let cey = cy - ey;
+    let dey = dy - ey;
+    let aez = az - ez;
+    let bez = bz - ez;
+    let cez = cz - ez;
+    let dez = dz - ez;
+
+    let aexbey = aex * bey;
+    let bexaey = bex * aey;
+    let ab = aexbey - bexaey;
+    let bexcey = bex * cey;
+    let cexbey = cex * bey;
+    let bc = bexcey - cexbey;
+    let cexdey = cex * dey;
+    let dexcey = dex * cey;
+    let cd = cexdey - dexcey;
+    let dexaey = dex * aey;
+    let aexdey = aex * dey;
+    let da = dexaey - aexdey;
+    let aexcey = aex * cey;
+    let cexaey = cex * aey;
+    let ac = aexcey - cexaey;
+    let bexdey = bex * dey;
+    let dexbey = dex * bey;
+    let bd = bexdey - dexbey;
+
+    let abc = aez * bc - bez * ac + cez * ab;
+    let bcd = bez * cd - cez * bd + dez * bc;
+    let cda = cez * da + dez * ac + aez * cd;
+    let dab = dez * ab + aez * bd + bez * da;
+
+    let alift = aex * aex + aey * aey + aez * aez;
+    let blift = bex * bex + bey * bey + bez * bez;
+    let clift = cex * cex + cey * cey + cez * cez;
+    let dlift = dex * dex + dey * dey + dez * dez;
+
+    let det = (dlift * abc - clift * dab) + (blift * cda - alift * bcd);
+
+    let aez_abs = aez.abs();
+    let bez_abs = bez.abs();
+    let cez_abs = cez.abs();
+    let dez_abs = dez.abs();
+    let aexbey_abs = aexbey.abs() + bexaey.abs();
+    let bexcey_abs = bexcey.abs() + cexbey.abs();
+    let cexdey_abs = cexdey.abs() + dexcey.abs();
+    let dexaey_abs = dexaey.abs() + aexdey.abs();
+    let aexcey_abs = aexcey.abs() + cexaey.abs();
+    let bexdey_abs = bexdey.abs() + dexbey.abs();
+    let permanent = (cexdey_abs * bez_abs + bexdey_abs * cez_abs + bexcey_abs * dez_abs) * alift
+        + (dexaey_abs * cez_abs + aexcey_abs * dez_abs + cexdey_abs * aez_abs) * blift
+        + (aexbey_abs * dez_abs + bexdey_abs * aez_abs + dexaey_abs * bez_abs) * clift
+        + (bexcey_abs * aez_abs + aexcey_abs * bez_abs + aexbey_abs * cez_abs) * dlift;
+
+    // For positively oriented tetrahedra (in the sense of `orient_3d`), the determinant above is
+    // already negative for points inside the sphere.
+    if det.abs() > IN_SPHERE_3D_ERROR_BOUND * permanent {
+        return det;
+    }
+    in_sphere_3d_exact(ax, ay, az, bx, by, bz, cx, cy, cz, dx, dy, dz, ex, ey, ez)
+}
+
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+fn in_sphere_3d_exact(ax: f64, ay: f64, az: f64, bx: f64, by: f64, bz: f64,
+                      cx: f64, cy: f64, cz: f64, dx: f64, dy: f64, dz: f64,
+                      ex: f64, ey: f64, ez: f64) -> f64 {
+    let aex = expansion::difference(ax, ex);
+    let bex = expansion::difference(bx, ex);
+    let cex = expansion::difference(cx, ex);
+    let dex = expansion::difference(dx, ex);
+    let aey = expansion::difference(ay, ey);
+    let bey = expansion::difference(by, ey);
+    let cey = expansion::difference(cy, ey);
+    let dey = expansion::difference(dy, ey);
+    let aez = expansion::difference(az, ez);
+    let bez = expansion::difference(bz, ez);
+    let cez = expansion::difference(cz, ez);
+    let dez = expansion::difference(dz, ez);
+
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| expansion::diff(
+        &expansion::product(x1, y2),
+        &expansion::product(x2, y1)
+    );
+    let ab = cross(&aex, &bey, &bex, &aey);
+    let bc = cross(&bex, &cey, &cex, &bey);
+    let cd = cross(&cex, &dey, &dex, &cey);
+    let da = cross(&dex, &aey, &aex, &dey);
+    let ac = cross(&aex, &cey, &cex, &aey);
+    let bd = cross(&bex, &dey, &dex, &bey);
+
+    let abc = expansion::diff(
+        &expansion::sum(&expansion::product(&aez, &bc), &expansion::product(&cez, &ab)),
+        &expansion::product(&bez, &ac)
+    );
+    let bcd = expansion::diff(
+        &expansion::sum(&expansion::product(&bez, &cd), &expansion::product(&dez, &bc)),
+        &expansion::product(&cez, &bd)
+    );
+    let cda = expansion::sum(
+        &expansion::sum(&expansion::product(&cez, &da), &expansion::product(&dez, &ac)),
+        &expansion::product(&aez, &cd)
+    );
+    let dab = expansion::sum(
+        &expansion::sum(&expansion::product(&dez, &ab), &expansion::product(&aez, &bd)),
+        &expansion::product(&bez, &da)
+    );
+
+    let lift = |x: &[f64], y: &[f64], z: &[f64]| expansion::sum(
+        &expansion::sum(&expansion::product(x, x), &expansion::product(y, y)),
+        &expansion::product(z, z)
+    );
+
+    let det = expansion::sum(
+        &expansion::diff(&expansion::product(&lift(&dex, &dey, &dez), &abc),
+                         &expansion::product(&lift(&cex, &cey, &cez), &dab)),
+        &expansion::diff(&expansion::product(&lift(&bex, &bey, &bez), &cda),
+                         &expansion::product(&lift(&aex, &aey, &aez), &bcd))
+    );
+    expansion::estimate(&det)
 }
 
 
@@ -66,6 +432,25 @@ pub(super) fn circumradius_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f
 }
 
 
+/// The orthocenter (power center) of the weighted points a, b and c: the point with equal power
+/// distance |x - p|^2 - w to all three of them. For zero weights, this is the circumcenter.
+#[allow(clippy::too_many_arguments, reason = "the coordinates of the points are passed as scalars")]
+pub(super) fn power_center_2d(ax: f64, ay: f64, aw: f64, bx: f64, by: f64, bw: f64, cx: f64, cy: f64, cw: f64) -> Vertex2D {
+    let bxn = bx - ax;
+    let byn = by - ay;
+    let cxn = cx - ax;
+    let cyn = cy - ay;
+    let blift = bxn * bxn + byn * byn - (bw - aw);
+    let clift = cxn * cxn + cyn * cyn - (cw - aw);
+
+    let tmp = 2.*(bxn * cyn - byn * cxn);
+    let s3x = (cyn * blift - byn * clift) / tmp;
+    let s3y = (bxn * clift - cxn * blift) / tmp;
+
+    Vertex2D{x: s3x+ax, y: s3y+ay}
+}
+
+
 pub(super) fn oriented_volume_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64) -> f64 {
     (ax*by + bx*cy + cx*ay - bx*ay - cx*by - ax*cy) / 2.
 }
@@ -73,4 +458,22 @@ pub(super) fn oriented_volume_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy
 
 fn centroid_2d(ax: f64, ay: f64, bx: f64, by: f64, cx: f64, cy: f64) -> Vertex2D {
     Vertex2D{x: (ax + bx + cx) / 3., y: (ay + by + cy) / 3.}
-}
\ No newline at end of file
+}
+
+pub(super) fn circumcenter_3d(a: Vertex3D, b: Vertex3D, c: Vertex3D, d: Vertex3D) -> Vertex3D {
+    let b = b - a;
+    let c = c - a;
+    let d = d - a;
+
+    let c_cross_d = c.cross(&d);
+    let d_cross_b = d.cross(&b);
+    let b_cross_c = b.cross(&c);
+    let tmp = 2. * b.dot(&c_cross_d);
+
+    a + (b.dot(&b) * c_cross_d + c.dot(&c) * d_cross_b + d.dot(&d) * b_cross_c) / tmp
+}
+
+pub(super) fn circumradius_3d(a: Vertex3D, b: Vertex3D, c: Vertex3D, d: Vertex3D) -> f64 {
+    (a - circumcenter_3d(a, b, c, d)).norm()
+}
+
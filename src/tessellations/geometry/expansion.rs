@@ -0,0 +1,128 @@
+//! Exact floating point arithmetic on nonoverlapping expansions, following J.R. Shewchuk,
+//! "Adaptive Precision Floating-Point Arithmetic and Fast Robust Geometric Predicates" (1997).
+//!
+//! An expansion is a sequence of f64 components ordered by increasing magnitude whose exact sum
+//! is the represented value. The sign of an expansion is the sign of its largest component.
+
+/// Machine epsilon as used by Shewchuk: half an ulp of 1.
+pub(super) const EPSILON: f64 = f64::EPSILON * 0.5;
+
+/// 2^ceil(53 / 2) + 1, used to split a double in two non-overlapping halves.
+const SPLITTER: f64 = 134_217_729.;
+
+
+/// Returns (x, y) with x = fl(a + b) and x + y = a + b exactly.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_roundoff = b - b_virtual;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+/// Returns (x, y) with x = fl(a - b) and x + y = a - b exactly.
+fn two_diff(a: f64, b: f64) -> (f64, f64) {
+    let x = a - b;
+    let b_virtual = a - x;
+    let a_virtual = x + b_virtual;
+    let b_roundoff = b_virtual - b;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let c = SPLITTER * a;
+    let a_big = c - a;
+    let a_hi = c - a_big;
+    (a_hi, a - a_hi)
+}
+
+/// Returns (x, y) with x = fl(a * b) and x + y = a * b exactly.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    let err1 = x - a_hi * b_hi;
+    let err2 = err1 - a_lo * b_hi;
+    let err3 = err2 - a_hi * b_lo;
+    (x, a_lo * b_lo - err3)
+}
+
+/// The exact difference a - b as an expansion.
+pub(super) fn difference(a: f64, b: f64) -> Vec<f64> {
+    let (x, y) = two_diff(a, b);
+    vec![y, x]
+}
+
+/// Adds a single double to an expansion (Shewchuk's GROW-EXPANSION with zero elimination).
+fn grow(e: &[f64], b: f64) -> Vec<f64> {
+    let mut result = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &component in e {
+        let (sum, err) = two_sum(q, component);
+        q = sum;
+        if err != 0. {
+            result.push(err);
+        }
+    }
+    if q != 0. || result.is_empty() {
+        result.push(q);
+    }
+    result
+}
+
+/// The exact sum of two expansions.
+pub(super) fn sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut result = e.to_vec();
+    for &component in f {
+        result = grow(&result, component);
+    }
+    result
+}
+
+/// The exact difference of two expansions.
+pub(super) fn diff(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let negated: Vec<f64> = f.iter().map(|c| -c).collect();
+    sum(e, &negated)
+}
+
+/// Multiplies an expansion by a single double (Shewchuk's SCALE-EXPANSION with zero elimination).
+fn scale(e: &[f64], b: f64) -> Vec<f64> {
+    let mut result = Vec::with_capacity(2 * e.len());
+    let (mut q, err) = two_product(e[0], b);
+    if err != 0. {
+        result.push(err);
+    }
+    for &component in e[1..].iter() {
+        let (product, product_err) = two_product(component, b);
+        let (sum, sum_err) = two_sum(q, product_err);
+        if sum_err != 0. {
+            result.push(sum_err);
+        }
+        let (new_q, err) = two_sum(product, sum);
+        q = new_q;
+        if err != 0. {
+            result.push(err);
+        }
+    }
+    if q != 0. || result.is_empty() {
+        result.push(q);
+    }
+    result
+}
+
+/// The exact product of two expansions.
+pub(super) fn product(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.];
+    for &component in f {
+        result = sum(&result, &scale(e, component));
+    }
+    result
+}
+
+/// The most significant component of an expansion, which carries its sign and approximates its
+/// value.
+pub(super) fn estimate(e: &[f64]) -> f64 {
+    *e.last().unwrap()
+}
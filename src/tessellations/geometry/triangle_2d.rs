@@ -21,4 +21,18 @@ impl Triangle2D {
     pub fn centroid(&self) -> Vertex2D {
         centroid_2d(self.a.x, self.a.y, self.b.x, self.b.y, self.c.x, self.c.y)
     }
+
+    /// The points and weights of a three point quadrature rule over the triangle, exact for
+    /// polynomials up to degree 2. The points lie strictly inside the triangle, at barycentric
+    /// coordinates (2/3, 1/6, 1/6) and permutations, and the weights are a third of the (signed)
+    /// area.
+    pub fn quadrature_points(&self) -> [(Vertex2D, f64); 3] {
+        let weight = self.area() / 3.;
+        let point = |p: Vertex2D, q: Vertex2D, r: Vertex2D| (2. / 3.) * p + (q + r) * (1. / 6.);
+        [
+            (point(self.a, self.b, self.c), weight),
+            (point(self.b, self.c, self.a), weight),
+            (point(self.c, self.a, self.b), weight)
+        ]
+    }
 }
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::ops;
+
+/// A simple point in 1D space
+#[derive(Debug, Copy, Clone)]
+pub struct Vertex1D {
+    pub x: f64
+}
+
+impl Default for Vertex1D {
+    fn default() -> Vertex1D {
+        Vertex1D{x: f64::NAN}
+    }
+}
+
+impl ops::Add<Vertex1D> for Vertex1D {
+    type Output = Vertex1D;
+
+    fn add(self, rhs: Vertex1D) -> Vertex1D {
+        Vertex1D{x: self.x + rhs.x}
+    }
+}
+
+impl ops::AddAssign for Vertex1D {
+    fn add_assign(&mut self, rhs: Vertex1D) {
+        self.x += rhs.x;
+    }
+}
+
+impl ops::Sub<Vertex1D> for Vertex1D {
+    type Output = Vertex1D;
+
+    fn sub(self, rhs: Vertex1D) -> Vertex1D {
+        Vertex1D{x: self.x - rhs.x}
+    }
+}
+
+impl ops::Mul<f64> for Vertex1D {
+    type Output = Vertex1D;
+
+    fn mul(self, rhs: f64) -> Vertex1D {
+        Vertex1D{x: self.x * rhs }
+    }
+}
+
+impl ops::Mul<Vertex1D> for f64 {
+    type Output = Vertex1D;
+
+    fn mul(self, rhs: Vertex1D) -> Vertex1D {
+        Vertex1D{x: rhs.x * self }
+    }
+}
+
+impl ops::DivAssign<f64> for Vertex1D {
+    fn div_assign(&mut self, rhs: f64) {
+        self.x /= rhs;
+    }
+}
+
+impl ops::Div<f64> for Vertex1D {
+    type Output = Vertex1D;
+
+    fn div(self, rhs: f64) -> Vertex1D {
+        Vertex1D{x: self.x / rhs }
+    }
+}
+
+impl Vertex1D {
+    pub fn norm(&self) -> f64 {
+        self.x.abs()
+    }
+}
\ No newline at end of file
@@ -9,7 +9,7 @@ pub struct Vertex2D {
 
 impl Default for Vertex2D {
     fn default() -> Vertex2D {
-        Vertex2D{x: std::f64::NAN, y: std::f64::NAN}
+        Vertex2D{x: f64::NAN, y: f64::NAN}
     }
 }
 
@@ -71,4 +71,8 @@ impl Vertex2D {
     pub fn norm(&self) -> f64 {
         f64::sqrt(self.x*self.x + self.y*self.y)
     }
+
+    pub fn dot(&self, other: &Vertex2D) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
 }
\ No newline at end of file
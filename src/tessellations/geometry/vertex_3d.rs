@@ -0,0 +1,89 @@
+use std::ops;
+
+/// A simple point in 3D space
+#[derive(Debug, Copy, Clone)]
+pub struct Vertex3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64
+}
+
+impl Default for Vertex3D {
+    fn default() -> Vertex3D {
+        Vertex3D{x: f64::NAN, y: f64::NAN, z: f64::NAN}
+    }
+}
+
+impl ops::Add<Vertex3D> for Vertex3D {
+    type Output = Vertex3D;
+
+    fn add(self, rhs: Vertex3D) -> Vertex3D {
+        Vertex3D{x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z}
+    }
+}
+
+impl ops::AddAssign for Vertex3D {
+    fn add_assign(&mut self, rhs: Vertex3D) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl ops::Sub<Vertex3D> for Vertex3D {
+    type Output = Vertex3D;
+
+    fn sub(self, rhs: Vertex3D) -> Vertex3D {
+        Vertex3D{x: self.x - rhs.x, y: self.y - rhs.y, z: self.z - rhs.z}
+    }
+}
+
+impl ops::Mul<f64> for Vertex3D {
+    type Output = Vertex3D;
+
+    fn mul(self, rhs: f64) -> Vertex3D {
+        Vertex3D{x: self.x * rhs, y: self.y * rhs, z: self.z * rhs}
+    }
+}
+
+impl ops::Mul<Vertex3D> for f64 {
+    type Output = Vertex3D;
+
+    fn mul(self, rhs: Vertex3D) -> Vertex3D {
+        Vertex3D{x: rhs.x * self, y: rhs.y * self, z: rhs.z * self}
+    }
+}
+
+impl ops::DivAssign<f64> for Vertex3D {
+    fn div_assign(&mut self, rhs: f64) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}
+
+impl ops::Div<f64> for Vertex3D {
+    type Output = Vertex3D;
+
+    fn div(self, rhs: f64) -> Vertex3D {
+        Vertex3D{x: self.x / rhs, y: self.y / rhs, z: self.z / rhs}
+    }
+}
+
+impl Vertex3D {
+    pub fn norm(&self) -> f64 {
+        f64::sqrt(self.dot(self))
+    }
+
+    pub fn dot(&self, other: &Vertex3D) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vertex3D) -> Vertex3D {
+        Vertex3D{
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::voronoi2d::VoronoiGrid2D;
+use super::Vertex2D;
+use crate::error::MiniSwiftError;
+
+
+/// The available methods to estimate the gradients of a quantity in the cells of a voronoi grid.
+/// Both reproduce the gradients of linear fields exactly, except for the Green-Gauss estimate in
+/// cells with boundary faces.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientEstimator {
+    /// The face based estimate of Springel (2010, eq. 21): Gauss' theorem applied to the cell,
+    /// with the quantity at each face midpoint interpolated from the generators on either side.
+    /// Boundary faces take the value of the cell itself.
+    GreenGauss,
+    /// The gradient minimizing the weighted squared errors of the linear extrapolations to the
+    /// generators of the neighbouring cells, with the weight of each neighbour the length of the
+    /// shared face over the distance between the generators. Boundary faces are ignored.
+    LeastSquares,
+}
+
+
+impl VoronoiGrid2D {
+    /// Estimates the gradients in the cells of the actual generators of a quantity with the given
+    /// `values` in all cells, i.e. including the ghost cells, in the order of `cells()`. Empty
+    /// cells and cells whose neighbours do not constrain the gradient get a zero gradient.
+    pub fn gradients(&self, values: &[f64], estimator: GradientEstimator) -> Result<Vec<Vertex2D>, MiniSwiftError> {
+        self.check_values(values)?;
+        Ok((0..self.n_cells()).map(|cell_idx| match estimator {
+            GradientEstimator::GreenGauss => self.green_gauss_gradient(cell_idx, values),
+            GradientEstimator::LeastSquares => self.least_squares_gradient(cell_idx, values),
+        }).collect())
+    }
+
+    /// Limits the given gradients of the cells of the actual generators (Barth & Jespersen 1989),
+    /// such that the linear reconstructions around the centroids of the cells stay within the
+    /// range of `values` in the cell and its neighbours at all vertices of the cell. Unlike
+    /// `SlopeLimiter::cell_factor`, which only bounds them at the face midpoints used by the
+    /// force loop, this bounds them at the vertices, since the remap integrates the
+    /// reconstructions over arbitrary parts of the cells.
+    pub fn limit_gradients(&self, values: &[f64], gradients: &mut [Vertex2D]) -> Result<(), MiniSwiftError> {
+        self.check_values(values)?;
+        if gradients.len() != self.n_cells() {
+            return Err(MiniSwiftError::LengthMismatch { expected: self.n_cells(), found: gradients.len() });
+        }
+        for (cell_idx, gradient) in gradients.iter_mut().enumerate() {
+            let cell = &self.cells()[cell_idx];
+            let value = values[cell_idx];
+            let (mut min, mut max) = (value, value);
+            for &neighbour_idx in cell.neighbours().iter().filter(|&&n| n >= 0) {
+                min = min.min(values[neighbour_idx as usize]);
+                max = max.max(values[neighbour_idx as usize]);
+            }
+            let mut limiter: f64 = 1.;
+            for &vertex_idx in cell.vertices() {
+                let change = gradient.dot(&(self.vertices()[vertex_idx as usize] - cell.centroid()));
+                if change > 0. {
+                    limiter = limiter.min((max - value) / change);
+                } else if change < 0. {
+                    limiter = limiter.min((min - value) / change);
+                }
+            }
+            *gradient = limiter * *gradient;
+        }
+        Ok(())
+    }
+
+    /// We need a value for every cell, including the ghost cells
+    fn check_values(&self, values: &[f64]) -> Result<(), MiniSwiftError> {
+        if values.len() != self.cells().len() {
+            return Err(MiniSwiftError::LengthMismatch { expected: self.cells().len(), found: values.len() });
+        }
+        Ok(())
+    }
+
+    fn green_gauss_gradient(&self, cell_idx: usize, values: &[f64]) -> Vertex2D {
+        let cell = &self.cells()[cell_idx];
+        if cell.volume() <= 0. {
+            return Vertex2D { x: 0., y: 0. };
+        }
+        let generator = self.generators()[cell_idx];
+        let mut gradient = Vertex2D { x: 0., y: 0. };
+        for (&face_idx, &neighbour_idx) in cell.faces().iter().zip(cell.neighbours().iter()) {
+            let face = &self.faces()[face_idx as usize];
+            if neighbour_idx < 0 {
+                // The normal of a boundary face points out of the cell
+                gradient += face.area() * values[cell_idx] * face.normal();
+                continue;
+            }
+            let neighbour_idx = neighbour_idx as usize;
+            let neighbour = self.generators()[neighbour_idx];
+            // The separation r_ij between the generators and the offset c_ij of the face midpoint
+            // from the midpoint between the generators
+            let separation = generator - neighbour;
+            let distance = separation.norm();
+            let offset = face.midpoint() - 0.5 * (generator + neighbour);
+            gradient += face.area() / distance * ((values[neighbour_idx] - values[cell_idx]) * offset
+                - 0.5 * (values[cell_idx] + values[neighbour_idx]) * separation);
+        }
+        gradient / cell.volume()
+    }
+
+    fn least_squares_gradient(&self, cell_idx: usize, values: &[f64]) -> Vertex2D {
+        let cell = &self.cells()[cell_idx];
+        let generator = self.generators()[cell_idx];
+        // The normal equations: (sum_j w_j d_j d_j^T) gradient = sum_j w_j (values_j - values_i) d_j
+        let (mut a_xx, mut a_xy, mut a_yy) = (0., 0., 0.);
+        let mut rhs = Vertex2D { x: 0., y: 0. };
+        for (&face_idx, &neighbour_idx) in cell.faces().iter().zip(cell.neighbours().iter()) {
+            if neighbour_idx < 0 {
+                continue;
+            }
+            let neighbour_idx = neighbour_idx as usize;
+            let separation = self.generators()[neighbour_idx] - generator;
+            let weight = self.faces()[face_idx as usize].area() / separation.norm();
+            a_xx += weight * separation.x * separation.x;
+            a_xy += weight * separation.x * separation.y;
+            a_yy += weight * separation.y * separation.y;
+            rhs += weight * (values[neighbour_idx] - values[cell_idx]) * separation;
+        }
+        let determinant = a_xx * a_yy - a_xy * a_xy;
+        if determinant <= 1e-12 * a_xx * a_yy {
+            return Vertex2D { x: 0., y: 0. };
+        }
+        Vertex2D {
+            x: (a_yy * rhs.x - a_xy * rhs.y) / determinant,
+            y: (a_xx * rhs.y - a_xy * rhs.x) / determinant,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::delaunay2d::DelaunayTriangulation2D;
+use super::voronoi2d::{VoronoiGrid2D, VoronoiCell2D, VoronoiFace2D};
+use super::{Vertex2D, VoronoiGrid, TwoD};
+use crate::simulation_domain_2d::SimulationDomain2D;
+use crate::error::MiniSwiftError;
+use std::fs;
+
+
+/// The power diagram (weighted voronoi or Laguerre diagram) of weighted generators: the cell of
+/// generator i consists of the points x whose power distance |x - p_i|^2 - w_i to it is smaller
+/// than to any other generator. The faces are still perpendicular to the segments connecting
+/// neighbouring generators, but no longer halfway between them. A generator can lie outside of
+/// its cell, or even have an empty cell (see `is_hidden`).
+///
+/// This is the dual of a weighted `DelaunayTriangulation2D` (a regular triangulation). Cells,
+/// faces and vertices are laid out like those of a `VoronoiGrid2D`, which it reduces to when all
+/// weights are equal.
+#[derive(Default, Debug)]
+pub struct PowerDiagram2D {
+    grid: VoronoiGrid2D,
+    /// The weights of the generators, in the order of the cells
+    weights: Vec<f64>,
+}
+
+impl PowerDiagram2D {
+    pub fn from_regular_triangulation(triangulation: &DelaunayTriangulation2D) -> Result<PowerDiagram2D, MiniSwiftError> {
+        Ok(PowerDiagram2D {
+            grid: VoronoiGrid2D::from_delaunay_triangulation(triangulation)?,
+            weights: triangulation.vertices[3..].iter().map(|v| v.weight).collect(),
+        })
+    }
+
+    /// Constructs the power diagram of the given weighted points, see
+    /// `DelaunayTriangulation2D::from_weighted_points`. Without `apply_boundary_conditions`, the
+    /// cells are clipped to the simulation domain.
+    pub fn from_points(points_x: &[f64],
+                       points_y: &[f64],
+                       weights: &[f64],
+                       simulation_domain: SimulationDomain2D,
+                       apply_boundary_conditions: bool) -> Result<PowerDiagram2D, MiniSwiftError> {
+        let triangulation = DelaunayTriangulation2D::from_weighted_points(
+            points_x,
+            points_y,
+            weights,
+            simulation_domain,
+            apply_boundary_conditions
+        )?;
+        PowerDiagram2D::from_regular_triangulation(&triangulation)
+    }
+
+    /// The weights of the generators of all cells
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// The power distance of a point to the generator of the cell with the given index
+    pub fn power_distance(&self, cell_idx: usize, point: Vertex2D) -> f64 {
+        (point - self.grid.generators()[cell_idx]).norm().powi(2) - self.weights[cell_idx]
+    }
+
+    /// The index of the cell containing the point (x, y), i.e. of the generator with the smallest
+    /// power distance to it, see `VoronoiGrid2D::cell_containing`.
+    pub fn cell_containing(&self, x: f64, y: f64) -> Result<usize, MiniSwiftError> {
+        let point = self.grid.query_point(x, y)?;
+        self.grid.walk_to_cell_containing(point, &|cell_idx, point| self.power_distance(cell_idx, point))
+            .map(|(cell_idx, _)| cell_idx)
+    }
+
+    /// Whether the actual (non-ghost) cell with the given index is empty: its generator is
+    /// redundant (see `VertexInsertion::Redundant`) or, for diagrams clipped to the simulation
+    /// domain, its cell lies outside of the domain. Only actual cells can be hidden.
+    pub fn is_hidden(&self, cell_idx: usize) -> Result<bool, MiniSwiftError> {
+        if cell_idx >= self.n_cells() {
+            return Err(MiniSwiftError::InvalidCell(cell_idx));
+        }
+        Ok(self.grid.cells()[cell_idx].vertices().is_empty())
+    }
+
+    /// The underlying grid, e.g. for code that works with any `VoronoiGrid2D`
+    pub fn as_voronoi_grid(&self) -> &VoronoiGrid2D {
+        &self.grid
+    }
+
+    /// The number of cells of actual (non-ghost) generators
+    pub fn n_cells(&self) -> usize {
+        self.grid.n_cells()
+    }
+
+    pub fn cells(&self) -> &[VoronoiCell2D] {
+        self.grid.cells()
+    }
+
+    pub fn faces(&self) -> &[VoronoiFace2D] {
+        self.grid.faces()
+    }
+
+    pub fn vertices(&self) -> &[Vertex2D] {
+        self.grid.vertices()
+    }
+
+    pub fn generators(&self) -> &[Vertex2D] {
+        self.grid.generators()
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut result = self.grid.to_str();
+        result += "\n# Weights #\n";
+        for (i, weight) in self.weights.iter().enumerate() {
+            result += &format!("{}\t{}\n", i, weight);
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl VoronoiGrid<TwoD> for PowerDiagram2D {
+    fn from_tessellation(tessellation: &DelaunayTriangulation2D) -> Result<Self, MiniSwiftError> {
+        PowerDiagram2D::from_regular_triangulation(tessellation)
+    }
+
+    fn n_cells(&self) -> usize {
+        PowerDiagram2D::n_cells(self)
+    }
+
+    fn faces(&self) -> &[VoronoiFace2D] {
+        PowerDiagram2D::faces(self)
+    }
+
+    fn cell_volume(&self, cell_idx: usize) -> f64 {
+        self.grid.cell_volume(cell_idx)
+    }
+
+    fn cell_centroid(&self, cell_idx: usize) -> [f64; 2] {
+        self.grid.cell_centroid(cell_idx)
+    }
+
+    fn to_file(&self, filename: &str) {
+        PowerDiagram2D::to_file(self, filename)
+    }
+}
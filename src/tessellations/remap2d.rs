@@ -0,0 +1,188 @@
+use super::voronoi2d::VoronoiGrid2D;
+use super::{GradientEstimator, Triangle2D, Vertex2D};
+use crate::simulation_domain_2d::BoundaryCondition;
+use crate::error::MiniSwiftError;
+use std::collections::{HashSet, VecDeque};
+
+
+/// The intersection of a cell of a source grid with a cell of a target grid
+#[derive(Debug, Copy, Clone)]
+pub struct CellOverlap2D {
+    pub source_cell: usize,
+    pub target_cell: usize,
+    pub area: f64,
+    pub centroid: Vertex2D,
+    /// The shift of the periodic image of the source cell that overlaps the target cell, zero if
+    /// the source cell itself overlaps it
+    pub shift: Vertex2D,
+}
+
+
+/// How the conserved quantities are distributed over the source cells when remapping them
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Reconstruction {
+    /// Uniformly (first order)
+    Constant,
+    /// Linearly around the centroids of the cells, with least squares gradients limited to the
+    /// range of the neighbouring cells (second order)
+    Linear,
+}
+
+
+impl VoronoiGrid2D {
+    /// The (non-empty) intersections of the cells of the actual generators of this (unweighted)
+    /// grid with those of `target`, found by clipping the cells against each other. Both grids have
+    /// to cover the same region: the cells of this grid should be periodic along the periodic axes
+    /// of `target`.
+    pub fn overlaps(&self, target: &VoronoiGrid2D) -> Result<Vec<CellOverlap2D>, MiniSwiftError> {
+        self.check_covers(target)?;
+        let distance = |cell_idx: usize, point: Vertex2D| (point - self.generators()[cell_idx]).norm();
+        let mut overlaps = Vec::new();
+        for target_idx in 0..target.n_cells() {
+            let target_polygon = target.cell_polygon(target_idx, Vertex2D { x: 0., y: 0. });
+            if target_polygon.is_empty() {
+                continue;
+            }
+            // The source cells overlapping the target cell are connected, so we can search
+            // through the neighbours starting from the one containing the target centroid.
+            let start = self.walk_to_cell_containing(target.cells()[target_idx].centroid(), &distance)?;
+            let mut visited = HashSet::new();
+            let mut queue = VecDeque::from(vec![start]);
+            while let Some((source_idx, shift)) = queue.pop_front() {
+                if !visited.insert((source_idx, shift.x.to_bits(), shift.y.to_bits())) {
+                    continue;
+                }
+                let intersection = convex_intersection(&target_polygon, &self.cell_polygon(source_idx, shift));
+                let (area, centroid) = polygon_area_and_centroid(&intersection);
+                if area <= 0. {
+                    continue;
+                }
+                overlaps.push(CellOverlap2D { source_cell: source_idx, target_cell: target_idx, area, centroid, shift });
+                for &neighbour_idx in self.cells()[source_idx].neighbours().iter().filter(|&&n| n >= 0) {
+                    let (neighbour_idx, neighbour_shift) = self.resolve_periodic_ghost(neighbour_idx as usize);
+                    // Other ghosts are mirrored copies of generators, whose cells lie outside of
+                    // the domain
+                    if neighbour_idx < self.n_cells() {
+                        queue.push_back((neighbour_idx, shift + neighbour_shift));
+                    }
+                }
+            }
+        }
+        Ok(overlaps)
+    }
+
+    /// Remaps the given conserved quantities (e.g. mass, the components of the momentum and the
+    /// total energy), with one total per cell of the actual generators, onto the cells of
+    /// `target`. The totals over all cells are conserved up to round off, see `overlaps`. Empty
+    /// cells should have zero totals.
+    pub fn remap(&self, target: &VoronoiGrid2D, quantities: &[&[f64]],
+                 reconstruction: Reconstruction) -> Result<Vec<Vec<f64>>, MiniSwiftError> {
+        let overlaps = self.overlaps(target)?;
+        quantities.iter().map(|totals| self.remap_quantity(target, &overlaps, totals, reconstruction)).collect()
+    }
+
+    fn remap_quantity(&self, target: &VoronoiGrid2D, overlaps: &[CellOverlap2D], totals: &[f64],
+                      reconstruction: Reconstruction) -> Result<Vec<f64>, MiniSwiftError> {
+        if totals.len() != self.n_cells() {
+            return Err(MiniSwiftError::LengthMismatch { expected: self.n_cells(), found: totals.len() });
+        }
+        // The densities of the quantity in all cells, the ghosts taking the value of their original
+        let densities: Vec<f64> = (0..self.cells().len()).map(|cell_idx| {
+            let original_idx = match self.ghost_origin(cell_idx) {
+                Some(origin) => origin.particle_idx,
+                None => cell_idx,
+            };
+            let volume = self.cells()[original_idx].volume();
+            if volume > 0. { totals[original_idx] / volume } else { 0. }
+        }).collect();
+        let gradients = match reconstruction {
+            Reconstruction::Constant => vec![Vertex2D { x: 0., y: 0. }; self.n_cells()],
+            Reconstruction::Linear => {
+                let mut gradients = self.gradients(&densities, GradientEstimator::LeastSquares)?;
+                self.limit_gradients(&densities, &mut gradients)?;
+                gradients
+            }
+        };
+
+        // The integral of the linear reconstruction over an overlap is its value at the centroid
+        // of the overlap times the area. Since the reconstructions are centered on the centroids
+        // of the cells, the integrals over all overlaps of a cell add up to its total.
+        let mut remapped = vec![0.; target.n_cells()];
+        for overlap in overlaps {
+            let source = &self.cells()[overlap.source_cell];
+            let offset = overlap.centroid - overlap.shift - source.centroid();
+            remapped[overlap.target_cell] += overlap.area
+                * (densities[overlap.source_cell] + gradients[overlap.source_cell].dot(&offset));
+        }
+        Ok(remapped)
+    }
+
+    /// The vertices of the cell with the given index, shifted by `shift`
+    fn cell_polygon(&self, cell_idx: usize, shift: Vertex2D) -> Vec<Vertex2D> {
+        self.cells()[cell_idx].vertices().iter().map(|&v| self.vertices()[v as usize] + shift).collect()
+    }
+
+    /// Checks that the cells of this grid cover those of `target`: both grids need to cover
+    /// (only) their domains, possibly extended periodically, and the domains should be the same.
+    fn check_covers(&self, target: &VoronoiGrid2D) -> Result<(), MiniSwiftError> {
+        let covers_domain = |grid: &VoronoiGrid2D| grid.has_boundary_ghosts || grid.cells().len() == grid.n_cells();
+        let is_periodic = |grid: &VoronoiGrid2D, axis: usize| {
+            grid.has_boundary_ghosts && grid.domain.boundary_condition(axis, false) == BoundaryCondition::Periodic
+        };
+        if !covers_domain(self) || !covers_domain(target)
+            || self.domain.anchor() != target.domain.anchor() || self.domain.sides() != target.domain.sides()
+            || (0..2).any(|axis| is_periodic(target, axis) && !is_periodic(self, axis)) {
+            return Err(MiniSwiftError::IncompatibleGrids);
+        }
+        Ok(())
+    }
+}
+
+
+/// Clips the convex polygon `subject` against each edge of the convex polygon `clip`, both with
+/// their vertices in counterclockwise order.
+fn convex_intersection(subject: &[Vertex2D], clip: &[Vertex2D]) -> Vec<Vertex2D> {
+    let edge = |i: usize| clip[(i + 1) % clip.len()] - clip[i];
+    let perimeter: f64 = (0..clip.len()).map(|i| edge(i).norm()).sum();
+    let mut polygon = subject.to_vec();
+    for (i, &from) in clip.iter().enumerate() {
+        if polygon.is_empty() {
+            break;
+        }
+        let edge = edge(i);
+        // The direction of (almost) degenerate edges, e.g. between (almost) coinciding vertices
+        // of cells around mirrored generators, is dominated by round off.
+        if edge.norm() <= 1e-10 * perimeter {
+            continue;
+        }
+        // positive on the inside (left) of the edge
+        let side = |v: &Vertex2D| edge.x * (v.y - from.y) - edge.y * (v.x - from.x);
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (j, current) in polygon.iter().enumerate() {
+            let next = &polygon[(j + 1) % polygon.len()];
+            let (current_side, next_side) = (side(current), side(next));
+            if current_side >= 0. {
+                clipped.push(*current);
+            }
+            if (current_side >= 0.) != (next_side >= 0.) {
+                let t = current_side / (current_side - next_side);
+                clipped.push(*current + t * (*next - *current));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+
+/// The area and centroid of a polygon with its vertices in counterclockwise order
+fn polygon_area_and_centroid(polygon: &[Vertex2D]) -> (f64, Vertex2D) {
+    let mut area = 0.;
+    let mut moment = Vertex2D { x: 0., y: 0. };
+    for i in 1..polygon.len().saturating_sub(1) {
+        let triangle = Triangle2D::new(polygon[0], polygon[i], polygon[i + 1]);
+        area += triangle.area();
+        moment += triangle.area() * triangle.centroid();
+    }
+    if area > 0. { (area, moment / area) } else { (0., moment) }
+}
@@ -0,0 +1,143 @@
+use crate::simulation_domain_1d::SimulationDomain1D;
+use crate::simulation_domain_2d::SimulationDomain2D;
+use crate::simulation_domain_3d::SimulationDomain3D;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+
+/// The order in which points are inserted when constructing a Delaunay triangulation in bulk.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InsertionOrder {
+    /// Insert the points in the order in which they are given.
+    Input,
+    /// Insert the points in the order in which they are visited by a Hilbert curve.
+    Hilbert,
+    /// Biased randomized insertion order: the points are divided in rounds of doubling size,
+    /// chosen at random, and the points of each round are sorted along a Hilbert curve. The
+    /// random choice is made with the given seed, such that the insertion order is reproducible.
+    Brio { seed: u64 }
+}
+
+
+/// Number of bits used per coordinate to compute the Hilbert keys in 1D, 2D and 3D.
+const HILBERT_ORDER_1D: u32 = 62;
+const HILBERT_ORDER: u32 = 31;
+const HILBERT_ORDER_3D: u32 = 21;
+
+
+/// Returns the index of x along the (trivial) Hilbert curve covering the given domain in 1D.
+pub(super) fn hilbert_key_1d(x: f64, domain: &SimulationDomain1D) -> u64 {
+    to_grid(x, domain.anchor()[0], domain.sides()[0], HILBERT_ORDER_1D)
+}
+
+
+/// Returns the index of (x, y) along a Hilbert curve covering the given domain.
+pub(super) fn hilbert_key(x: f64, y: f64, domain: &SimulationDomain2D) -> u64 {
+    let mut gx = to_grid(x, domain.anchor()[0], domain.sides()[0], HILBERT_ORDER);
+    let mut gy = to_grid(y, domain.anchor()[1], domain.sides()[1], HILBERT_ORDER);
+
+    let mut key = 0u64;
+    let mut s = 1u64 << (HILBERT_ORDER - 1);
+    while s > 0 {
+        let rx = (gx & s > 0) as u64;
+        let ry = (gy & s > 0) as u64;
+        key += s * s * ((3 * rx) ^ ry);
+        // rotate the quadrant
+        if ry == 0 {
+            if rx == 1 {
+                gx = s - 1 - (gx & (s - 1));
+                gy = s - 1 - (gy & (s - 1));
+            }
+            std::mem::swap(&mut gx, &mut gy);
+        }
+        gx &= s - 1;
+        gy &= s - 1;
+        s >>= 1;
+    }
+    key
+}
+
+
+/// Returns the index of (x, y, z) along a Hilbert curve covering the given domain, using
+/// Skilling's algorithm ("Programming the Hilbert curve", 2004).
+pub(super) fn hilbert_key_3d(x: f64, y: f64, z: f64, domain: &SimulationDomain3D) -> u64 {
+    let mut g = [
+        to_grid(x, domain.anchor()[0], domain.sides()[0], HILBERT_ORDER_3D),
+        to_grid(y, domain.anchor()[1], domain.sides()[1], HILBERT_ORDER_3D),
+        to_grid(z, domain.anchor()[2], domain.sides()[2], HILBERT_ORDER_3D)
+    ];
+
+    // Transform the coordinates to the transposed Hilbert index
+    let mut q = 1u64 << (HILBERT_ORDER_3D - 1);
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if g[i] & q > 0 {
+                g[0] ^= p;
+            } else {
+                let t = (g[0] ^ g[i]) & p;
+                g[0] ^= t;
+                g[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    // Gray encode
+    g[1] ^= g[0];
+    g[2] ^= g[1];
+    let mut t = 0;
+    let mut q = 1u64 << (HILBERT_ORDER_3D - 1);
+    while q > 1 {
+        if g[2] & q > 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for value in g.iter_mut() {
+        *value ^= t;
+    }
+
+    // Interleave the bits of the transposed index
+    let mut key = 0u64;
+    for bit in (0..HILBERT_ORDER_3D).rev() {
+        for value in g.iter() {
+            key = (key << 1) | ((value >> bit) & 1);
+        }
+    }
+    key
+}
+
+/// Maps a coordinate to a cell of a grid of 2^order cells covering [anchor, anchor + side].
+fn to_grid(value: f64, anchor: f64, side: f64, order: u32) -> u64 {
+    let n_cells = (1u64 << order) as f64;
+    // Clamp, since points (e.g. of ghost particles) might fall slightly outside of the domain
+    ((value - anchor) / side * n_cells).clamp(0., n_cells - 1.) as u64
+}
+
+
+/// Returns the indices of the given points in the requested insertion order, given a function
+/// computing the Hilbert key of the point with a given index.
+pub(super) fn insertion_order(n_points: usize, hilbert_key: impl Fn(usize) -> u64, order: InsertionOrder) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..n_points).collect();
+    let keys = || (0..n_points).map(&hilbert_key).collect::<Vec<u64>>();
+    match order {
+        InsertionOrder::Input => {}
+        InsertionOrder::Hilbert => {
+            let keys = keys();
+            indices.sort_unstable_by_key(|&i| keys[i]);
+        }
+        InsertionOrder::Brio { seed } => {
+            let keys = keys();
+            indices.shuffle(&mut StdRng::seed_from_u64(seed));
+            // Each round contains half of the remaining points, the last round the first half
+            let mut end = indices.len();
+            while end > 0 {
+                let start = end / 2;
+                indices[start..end].sort_unstable_by_key(|&i| keys[i]);
+                end = start;
+            }
+        }
+    }
+    indices
+}
@@ -0,0 +1,133 @@
+use super::{Dimension, VertexInsertion};
+use crate::error::MiniSwiftError;
+
+
+/// The functionality shared by the Delaunay tessellations in all dimensions.
+pub trait Tessellation<D: Dimension>: Sized {
+    /// Creates an empty tessellation of the given domain, with room for `vertex_size` vertices and
+    /// `simplex_size` simplices.
+    fn new(domain: D::Domain, vertex_size: usize, simplex_size: usize) -> Self;
+
+    fn insert_vertex(&mut self, position: D::Position) -> Result<VertexInsertion, MiniSwiftError>;
+
+    /// Inserts a ghost vertex, a copy of a particle of a neighbouring cell (see `GhostOrigin`).
+    /// Only allowed after `finalize`.
+    fn insert_ghost_vertex(&mut self, position: D::Position, origin: GhostOrigin<D>) -> Result<VertexInsertion, MiniSwiftError>;
+
+    /// Marks all vertices inserted so far as the actual (non-ghost) vertices.
+    fn finalize(&mut self) -> Result<(), MiniSwiftError>;
+
+    /// The number of actual (non-ghost) vertices
+    fn n_vertices(&self) -> usize;
+
+    /// The origins of the ghost vertices in order of insertion, i.e. the i-th one is the origin of
+    /// the ghost following the `n_vertices` actual vertices.
+    fn ghost_origins(&self) -> &[GhostOrigin<D>];
+
+    /// The search radius of the actual vertex with the given index (in insertion order)
+    fn search_radius(&self, vertex_idx: usize) -> f64;
+
+    fn update_vertex_search_radii(&mut self, current_search_radius: f64, previous_n_vertices_larger_radius: usize);
+
+    fn to_file(&self, filename: &str);
+}
+
+
+/// Where a ghost vertex comes from: it is a copy of a particle of a (neighbouring) cell, or of an
+/// actual vertex of the tessellation itself for the ghosts added for the boundary conditions of
+/// the domain. The position of the ghost is obtained by mirroring the original position along the
+/// `mirrored` axes (i.e. negating those coordinates) and adding `shift`.
+#[derive(Debug, Copy, Clone)]
+pub struct GhostOrigin<D: Dimension> {
+    /// The id of the cell of the original particle (see `Cell::id`), `None` if the original is an
+    /// actual vertex of the tessellation itself
+    pub cell_id: Option<usize>,
+    /// The index of the original particle in its cell, or of the original vertex among the actual
+    /// vertices
+    pub particle_idx: usize,
+    pub shift: D::Position,
+    pub mirrored: [bool; 3]
+}
+
+impl<D: Dimension> GhostOrigin<D> {
+    /// The origin of a copy of a particle shifted by `shift`, e.g. a periodic image
+    pub fn shifted(cell_id: Option<usize>, particle_idx: usize, shift: D::Position) -> Self {
+        GhostOrigin{cell_id, particle_idx, shift, mirrored: [false; 3]}
+    }
+
+    /// The position of the ghost of a particle at the given position
+    pub fn ghost_position(&self, position: D::Position) -> D::Position {
+        let mut ghost_position = position;
+        for (i, (p, s)) in ghost_position.as_mut().iter_mut().zip(self.shift.as_ref()).enumerate() {
+            if self.mirrored[i] { *p = -*p; }
+            *p += s;
+        }
+        ghost_position
+    }
+}
+
+
+/// The functionality shared by the faces of the voronoi grids in all dimensions.
+pub trait VoronoiFace<D: Dimension> {
+    /// The area of the face (its length in 2D, 1 in 1D)
+    fn area(&self) -> f64;
+
+    fn midpoint(&self) -> D::Position;
+
+    /// The indices of the cells on either side of the face. Indices of at least `n_cells` refer
+    /// to the cells of ghost generators, in the order of `Tessellation::ghost_origins`, negative
+    /// indices to the boundary of the region covered by the grid.
+    fn adjacent_cells(&self) -> [i32; 2];
+
+    /// The index of the actual cell on one side of the face and what lies on its other side, given
+    /// the number of actual cells of the grid. `None` if neither side is an actual cell.
+    fn sides(&self, n_cells: usize) -> Option<(usize, FaceNeighbour)> {
+        let classify = |cell_idx: i32| {
+            if cell_idx < 0 {
+                FaceNeighbour::Boundary
+            } else if (cell_idx as usize) < n_cells {
+                FaceNeighbour::Local(cell_idx as usize)
+            } else {
+                FaceNeighbour::Ghost(cell_idx as usize - n_cells)
+            }
+        };
+        let [left, right] = self.adjacent_cells();
+        match (classify(left), classify(right)) {
+            (FaceNeighbour::Local(i), neighbour) => Some((i, neighbour)),
+            (neighbour, FaceNeighbour::Local(i)) => Some((i, neighbour)),
+            _ => None,
+        }
+    }
+}
+
+
+/// What lies on the other side of a face, seen from an actual cell adjacent to it (see
+/// `VoronoiFace::sides`).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FaceNeighbour {
+    /// The cell of the actual generator with this index
+    Local(usize),
+    /// The cell of the ghost generator with this index in `Tessellation::ghost_origins`
+    Ghost(usize),
+    /// The boundary of the region covered by the grid
+    Boundary,
+}
+
+
+/// The functionality shared by the voronoi grids in all dimensions.
+pub trait VoronoiGrid<D: Dimension>: Sized {
+    fn from_tessellation(tessellation: &D::Delaunay) -> Result<Self, MiniSwiftError>;
+
+    /// The number of cells of actual (non-ghost) generators
+    fn n_cells(&self) -> usize;
+
+    fn faces(&self) -> &[D::Face];
+
+    /// The volume of the cell with the given index
+    fn cell_volume(&self, cell_idx: usize) -> f64;
+
+    /// The centroid of the cell with the given index
+    fn cell_centroid(&self, cell_idx: usize) -> D::Position;
+
+    fn to_file(&self, filename: &str);
+}
@@ -0,0 +1,180 @@
+use super::delaunay1d::DelaunayTessellation1D;
+use super::Vertex1D;
+use super::{VoronoiGrid, VoronoiFace, OneD};
+use std::fs;
+use crate::simulation_domain_1d::SimulationDomain1D;
+use crate::error::MiniSwiftError;
+
+
+/// A face (point) between two cells in a voronoi grid in 1D. Its area is 1.
+#[derive(Debug, Default)]
+pub struct VoronoiFace1D {
+    area: f64,
+    midpoint: Vertex1D,
+    adjacent_cells: [i32; 2]
+}
+
+impl VoronoiFace<OneD> for VoronoiFace1D {
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn midpoint(&self) -> [f64; 1] {
+        [self.midpoint.x]
+    }
+
+    fn adjacent_cells(&self) -> [i32; 2] {
+        self.adjacent_cells
+    }
+}
+
+
+/// A cell (segment) from a voronoi grid in 1D
+#[derive(Debug)]
+struct VoronoiCell1D {
+    faces: Vec<i32>,
+    centroid: Vertex1D,
+    volume: f64
+}
+
+impl Default for VoronoiCell1D {
+    fn default() -> VoronoiCell1D {
+        VoronoiCell1D{
+            faces: Vec::new(),
+            centroid: Vertex1D{x: 0.},
+            volume: 0.
+        }
+    }
+}
+
+/// The faces of a voronoi grid in 1D lie halfway between consecutive generators and are sorted
+/// from left to right. The vertices of the grid coincide with its faces.
+#[derive(Default, Debug)]
+pub struct VoronoiGrid1D {
+    vertices: Vec<Vertex1D>,
+    faces: Vec<VoronoiFace1D>,
+    cells: Vec<VoronoiCell1D>,
+    is_periodic: bool,
+    domain: SimulationDomain1D,
+    n_cells: usize
+}
+
+impl VoronoiGrid1D {
+    pub fn with_capacity(n_cells: usize, n_vertices: usize) -> VoronoiGrid1D {
+        VoronoiGrid1D {
+            vertices: Vec::with_capacity(n_vertices),
+            faces: Vec::with_capacity(n_vertices),
+            cells: Vec::with_capacity(n_cells),
+            ..VoronoiGrid1D::default()
+        }
+    }
+
+    /// Constructs the voronoi cells of the non-ghost vertices of the given tessellation, in the
+    /// same order. The faces towards ghost vertices are included; their second adjacent cell is
+    /// the index of the ghost vertex minus 2, i.e. larger than the index of any cell.
+    pub fn from_delaunay_tessellation(tessellation: &DelaunayTessellation1D) -> Result<VoronoiGrid1D, MiniSwiftError> {
+        let mut grid = VoronoiGrid1D::with_capacity(tessellation.n_vertices, tessellation.n_vertices + 1);
+        grid.n_cells = tessellation.n_vertices;
+        grid.is_periodic = tessellation.is_periodic;
+        grid.domain = tessellation.domain;
+        grid.cells.resize_with(grid.n_cells, VoronoiCell1D::default);
+
+        // Walk along the generators from left to right, the right face of the previous cell is
+        // the left face of the current one.
+        let is_cell = |vertex_idx: usize| vertex_idx >= 2 && vertex_idx < tessellation.n_vertices + 2;
+        let sorted_vertex_indices = tessellation.sorted_vertex_indices();
+        let mut left_face_idx = -1;
+        for segment in sorted_vertex_indices.windows(2) {
+            let (left_idx, right_idx) = (segment[0], segment[1]);
+            if !is_cell(left_idx) && !is_cell(right_idx) {
+                left_face_idx = -1;
+                continue;
+            }
+            let face_idx = grid.faces.len() as i32;
+            let midpoint = (tessellation.vertices[left_idx].as_vertex1d()
+                + tessellation.vertices[right_idx].as_vertex1d()) / 2.;
+            grid.vertices.push(midpoint);
+            grid.faces.push(VoronoiFace1D{
+                area: 1.,
+                midpoint,
+                adjacent_cells: [left_idx as i32 - 2, right_idx as i32 - 2]
+            });
+            if is_cell(left_idx) {
+                let cell = &mut grid.cells[left_idx - 2];
+                cell.faces = vec![left_face_idx, face_idx];
+                let left_face = &grid.faces[left_face_idx as usize];
+                cell.volume = midpoint.x - left_face.midpoint.x;
+                cell.centroid = (left_face.midpoint + midpoint) / 2.;
+                if cell.volume <= 0. {
+                    return Err(MiniSwiftError::DegenerateCell(left_idx - 2));
+                }
+            }
+            left_face_idx = face_idx;
+        }
+        Ok(grid)
+    }
+
+    /// Constructs the voronoi grid of the given points. Without periodic boundary conditions, the
+    /// ends of the domain are reflective, i.e. the outer faces lie on the boundary of the domain,
+    /// and the points must lie strictly inside the domain.
+    pub fn from_points(points_x: &[f64],
+                       simulation_domain: SimulationDomain1D,
+                       make_periodic: bool) -> Result<VoronoiGrid1D, MiniSwiftError> {
+        let delaunay = DelaunayTessellation1D::from_points(
+            points_x,
+            simulation_domain,
+            make_periodic
+        )?;
+        VoronoiGrid1D::from_delaunay_tessellation(&delaunay)
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut result = String::from("# Vertices #\n");
+        for (i, v) in self.vertices.iter().enumerate() {
+            result += &format!("{}\t({})\n", i, v.x);
+        }
+
+        result += "\n# Cells #\n";
+        for (i, cell) in self.cells.iter().enumerate() {
+            let faces: Vec<String> = cell.faces.iter().map(|f| f.to_string()).collect();
+            result += &format!("{}\t({})\n", i, faces.join(", "));
+        }
+
+        result += "\n# Centroids #\n";
+        for (i, cell) in self.cells.iter().enumerate() {
+            result += &format!("{}\t({})\n", i, cell.centroid.x);
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl VoronoiGrid<OneD> for VoronoiGrid1D {
+    fn from_tessellation(tessellation: &DelaunayTessellation1D) -> Result<Self, MiniSwiftError> {
+        VoronoiGrid1D::from_delaunay_tessellation(tessellation)
+    }
+
+    fn n_cells(&self) -> usize {
+        self.n_cells
+    }
+
+    fn faces(&self) -> &[VoronoiFace1D] {
+        &self.faces
+    }
+
+    fn cell_volume(&self, cell_idx: usize) -> f64 {
+        self.cells[cell_idx].volume
+    }
+
+    fn cell_centroid(&self, cell_idx: usize) -> [f64; 1] {
+        [self.cells[cell_idx].centroid.x]
+    }
+
+    fn to_file(&self, filename: &str) {
+        VoronoiGrid1D::to_file(self, filename)
+    }
+}
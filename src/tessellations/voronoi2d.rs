@@ -1,24 +1,66 @@
 use super::delaunay2d::DelaunayTriangulation2D;
 use super::{Vertex2D, Triangle2D};
+use super::{VoronoiGrid, VoronoiFace, TwoD, GhostOrigin};
+use std::collections::HashMap;
 use std::fs;
 use std::iter::FromIterator;
 use crate::simulation_domain_2d::SimulationDomain2D;
+use crate::error::MiniSwiftError;
 
 
-/// A face (line) between two cells in a voronoi grid
+/// A face (line) between two cells in a voronoi grid, or between a cell and the boundary of the
+/// domain. The first (left) adjacent cell is always the cell of an actual generator, the second
+/// (right) one can be a ghost.
 #[derive(Debug, Default)]
-struct VoronoiFace2D {
+pub struct VoronoiFace2D {
     area: f64,
     midpoint: Vertex2D,
-    adjacent_cells: [i32; 2]
+    normal: Vertex2D,
+    adjacent_cells: [i32; 2],
+}
+
+impl VoronoiFace2D {
+    /// The length of the face
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+
+    pub fn midpoint(&self) -> Vertex2D {
+        self.midpoint
+    }
+
+    /// The unit normal of the face, pointing from the left to the right adjacent cell (or out of
+    /// the domain for boundary faces).
+    pub fn normal(&self) -> Vertex2D {
+        self.normal
+    }
+
+    /// The indices of the left and right adjacent cell, see `is_boundary` and `is_ghost` for the
+    /// right one.
+    pub fn adjacent_cells(&self) -> [i32; 2] {
+        self.adjacent_cells
+    }
+
+    /// Whether this face lies on the boundary of the domain (its right adjacent cell is -1)
+    pub fn is_boundary(&self) -> bool {
+        self.adjacent_cells[1] < 0
+    }
+
+    /// Whether the right adjacent cell is the cell of a ghost generator, e.g. a periodic or
+    /// mirrored image of a generator or a copy of a generator of a neighbouring cell, given the
+    /// number of cells of actual generators of the grid.
+    pub fn is_ghost(&self, n_cells: usize) -> bool {
+        self.adjacent_cells[1] >= n_cells as i32
+    }
 }
 
 
 /// A cell from a voronoi grid in 2D
 #[derive(Debug)]
-struct VoronoiCell2D {
+pub struct VoronoiCell2D {
     vertices: Vec<i32>,
     faces: Vec<i32>,
+    neighbours: Vec<i32>,
     centroid: Vertex2D,
     volume: f64
 }
@@ -28,19 +70,76 @@ impl Default for VoronoiCell2D {
         VoronoiCell2D{
             vertices: Vec::new(),
             faces: Vec::new(),
+            neighbours: Vec::new(),
             centroid: Vertex2D{x: 0., y: 0.0},
             volume: 0.
         }
     }
 }
 
+impl VoronoiCell2D {
+    /// The indices of the vertices of the cell, in counterclockwise order
+    pub fn vertices(&self) -> &[i32] {
+        &self.vertices
+    }
+
+    /// The indices of the faces of the cell, the i-th face runs from the i-th to the next vertex.
+    pub fn faces(&self) -> &[i32] {
+        &self.faces
+    }
+
+    /// The indices of the cells on the other side of each face, -1 for boundary faces
+    pub fn neighbours(&self) -> &[i32] {
+        &self.neighbours
+    }
+
+    pub fn centroid(&self) -> Vertex2D {
+        self.centroid
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+}
+
+
+/// The diagnostics of one iteration of a Lloyd relaxation, measured before the generators are
+/// moved to the centroids of their cells.
+#[derive(Debug, Copy, Clone)]
+pub struct LloydIteration {
+    pub iteration: usize,
+    /// The energy sum_i ∫_{V_i} density(x) |x - z_i|^2 dx of the cells V_i and their generators
+    /// z_i, which decreases monotonically towards a centroidal voronoi tessellation
+    pub energy: f64,
+    /// The largest distance between a generator and the centroid of its cell
+    pub max_displacement: f64,
+    /// The root mean square distance between the generators and the centroids of their cells
+    pub rms_displacement: f64,
+}
+
+/// The outcome of a Lloyd relaxation
+#[derive(Debug)]
+pub struct LloydRelaxation {
+    pub grid: VoronoiGrid2D,
+    /// The diagnostics of each iteration, in order
+    pub iterations: Vec<LloydIteration>,
+    /// Whether the largest displacement dropped below the threshold
+    pub converged: bool,
+}
+
+/// The voronoi grid of the vertices of a `DelaunayTriangulation2D`. Cell `i` is the cell of vertex
+/// `i + 3` of the triangulation: the cells of the actual generators come first, followed by those
+/// of the ghost generators. Only the cells of actual generators have faces.
 #[derive(Default, Debug)]
 pub struct VoronoiGrid2D {
     vertices: Vec<Vertex2D>,
+    generators: Vec<Vertex2D>,
     faces: Vec<VoronoiFace2D>,
     cells: Vec<VoronoiCell2D>,
-    is_periodic: bool,
-    domain: SimulationDomain2D,
+    /// The origins of the ghost generators, in the order of their cells
+    ghost_origins: Vec<GhostOrigin<TwoD>>,
+    pub(super) has_boundary_ghosts: bool,
+    pub(super) domain: SimulationDomain2D,
     n_cells: usize
 }
 
@@ -54,44 +153,58 @@ impl VoronoiGrid2D {
         }
     }
 
-    pub fn from_delaunay_triangulation(triangulation: &DelaunayTriangulation2D) -> VoronoiGrid2D {
+    pub fn from_delaunay_triangulation(triangulation: &DelaunayTriangulation2D) -> Result<VoronoiGrid2D, MiniSwiftError> {
         let mut grid = VoronoiGrid2D::with_capacity(triangulation.vertices.len() - 3,
                                                     triangulation.triangles.len() - 3);
         grid.n_cells = triangulation.n_vertices;
-        grid.is_periodic = triangulation.is_periodic;
-        // assert!(grid.is_periodic, "Non periodic grids are not yet supported!");
+        grid.has_boundary_ghosts = triangulation.has_boundary_ghosts;
         grid.domain = triangulation.domain;
-        // for each triangle of triangulation add the circumcenter to vertices (skip dummy triangles)
+        grid.generators.extend(triangulation.vertices[3..].iter().map(|v| Vertex2D{x: v.x, y: v.y}));
+        grid.ghost_origins.extend_from_slice(&triangulation.ghost_origins);
+        // for each triangle of triangulation add the circumcenter (orthocenter for weighted
+        // triangulations) to vertices (skip dummy triangles)
         for triangle in triangulation.triangles[3..].iter() {
-            grid.vertices.push(triangle.circumcenter(triangulation));
+            grid.vertices.push(triangle.orthocenter(triangulation));
         }
 
         // For each vertex of triangulation (generator): loop around the Delaunay triangles containing
         // that vertex. The circumcenters of those triangles are the voronoi vertices of the cell
         // generated by the current generator. Update the area and centroid of the current cell as
         // you go.
+        // Without periodic boundary conditions or ghost vertices, the cells near the boundary
+        // extend towards the vertices of the encapsulating triangle and are clipped to the domain
+        // instead.
+        let clip_to_domain = !triangulation.has_ghost_vertices();
+        let mut face_indices = HashMap::<(i32, i32), i32>::new();
         for i in 3..triangulation.vertices.len() {
             if triangulation.is_connected_to_non_dummy_non_ghost_vertex(i){
-                grid.add_cell_from_delaunay_generator(i, triangulation);
+                if clip_to_domain {
+                    grid.add_clipped_cell_from_delaunay_generator(i, triangulation, &mut face_indices)?;
+                } else {
+                    grid.add_cell_from_delaunay_generator(i, triangulation)?;
+                }
+            } else {
+                // keep the cells aligned with the generators
+                grid.cells.push(VoronoiCell2D::default());
             }
         }
-        grid
+        Ok(grid)
     }
 
-    pub fn from_points(points_x: &Vec<f64>,
-                       points_y: &Vec<f64>,
+    pub fn from_points(points_x: &[f64],
+                       points_y: &[f64],
                        simulation_domain: SimulationDomain2D,
-                       make_periodic: bool) -> VoronoiGrid2D {
+                       apply_boundary_conditions: bool) -> Result<VoronoiGrid2D, MiniSwiftError> {
         let delaunay = DelaunayTriangulation2D::from_points(
             points_x,
             points_y,
             simulation_domain,
-            make_periodic
-        );
+            apply_boundary_conditions
+        )?;
         VoronoiGrid2D::from_delaunay_triangulation(&delaunay)
     }
 
-    fn add_cell_from_delaunay_generator(&mut self, generator_idx: usize, triangulation: &DelaunayTriangulation2D) {
+    fn add_cell_from_delaunay_generator(&mut self, generator_idx: usize, triangulation: &DelaunayTriangulation2D) -> Result<(), MiniSwiftError> {
         let generator = &triangulation.vertices[generator_idx];
         let current_voronoi_cell_idx = generator_idx as i32 - 3;
         let mut idx_in_current_triangle = generator.index_in_triangle;
@@ -112,6 +225,7 @@ impl VoronoiGrid2D {
                 // create faces between cells
                 let neighbouring_generator_idx_in_d = current_triangle.vertices[((idx_in_current_triangle + 2) % 3) as usize];
                 let neighbouring_voronoi_cell_idx = neighbouring_generator_idx_in_d - 3;
+                current_cell.neighbours.push(neighbouring_voronoi_cell_idx);
                 current_cell.faces.push(
                     self.get_or_create_face(
                         current_voronoi_vertex_idx,
@@ -127,7 +241,13 @@ impl VoronoiGrid2D {
                     self.vertices[next_voronoi_vertex_idx as usize]
                 );
                 let current_wedge_area = current_wedge.area();
-                assert!(current_wedge_area >= 0.);
+                // Cocircular generators produce coinciding voronoi vertices, whose wedges can have
+                // a tiny negative area due to roundoff. In a power diagram, a generator can lie
+                // outside of its cell, but the signed wedge areas still add up to its area.
+                let tolerance = 1e-10 * (self.vertices[current_voronoi_vertex_idx as usize] - generator_as_vertex2d).norm().powi(2);
+                if current_wedge_area < -tolerance && !triangulation.is_weighted {
+                    return Err(MiniSwiftError::DegenerateCell(current_voronoi_cell_idx as usize));
+                }
                 current_cell.volume += current_wedge_area;
                 current_cell.centroid += current_wedge_area * current_wedge.centroid();
             }
@@ -138,13 +258,108 @@ impl VoronoiGrid2D {
         // Divide area weighted sum of centroids by total area of cell -> centroid of cell
         current_cell.centroid /= current_cell.volume;
         self.cells.push(current_cell);
+        Ok(())
+    }
+
+    /// Adds the cell of the given generator, clipped to the domain. The faces between cells are
+    /// shared through `face_indices`, indexed by the (sorted) indices of their adjacent cells, the
+    /// parts of the boundary of the domain delimiting the cell become boundary faces.
+    fn add_clipped_cell_from_delaunay_generator(&mut self, generator_idx: usize, triangulation: &DelaunayTriangulation2D,
+                                                face_indices: &mut HashMap<(i32, i32), i32>) -> Result<(), MiniSwiftError> {
+        let generator = &triangulation.vertices[generator_idx];
+        let current_voronoi_cell_idx = generator_idx as i32 - 3;
+        let generator_as_vertex2d = Vertex2D{x: generator.x, y: generator.y };
+
+        // Collect the unclipped cell: each voronoi vertex together with the neighbouring cell on
+        // the other side of the face starting at that vertex
+        let mut polygon = Vec::<ClipVertex>::new();
+        let mut idx_in_current_triangle = generator.index_in_triangle;
+        for current_triangle_idx_in_d in triangulation.get_triangle_idx_around_vertex(generator_idx) {
+            let current_triangle = &triangulation.triangles[current_triangle_idx_in_d];
+            let current_voronoi_vertex_idx = current_triangle_idx_in_d as i32 - 3;
+            polygon.push(ClipVertex{
+                vertex_idx: Some(current_voronoi_vertex_idx),
+                position: self.vertices[current_voronoi_vertex_idx as usize],
+                neighbour: Some(current_triangle.vertices[((idx_in_current_triangle + 2) % 3) as usize] - 3)
+            });
+            let next_triangle_idx_in_current_triangle = ((idx_in_current_triangle + 1) % 3) as usize;
+            let current_triangle_idx_in_next_triangle = current_triangle.index_in_neighbours[next_triangle_idx_in_current_triangle];
+            idx_in_current_triangle = (current_triangle_idx_in_next_triangle + 1) % 3;
+        }
+        let polygon = clip_polygon(polygon, &self.domain);
+        // The cell of a weighted generator can lie entirely outside of the domain
+        if polygon.is_empty() && triangulation.is_weighted {
+            self.cells.push(VoronoiCell2D::default());
+            return Ok(());
+        }
+
+        let mut current_cell = VoronoiCell2D::default();
+        for (i, clip_vertex) in polygon.iter().enumerate() {
+            let next_clip_vertex = &polygon[(i + 1) % polygon.len()];
+            let vertex_idx = match clip_vertex.vertex_idx {
+                Some(vertex_idx) => vertex_idx,
+                None => {
+                    self.vertices.push(clip_vertex.position);
+                    self.vertices.len() as i32 - 1
+                }
+            };
+            current_cell.vertices.push(vertex_idx);
+            current_cell.neighbours.push(clip_vertex.neighbour.unwrap_or(-1));
+
+            let edge = next_clip_vertex.position - clip_vertex.position;
+            let area = edge.norm();
+            let midpoint = (next_clip_vertex.position + clip_vertex.position) / 2.;
+            let face_idx = match clip_vertex.neighbour {
+                Some(neighbouring_voronoi_cell_idx) => {
+                    let key = (current_voronoi_cell_idx.min(neighbouring_voronoi_cell_idx),
+                               current_voronoi_cell_idx.max(neighbouring_voronoi_cell_idx));
+                    match face_indices.get(&key) {
+                        Some(&face_idx) => face_idx,
+                        None => {
+                            self.faces.push(VoronoiFace2D{
+                                area,
+                                midpoint,
+                                normal: self.generator_normal(current_voronoi_cell_idx, neighbouring_voronoi_cell_idx),
+                                adjacent_cells: [current_voronoi_cell_idx, neighbouring_voronoi_cell_idx],
+                            });
+                            face_indices.insert(key, self.faces.len() as i32 - 1);
+                            self.faces.len() as i32 - 1
+                        }
+                    }
+                }
+                None => {
+                    // the cell lies to the left of its counterclockwise edges
+                    self.faces.push(VoronoiFace2D{
+                        area,
+                        midpoint,
+                        normal: Vertex2D{x: edge.y, y: -edge.x} / area,
+                        adjacent_cells: [current_voronoi_cell_idx, -1],
+                    });
+                    self.faces.len() as i32 - 1
+                }
+            };
+            current_cell.faces.push(face_idx);
+
+            // Update area and area weighted centroid sum of cell
+            let current_wedge = Triangle2D::new(generator_as_vertex2d, clip_vertex.position, next_clip_vertex.position);
+            let current_wedge_area = current_wedge.area();
+            current_cell.volume += current_wedge_area;
+            current_cell.centroid += current_wedge_area * current_wedge.centroid();
+        }
+        if current_cell.volume <= 0. {
+            return Err(MiniSwiftError::DegenerateCell(current_voronoi_cell_idx as usize));
+        }
+        // Divide area weighted sum of centroids by total area of cell -> centroid of cell
+        current_cell.centroid /= current_cell.volume;
+        self.cells.push(current_cell);
+        Ok(())
     }
 
     fn get_or_create_face(&mut self, vertex_from_idx: i32, vertex_to_idx: i32, cell_in_idx: i32, cell_out_idx: i32) -> i32 {
         assert_ne!(cell_in_idx, cell_out_idx, "Trying to add face between a cell and itself!");
         assert_ne!(vertex_from_idx, vertex_to_idx, "Trying to add a face from a vertex to itself!");
         let face_idx: i32;
-        if cell_out_idx < cell_in_idx && cell_out_idx > 2 {
+        if cell_out_idx < cell_in_idx && cell_out_idx >= 0 {
             let cell_out = &self.cells[cell_out_idx as usize];
             let face_idx_in_cell_out = cell_out.vertices.iter().position(|&v_idx| v_idx == vertex_to_idx).unwrap();
             face_idx = cell_out.faces[face_idx_in_cell_out];
@@ -157,54 +372,286 @@ impl VoronoiGrid2D {
                 VoronoiFace2D{
                     area: (self.vertices[vertex_to_idx as usize] - self.vertices[vertex_from_idx as usize]).norm(),
                     midpoint: (self.vertices[vertex_to_idx as usize] + self.vertices[vertex_from_idx as usize]) / 2.,
-                    adjacent_cells: [cell_in_idx, cell_out_idx]
+                    normal: self.generator_normal(cell_in_idx, cell_out_idx),
+                    adjacent_cells: [cell_in_idx, cell_out_idx],
                 }
             );
         }
         face_idx
     }
 
-    pub fn lloyd_relax(&self, move_threshold: f64, max_iter: usize) -> VoronoiGrid2D {
-        let mut generators_x = Vec::from_iter(self.cells[..self.n_cells].iter().map(|c| c.centroid.x));
-        let mut generators_y = Vec::from_iter(self.cells[..self.n_cells].iter().map(|c| c.centroid.y));
+    /// The unit vector pointing from the generator of one cell to that of another one
+    fn generator_normal(&self, cell_from_idx: i32, cell_to_idx: i32) -> Vertex2D {
+        let separation = self.generators[cell_to_idx as usize] - self.generators[cell_from_idx as usize];
+        separation / separation.norm()
+    }
+
+    /// The number of cells of actual (non-ghost) generators
+    pub fn n_cells(&self) -> usize {
+        self.n_cells
+    }
+
+    /// All cells, the cells of the actual generators followed by those of the ghost generators
+    pub fn cells(&self) -> &[VoronoiCell2D] {
+        &self.cells
+    }
+
+    pub fn faces(&self) -> &[VoronoiFace2D] {
+        &self.faces
+    }
+
+    pub fn vertices(&self) -> &[Vertex2D] {
+        &self.vertices
+    }
+
+    /// The generators of the cells, e.g. to express the position of a face midpoint relative to
+    /// the generators of its adjacent cells.
+    pub fn generators(&self) -> &[Vertex2D] {
+        &self.generators
+    }
+
+    /// The origin of the ghost generator of the cell with the given index, if it is a ghost cell
+    pub fn ghost_origin(&self, cell_idx: usize) -> Option<&GhostOrigin<TwoD>> {
+        cell_idx.checked_sub(self.n_cells).and_then(|k| self.ghost_origins.get(k))
+    }
+
+    /// The index of the cell containing the point (x, y), i.e. of the generator nearest to it. See
+    /// `nearest_generators` for the treatment of points outside of the domain and of ghost cells.
+    pub fn cell_containing(&self, x: f64, y: f64) -> Result<usize, MiniSwiftError> {
+        let distance = |cell_idx: usize, point: Vertex2D| (point - self.generators[cell_idx]).norm();
+        let point = self.query_point(x, y)?;
+        self.walk_to_cell_containing(point, &distance).map(|(cell_idx, _)| cell_idx)
+    }
+
+    /// The indices of the cells of the `k` generators nearest to the point (x, y), nearest first.
+    /// Points outside of the domain are mapped onto their periodic image along periodic axes and
+    /// rejected otherwise, unless the ghosts of the grid stem from neighbouring cells. Periodic
+    /// ghosts are replaced by the cells of their originals, so every cell occurs at most once.
+    /// Other ghost cells can be returned, but their neighbours are not searched.
+    pub fn nearest_generators(&self, x: f64, y: f64, k: usize) -> Result<Vec<usize>, MiniSwiftError> {
+        use ordered_float::OrderedFloat;
+        use std::cmp::Reverse;
+        use std::collections::{BinaryHeap, HashSet};
+
+        let point = self.query_point(x, y)?;
+        let distance = |cell_idx: usize, point: Vertex2D| (point - self.generators[cell_idx]).norm();
+        let (nearest_idx, nearest_offset) = self.walk_to_cell_containing(point, &distance)?;
+
+        // Best first search through the neighbours: the next nearest generator is always a
+        // neighbour of one of the generators found so far.
+        let mut nearest = Vec::with_capacity(k);
+        let mut found = HashSet::new();
+        let mut candidates = vec![(nearest_idx, nearest_offset)];
+        let mut queue = BinaryHeap::new();
+        queue.push((Reverse(OrderedFloat(distance(nearest_idx, point - nearest_offset))), 0));
+        while let Some((_, candidate_idx)) = queue.pop() {
+            if nearest.len() == k {
+                break;
+            }
+            let (cell_idx, offset) = candidates[candidate_idx];
+            if !found.insert(cell_idx) {
+                continue;
+            }
+            nearest.push(cell_idx);
+            for &neighbour_idx in self.cells[cell_idx].neighbours.iter().filter(|&&n| n >= 0) {
+                let (neighbour_idx, shift) = self.resolve_periodic_ghost(neighbour_idx as usize);
+                if found.contains(&neighbour_idx) {
+                    continue;
+                }
+                let neighbour_offset = offset + shift;
+                queue.push((Reverse(OrderedFloat(distance(neighbour_idx, point - neighbour_offset))), candidates.len()));
+                candidates.push((neighbour_idx, neighbour_offset));
+            }
+        }
+        Ok(nearest)
+    }
+
+    /// Checks that the point (x, y) lies inside the region covered by the grid, after mapping it
+    /// onto its periodic image inside of the domain for grids with boundary ghosts.
+    pub(super) fn query_point(&self, x: f64, y: f64) -> Result<Vertex2D, MiniSwiftError> {
+        let outside_error = MiniSwiftError::PointOutsideDomain { position: vec![x, y] };
+        if !x.is_finite() || !y.is_finite() {
+            return Err(outside_error);
+        }
+        let is_clipped = self.ghost_origins.is_empty();
+        if !is_clipped && !self.has_boundary_ghosts {
+            // The ghosts stem from neighbouring cells and cover an unknown region
+            return Ok(Vertex2D { x, y });
+        }
+        let [x, y] = if self.has_boundary_ghosts { self.domain.wrap_periodic([x, y]) } else { [x, y] };
+        let (anchor, sides) = (self.domain.anchor(), self.domain.sides());
+        if x < anchor[0] || x > anchor[0] + sides[0] || y < anchor[1] || y > anchor[1] + sides[1] {
+            return Err(outside_error);
+        }
+        Ok(Vertex2D { x, y })
+    }
+
+    /// The actual cell of which the cell with the given index is a periodic copy, together with
+    /// the shift from that cell to its copy. Other cells are returned unchanged.
+    pub(super) fn resolve_periodic_ghost(&self, cell_idx: usize) -> (usize, Vertex2D) {
+        match self.ghost_origin(cell_idx) {
+            Some(origin) if origin.cell_id.is_none() && !origin.mirrored.iter().any(|&mirrored| mirrored) => {
+                (origin.particle_idx, Vertex2D { x: origin.shift[0], y: origin.shift[1] })
+            }
+            _ => (cell_idx, Vertex2D { x: 0., y: 0. }),
+        }
+    }
+
+    /// Walks through the cells towards the point, moving to the neighbouring cell whose generator
+    /// is nearest to it according to `distance`, which takes the index of a cell and the point
+    /// relative to the (unshifted) generator of that cell. Also works for the power distance, since
+    /// the difference of the distances to two generators is linear in the point. Returns the cell
+    /// containing the point together with the shift to the periodic copy of it containing the
+    /// point.
+    pub(super) fn walk_to_cell_containing(&self, point: Vertex2D, distance: &dyn Fn(usize, Vertex2D) -> f64)
+                                          -> Result<(usize, Vertex2D), MiniSwiftError> {
+        let mut current_idx = (0..self.n_cells).find(|&i| !self.cells[i].neighbours.is_empty())
+            .ok_or(MiniSwiftError::PointOutsideDomain { position: vec![point.x, point.y] })?;
+        let mut offset = Vertex2D { x: 0., y: 0. };
+        let mut current_distance = distance(current_idx, point);
+        // The distance strictly decreases, so the walk never visits a cell twice
+        loop {
+            let nearest_neighbour = self.cells[current_idx].neighbours.iter()
+                .filter(|&&n| n >= 0)
+                .map(|&n| self.resolve_periodic_ghost(n as usize))
+                // The mirrored ghosts lie on the other side of the sides of the domain, so the
+                // walk towards a point inside of the domain never needs to cross them.
+                .filter(|&(neighbour_idx, _)| !self.has_boundary_ghosts || neighbour_idx < self.n_cells)
+                .map(|(neighbour_idx, shift)| {
+                    (neighbour_idx, offset + shift, distance(neighbour_idx, point - (offset + shift)))
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+            match nearest_neighbour {
+                Some((neighbour_idx, neighbour_offset, neighbour_distance)) if neighbour_distance < current_distance => {
+                    current_idx = neighbour_idx;
+                    offset = neighbour_offset;
+                    current_distance = neighbour_distance;
+                }
+                _ => return Ok((current_idx, offset)),
+            }
+        }
+    }
+
+    /// Moves the generators to the centroids of their cells, until the largest displacement of a
+    /// generator drops below `move_threshold`, for at most `max_iter` iterations. Stops early when
+    /// the energy of the grid (see `LloydIteration`) no longer decreases.
+    pub fn lloyd_relax(&self, move_threshold: f64, max_iter: usize) -> Result<LloydRelaxation, MiniSwiftError> {
+        self.relax(None, move_threshold, max_iter)
+    }
+
+    /// Like `lloyd_relax`, but moves the generators to the centers of mass of their cells for the
+    /// given target density, converging towards a centroidal voronoi tessellation with smaller
+    /// cells where the density is higher. Asymptotically, the cell areas scale as `density^(-1/2)`,
+    /// so a generator number density proportional to n(x, y) requires `density` = n^2.
+    pub fn lloyd_relax_with_density(&self, density: impl Fn(f64, f64) -> f64, move_threshold: f64,
+                                    max_iter: usize) -> Result<LloydRelaxation, MiniSwiftError> {
+        self.relax(Some(&density), move_threshold, max_iter)
+    }
+
+    fn relax(&self, density: Option<&dyn Fn(f64, f64) -> f64>, move_threshold: f64,
+             max_iter: usize) -> Result<LloydRelaxation, MiniSwiftError> {
+        let uniform = |_: f64, _: f64| 1.;
+        let integration_density = density.unwrap_or(&uniform);
+        // The new positions of the generators and the energy of the grid. The geometric centroids
+        // are exact for a uniform density.
+        let targets = |grid: &VoronoiGrid2D| {
+            let mut energy = 0.;
+            let targets: Vec<Vertex2D> = (0..grid.n_cells).map(|i| {
+                let (_, center_of_mass, cell_energy) = grid.cell_integrals(i, integration_density);
+                energy += cell_energy;
+                if density.is_some() { center_of_mass } else { grid.cells[i].centroid }
+            }).collect();
+            (targets, energy)
+        };
+
+        // The centroids of cells straddling a periodic side of the domain can lie outside of it
+        let wrap = |generators: Vec<Vertex2D>| Vec::from_iter(generators.into_iter().map(|g| {
+            let [x, y] = self.domain.wrap_periodic([g.x, g.y]);
+            Vertex2D{x, y}
+        }));
+
+        let mut generators = wrap(targets(self).0);
+        let mut generators_x = Vec::from_iter(generators.iter().map(|g| g.x));
+        let mut generators_y = Vec::from_iter(generators.iter().map(|g| g.y));
 
         let mut d = DelaunayTriangulation2D::from_points(
             &generators_x,
             &generators_y,
             self.domain,
-            self.is_periodic
-        );
-        let mut v: VoronoiGrid2D = VoronoiGrid2D::from_delaunay_triangulation(&d);
-
-        let mut displacement_threshold_satisfied = false;
-        let mut previous_max_displacement = f64::INFINITY;
-        let mut iter: usize = 0;
-        while iter < max_iter && !displacement_threshold_satisfied {
-            let mut max_displacement = 0.;
-            for (i, voronoi_cell) in v.cells[..v.n_cells].iter().enumerate() {
-                let centroid = voronoi_cell.centroid;
-                let generator = Vertex2D{x: generators_x[i], y: generators_y[i]};
-                let displacement = (generator - centroid).norm();
-                if displacement > max_displacement {
-                    max_displacement = displacement
-                }
+            self.has_boundary_ghosts
+        )?;
+        let mut v: VoronoiGrid2D = VoronoiGrid2D::from_delaunay_triangulation(&d)?;
+
+        let mut iterations = Vec::<LloydIteration>::new();
+        let mut converged = false;
+        let mut previous_energy = f64::INFINITY;
+        while iterations.len() < max_iter && !converged {
+            let (new_generators, energy) = targets(&v);
+            let mut max_displacement: f64 = 0.;
+            let mut sum_displacement_2 = 0.;
+            for (generator, new_generator) in generators.iter().zip(new_generators.iter()) {
+                let displacement = (*new_generator - *generator).norm();
+                max_displacement = max_displacement.max(displacement);
+                sum_displacement_2 += displacement * displacement;
             }
-            if max_displacement > previous_max_displacement { break; }
-            previous_max_displacement = max_displacement;
-            displacement_threshold_satisfied = max_displacement < move_threshold;
-            iter += 1;
-            generators_x = Vec::from_iter(v.cells[..v.n_cells].iter().map(|c| c.centroid.x));
-            generators_y = Vec::from_iter(v.cells[..v.n_cells].iter().map(|c| c.centroid.y));
-            d = DelaunayTriangulation2D::from_points(
-                &generators_x,
-                &generators_y,
-                self.domain,
-                self.is_periodic
+            iterations.push(LloydIteration {
+                iteration: iterations.len() + 1,
+                energy,
+                max_displacement,
+                rms_displacement: (sum_displacement_2 / v.n_cells as f64).sqrt()
+            });
+            if energy >= previous_energy { break; }
+            previous_energy = energy;
+            converged = max_displacement < move_threshold;
+            generators = wrap(new_generators);
+            generators_x = Vec::from_iter(generators.iter().map(|g| g.x));
+            generators_y = Vec::from_iter(generators.iter().map(|g| g.y));
+            if self.has_boundary_ghosts {
+                d = DelaunayTriangulation2D::from_points(
+                    &generators_x,
+                    &generators_y,
+                    self.domain,
+                    self.has_boundary_ghosts
+                )?;
+            } else {
+                // Without ghost vertices, the generators can simply be moved to their new positions
+                d.update_positions(&generators_x, &generators_y)?;
+            }
+            v = VoronoiGrid2D::from_delaunay_triangulation(&d)?;
+        }
+        Ok(LloydRelaxation{grid: v, iterations, converged})
+    }
+
+    /// The mass, center of mass and energy (second moment around the generator) of the cell of an
+    /// actual generator for the given density. The integrals are evaluated with the quadrature
+    /// rule of `Triangle2D::quadrature_points` on the wedges between the generator and the faces
+    /// of the cell, with the density evaluated at the periodic image inside of the domain of points
+    /// outside of it.
+    fn cell_integrals(&self, cell_idx: usize, density: &dyn Fn(f64, f64) -> f64) -> (f64, Vertex2D, f64) {
+        let generator = self.generators[cell_idx];
+        let cell = &self.cells[cell_idx];
+        let mut mass = 0.;
+        let mut moment = Vertex2D{x: 0., y: 0.};
+        let mut energy = 0.;
+        for (i, &vertex_idx) in cell.vertices.iter().enumerate() {
+            let next_vertex_idx = cell.vertices[(i + 1) % cell.vertices.len()];
+            let wedge = Triangle2D::new(
+                generator,
+                self.vertices[vertex_idx as usize],
+                self.vertices[next_vertex_idx as usize]
             );
-            v = VoronoiGrid2D::from_delaunay_triangulation(&d);
-            println!("Relaxation iter: {}, maximum displacement was: {}", iter, max_displacement);
+            for (point, weight) in wedge.quadrature_points() {
+                // cells can extend beyond periodic sides of the domain
+                let [x, y] = self.domain.wrap_periodic([point.x, point.y]);
+                let point_mass = weight * density(x, y);
+                mass += point_mass;
+                moment += point_mass * point;
+                energy += point_mass * (point - generator).norm().powi(2);
+            }
         }
-        v
+        // leave generators of cells without mass in place
+        let center_of_mass = if mass > 0. { moment / mass } else { generator };
+        (mass, center_of_mass, energy)
     }
 
     pub fn to_str(&self) -> String {
@@ -235,4 +682,117 @@ impl VoronoiGrid2D {
     pub fn to_file(&self, filename: &str) {
         fs::write(filename, self.to_str()).expect("Unable to write to file!");
     }
-}
\ No newline at end of file
+}
+
+
+
+/// A vertex of a voronoi cell that is being clipped, together with the face starting at it.
+#[derive(Debug, Copy, Clone)]
+struct ClipVertex {
+    /// The index of the voronoi vertex, `None` for vertices created by the clipping
+    vertex_idx: Option<i32>,
+    position: Vertex2D,
+    /// The cell on the other side of the face starting at this vertex, `None` for boundary faces
+    neighbour: Option<i32>
+}
+
+/// Clips a convex polygon to the domain using the Sutherland-Hodgman algorithm, i.e. against each
+/// side of the domain in turn. The parts of the sides of the domain inside the polygon become
+/// boundary faces.
+fn clip_polygon(mut polygon: Vec<ClipVertex>, domain: &SimulationDomain2D) -> Vec<ClipVertex> {
+    let (anchor, sides) = (domain.anchor(), domain.sides());
+    let coordinate = |v: &Vertex2D, axis: usize| if axis == 0 { v.x } else { v.y };
+    // (axis, position of the side, +1 if the inside lies above the side, -1 otherwise)
+    let domain_sides = [(0, anchor[0], 1.), (0, anchor[0] + sides[0], -1.),
+                        (1, anchor[1], 1.), (1, anchor[1] + sides[1], -1.)];
+    for (axis, side, sign) in domain_sides {
+        let inside = |v: &Vertex2D| sign * (coordinate(v, axis) - side) >= 0.;
+        let intersection = |from: &Vertex2D, to: &Vertex2D| {
+            let t = (side - coordinate(from, axis)) / (coordinate(to, axis) - coordinate(from, axis));
+            let mut intersection = *from + t * (*to - *from);
+            // Make sure the intersection lies exactly on the side of the domain
+            if axis == 0 { intersection.x = side; } else { intersection.y = side; }
+            intersection
+        };
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            match (inside(&current.position), inside(&next.position)) {
+                (true, true) => clipped.push(*current),
+                (true, false) => {
+                    // Leaving the domain: continue along the side of the domain
+                    clipped.push(*current);
+                    clipped.push(ClipVertex{vertex_idx: None, position: intersection(&current.position, &next.position), neighbour: None});
+                }
+                (false, true) => {
+                    // Entering the domain: the rest of the face lies inside
+                    clipped.push(ClipVertex{vertex_idx: None, position: intersection(&current.position, &next.position), neighbour: current.neighbour});
+                }
+                (false, false) => {}
+            }
+        }
+        polygon = clipped;
+    }
+
+    // Remove faces of length zero, e.g. created by clipping a polygon through one of its vertices
+    polygon.dedup_by(|next, current| {
+        if next.position.x != current.position.x || next.position.y != current.position.y {
+            return false;
+        }
+        current.neighbour = next.neighbour;
+        current.vertex_idx = current.vertex_idx.or(next.vertex_idx);
+        true
+    });
+    while polygon.len() > 1 {
+        let (first, last) = (polygon[0], polygon[polygon.len() - 1]);
+        if first.position.x != last.position.x || first.position.y != last.position.y {
+            break;
+        }
+        polygon[0].vertex_idx = first.vertex_idx.or(last.vertex_idx);
+        polygon.pop();
+    }
+    polygon
+}
+
+
+impl VoronoiFace<TwoD> for VoronoiFace2D {
+    fn area(&self) -> f64 {
+        VoronoiFace2D::area(self)
+    }
+
+    fn midpoint(&self) -> [f64; 2] {
+        [self.midpoint.x, self.midpoint.y]
+    }
+
+    fn adjacent_cells(&self) -> [i32; 2] {
+        VoronoiFace2D::adjacent_cells(self)
+    }
+}
+
+
+impl VoronoiGrid<TwoD> for VoronoiGrid2D {
+    fn from_tessellation(tessellation: &DelaunayTriangulation2D) -> Result<Self, MiniSwiftError> {
+        VoronoiGrid2D::from_delaunay_triangulation(tessellation)
+    }
+
+    fn n_cells(&self) -> usize {
+        VoronoiGrid2D::n_cells(self)
+    }
+
+    fn faces(&self) -> &[VoronoiFace2D] {
+        VoronoiGrid2D::faces(self)
+    }
+
+    fn cell_volume(&self, cell_idx: usize) -> f64 {
+        self.cells[cell_idx].volume
+    }
+
+    fn cell_centroid(&self, cell_idx: usize) -> [f64; 2] {
+        let centroid = self.cells[cell_idx].centroid;
+        [centroid.x, centroid.y]
+    }
+
+    fn to_file(&self, filename: &str) {
+        VoronoiGrid2D::to_file(self, filename)
+    }
+}
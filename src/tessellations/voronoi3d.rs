@@ -0,0 +1,277 @@
+use super::delaunay3d::DelaunayTetrahedralization3D;
+use super::Vertex3D;
+use std::collections::HashMap;
+use super::{VoronoiGrid, VoronoiFace, ThreeD};
+use std::fs;
+use crate::simulation_domain_3d::SimulationDomain3D;
+use crate::error::MiniSwiftError;
+
+
+/// A face (polygon) between two cells in a voronoi grid
+#[derive(Debug, Default)]
+pub struct VoronoiFace3D {
+    vertices: Vec<i32>,
+    area: f64,
+    midpoint: Vertex3D,
+    adjacent_cells: [i32; 2]
+}
+
+impl VoronoiFace<ThreeD> for VoronoiFace3D {
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn midpoint(&self) -> [f64; 3] {
+        [self.midpoint.x, self.midpoint.y, self.midpoint.z]
+    }
+
+    fn adjacent_cells(&self) -> [i32; 2] {
+        self.adjacent_cells
+    }
+}
+
+
+/// A cell from a voronoi grid in 3D
+#[derive(Debug)]
+struct VoronoiCell3D {
+    faces: Vec<i32>,
+    centroid: Vertex3D,
+    volume: f64
+}
+
+impl Default for VoronoiCell3D {
+    fn default() -> VoronoiCell3D {
+        VoronoiCell3D{
+            faces: Vec::new(),
+            centroid: Vertex3D{x: 0., y: 0., z: 0.},
+            volume: 0.
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct VoronoiGrid3D {
+    vertices: Vec<Vertex3D>,
+    faces: Vec<VoronoiFace3D>,
+    cells: Vec<VoronoiCell3D>,
+    is_periodic: bool,
+    domain: SimulationDomain3D,
+    n_cells: usize
+}
+
+impl VoronoiGrid3D {
+    pub fn with_capacity(n_cells: usize, n_vertices: usize) -> VoronoiGrid3D {
+        VoronoiGrid3D {
+            vertices: Vec::with_capacity(n_vertices),
+            faces: Vec::with_capacity(2 * n_vertices),
+            cells: Vec::with_capacity(n_cells),
+            ..VoronoiGrid3D::default()
+        }
+    }
+
+    /// Constructs the voronoi cells of the non-ghost vertices of the given tetrahedralization.
+    /// The faces towards ghost vertices are included; their second adjacent cell is the index of
+    /// the ghost vertex minus 4, i.e. larger than the index of any cell.
+    pub fn from_delaunay_tetrahedralization(tetrahedralization: &DelaunayTetrahedralization3D) -> Result<VoronoiGrid3D, MiniSwiftError> {
+        let mut grid = VoronoiGrid3D::with_capacity(tetrahedralization.n_vertices,
+                                                    tetrahedralization.tetrahedra.len() - 4);
+        grid.n_cells = tetrahedralization.n_vertices;
+        grid.is_periodic = tetrahedralization.is_periodic;
+        grid.domain = tetrahedralization.domain;
+        // for each tetrahedron add the circumcenter to vertices (skip dummy tetrahedra)
+        for tetrahedron in tetrahedralization.tetrahedra[4..].iter() {
+            grid.vertices.push(tetrahedron.circumcenter(tetrahedralization));
+        }
+
+        // Each Delaunay edge between generators corresponds to a voronoi face, whose vertices are
+        // the circumcenters of the tetrahedra around that edge.
+        let mut face_indices = HashMap::<(usize, usize), i32>::new();
+        for i in 4..tetrahedralization.n_vertices + 4 {
+            grid.add_cell_from_delaunay_generator(i, tetrahedralization, &mut face_indices)?;
+        }
+        Ok(grid)
+    }
+
+    pub fn from_points(points_x: &[f64],
+                       points_y: &[f64],
+                       points_z: &[f64],
+                       simulation_domain: SimulationDomain3D,
+                       make_periodic: bool) -> Result<VoronoiGrid3D, MiniSwiftError> {
+        let delaunay = DelaunayTetrahedralization3D::from_points(
+            points_x,
+            points_y,
+            points_z,
+            simulation_domain,
+            make_periodic
+        )?;
+        VoronoiGrid3D::from_delaunay_tetrahedralization(&delaunay)
+    }
+
+    fn add_cell_from_delaunay_generator(&mut self,
+                                        generator_idx: usize,
+                                        tetrahedralization: &DelaunayTetrahedralization3D,
+                                        face_indices: &mut HashMap<(usize, usize), i32>) -> Result<(), MiniSwiftError> {
+        let generator = tetrahedralization.vertices[generator_idx].as_vertex3d();
+        let current_voronoi_cell_idx = generator_idx as i32 - 4;
+        let mut current_cell = VoronoiCell3D::default();
+
+        // Find the neighbouring generators, together with a tetrahedron containing the edge to
+        // each of them.
+        let mut neighbours = Vec::<(usize, usize)>::new();
+        for tetrahedron_idx in tetrahedralization.get_tetrahedron_idx_around_vertex(generator_idx) {
+            for &v in tetrahedralization.tetrahedra[tetrahedron_idx].vertices.iter() {
+                let v = v as usize;
+                if v != generator_idx && !neighbours.iter().any(|&(w, _)| w == v) {
+                    neighbours.push((v, tetrahedron_idx));
+                }
+            }
+        }
+
+        for (neighbour_idx, tetrahedron_idx) in neighbours {
+            let face_idx = match face_indices.get(&(generator_idx.min(neighbour_idx), generator_idx.max(neighbour_idx))) {
+                Some(&face_idx) => face_idx,
+                None => {
+                    let face_idx = self.faces.len() as i32;
+                    let face = self.create_face(generator_idx, neighbour_idx, tetrahedron_idx, tetrahedralization);
+                    self.faces.push(face);
+                    face_indices.insert((generator_idx.min(neighbour_idx), generator_idx.max(neighbour_idx)), face_idx);
+                    face_idx
+                }
+            };
+            let face = &self.faces[face_idx as usize];
+            // The face lies halfway between both generators, so the height of the pyramid formed
+            // by the generator and the face is half their distance. The centroid of a pyramid lies
+            // at 3/4 of the way from its apex to the centroid of its base.
+            let distance = (tetrahedralization.vertices[neighbour_idx].as_vertex3d() - generator).norm();
+            let pyramid_volume = face.area * distance / 6.;
+            current_cell.volume += pyramid_volume;
+            current_cell.centroid += pyramid_volume * (generator + 0.75 * (face.midpoint - generator));
+            current_cell.faces.push(face_idx);
+        }
+        if current_cell.volume <= 0. {
+            return Err(MiniSwiftError::DegenerateCell(current_voronoi_cell_idx as usize));
+        }
+        // Divide volume weighted sum of centroids by total volume of cell -> centroid of cell
+        current_cell.centroid /= current_cell.volume;
+        self.cells.push(current_cell);
+        Ok(())
+    }
+
+    /// Creates the face between the cells of two neighbouring generators, by rotating around the
+    /// Delaunay edge between them, starting from the given tetrahedron containing that edge.
+    fn create_face(&self, generator_idx: usize, neighbour_idx: usize, start_tetrahedron_idx: usize,
+                   tetrahedralization: &DelaunayTetrahedralization3D) -> VoronoiFace3D {
+        let (i, j) = (generator_idx as i32, neighbour_idx as i32);
+        let mut vertices = Vec::<i32>::new();
+        let mut tetrahedron_idx = start_tetrahedron_idx as i32;
+        // The vertex of the current tetrahedron opposite of the face through which we leave it
+        let mut exit_vertex = *tetrahedralization.tetrahedra[start_tetrahedron_idx].vertices.iter()
+            .find(|&&v| v != i && v != j).unwrap();
+        loop {
+            vertices.push(tetrahedron_idx - 4);
+            let tetrahedron = &tetrahedralization.tetrahedra[tetrahedron_idx as usize];
+            let exit_idx = tetrahedron.vertices.iter().position(|&v| v == exit_vertex).unwrap();
+            let kept_vertex = *tetrahedron.vertices.iter()
+                .find(|&&v| v != i && v != j && v != exit_vertex).unwrap();
+            tetrahedron_idx = tetrahedron.neighbours[exit_idx];
+            if tetrahedron_idx == start_tetrahedron_idx as i32 {
+                break;
+            }
+            exit_vertex = kept_vertex;
+        }
+
+        // Decompose the polygon in triangles around its first vertex. The face is perpendicular
+        // to the Delaunay edge, so the area of each triangle is the projection of its vector area
+        // on the direction of that edge.
+        let direction = tetrahedralization.vertices[neighbour_idx].as_vertex3d()
+            - tetrahedralization.vertices[generator_idx].as_vertex3d();
+        let normal = direction / direction.norm();
+        let first = self.vertices[vertices[0] as usize];
+        let mut area = 0.;
+        let mut midpoint = Vertex3D{x: 0., y: 0., z: 0.};
+        for k in 1..vertices.len() - 1 {
+            let b = self.vertices[vertices[k] as usize];
+            let c = self.vertices[vertices[k + 1] as usize];
+            let triangle_area = 0.5 * (b - first).cross(&(c - first)).dot(&normal);
+            area += triangle_area;
+            midpoint += triangle_area * (first + b + c) / 3.;
+        }
+        // Correct for the orientation of the rotation around the edge
+        if area < 0. {
+            area = -area;
+            midpoint = -1. * midpoint;
+            vertices.reverse();
+        }
+        if area > 0. {
+            midpoint /= area;
+        } else {
+            // Cospherical generators give faces of zero area, whose vertices all coincide
+            midpoint = vertices.iter().fold(Vertex3D{x: 0., y: 0., z: 0.}, |sum, &v| sum + self.vertices[v as usize]);
+            midpoint /= vertices.len() as f64;
+        }
+
+        VoronoiFace3D{
+            vertices,
+            area,
+            midpoint,
+            adjacent_cells: [i - 4, j - 4]
+        }
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut result = String::from("# Vertices #\n");
+        for (i, v) in self.vertices.iter().enumerate() {
+            result += &format!("{}\t({}, {}, {})\n", i, v.x, v.y, v.z);
+        }
+
+        result += "\n# Faces #\n";
+        for (i, face) in self.faces.iter().enumerate() {
+            let vertices: Vec<String> = face.vertices.iter().map(|v| v.to_string()).collect();
+            result += &format!("{}\t({})\n", i, vertices.join(", "));
+        }
+
+        result += "\n# Cells #\n";
+        for (i, cell) in self.cells.iter().enumerate() {
+            let faces: Vec<String> = cell.faces.iter().map(|f| f.to_string()).collect();
+            result += &format!("{}\t({})\n", i, faces.join(", "));
+        }
+
+        result += "\n# Centroids #\n";
+        for (i, cell) in self.cells.iter().enumerate() {
+            result += &format!("{}\t({}, {}, {})\n", i, cell.centroid.x, cell.centroid.y, cell.centroid.z);
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl VoronoiGrid<ThreeD> for VoronoiGrid3D {
+    fn from_tessellation(tessellation: &DelaunayTetrahedralization3D) -> Result<Self, MiniSwiftError> {
+        VoronoiGrid3D::from_delaunay_tetrahedralization(tessellation)
+    }
+
+    fn n_cells(&self) -> usize {
+        self.n_cells
+    }
+
+    fn faces(&self) -> &[VoronoiFace3D] {
+        &self.faces
+    }
+
+    fn cell_volume(&self, cell_idx: usize) -> f64 {
+        self.cells[cell_idx].volume
+    }
+
+    fn cell_centroid(&self, cell_idx: usize) -> [f64; 3] {
+        let centroid = self.cells[cell_idx].centroid;
+        [centroid.x, centroid.y, centroid.z]
+    }
+
+    fn to_file(&self, filename: &str) {
+        VoronoiGrid3D::to_file(self, filename)
+    }
+}
@@ -0,0 +1,213 @@
+use super::voronoi2d::VoronoiGrid2D;
+use super::Vertex2D;
+use std::f64::consts::PI;
+use std::fs;
+
+
+/// The number of bins of the histograms of the continuous quality metrics
+const N_QUALITY_BINS: usize = 20;
+
+
+/// The geometric quality metrics of a single voronoi cell
+#[derive(Debug, Clone)]
+pub struct CellQuality2D {
+    /// The square root of the ratio of the principal second moments of area of the cell: 1 for
+    /// regular polygons, large for elongated cells
+    pub aspect_ratio: f64,
+    /// The distance between the generator and the centroid of the cell, relative to the radius
+    /// sqrt(volume / pi) of the cell
+    pub generator_offset: f64,
+    pub min_face_length: f64,
+    pub n_faces: usize,
+    /// The angle under which each face of the cell is seen from its generator, in the order of
+    /// `VoronoiCell2D::faces`
+    pub face_angles: Vec<f64>,
+}
+
+impl CellQuality2D {
+    /// The largest angle under which a face of the cell is seen from its generator
+    pub fn max_face_angle(&self) -> f64 {
+        self.face_angles.iter().cloned().fold(0., f64::max)
+    }
+}
+
+
+/// A histogram with bins of equal width between `lower` and `upper`
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    pub lower: f64,
+    pub upper: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn from_values(values: &[f64], lower: f64, upper: f64, n_bins: usize) -> Histogram {
+        let mut counts = vec![0; n_bins];
+        let width = (upper - lower) / n_bins as f64;
+        for &value in values {
+            // the upper edge belongs to the last bin, also when all values are equal
+            let bin = if width > 0. { ((value - lower) / width) as usize } else { 0 };
+            counts[bin.min(n_bins - 1)] += 1;
+        }
+        Histogram { lower, upper, counts }
+    }
+
+    /// The lower edge of the bin with the given index
+    pub fn bin_edge(&self, bin_idx: usize) -> f64 {
+        self.lower + bin_idx as f64 * (self.upper - self.lower) / self.counts.len() as f64
+    }
+}
+
+
+/// The minimum, maximum and mean of a quality metric over all cells (or faces), together with its
+/// distribution
+#[derive(Debug, Clone)]
+pub struct QualitySummary {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub histogram: Histogram,
+}
+
+impl QualitySummary {
+    fn from_values(values: &[f64]) -> QualitySummary {
+        if values.is_empty() {
+            return QualitySummary {
+                min: 0.,
+                max: 0.,
+                mean: 0.,
+                histogram: Histogram::from_values(values, 0., 0., N_QUALITY_BINS),
+            };
+        }
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        QualitySummary {
+            min,
+            max,
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            histogram: Histogram::from_values(values, min, max, N_QUALITY_BINS),
+        }
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        let mut result = format!("# {} #\nmin: {}\tmax: {}\tmean: {}\n", name, self.min, self.max, self.mean);
+        for (i, count) in self.histogram.counts.iter().enumerate() {
+            result += &format!("[{}, {})\t{}\n", self.histogram.bin_edge(i), self.histogram.bin_edge(i + 1), count);
+        }
+        result
+    }
+}
+
+
+/// The geometric quality of the actual cells of a voronoi grid, e.g. to decide which cells need to
+/// be regularised.
+#[derive(Debug, Clone)]
+pub struct VoronoiQuality2D {
+    /// The metrics of the cells of the actual generators, in the order of the cells. Empty cells
+    /// (hidden generators of weighted grids) are `None`.
+    pub cells: Vec<Option<CellQuality2D>>,
+    pub aspect_ratio: QualitySummary,
+    pub generator_offset: QualitySummary,
+    pub min_face_length: QualitySummary,
+    /// The distribution of the angles of all faces of all cells
+    pub face_angle: QualitySummary,
+    /// The number of cells with the given number of faces
+    pub n_faces: Vec<usize>,
+}
+
+impl VoronoiQuality2D {
+    pub fn to_str(&self) -> String {
+        let mut result = String::from("# Cells #\n");
+        result += "# idx\taspect_ratio\tgenerator_offset\tmin_face_length\tn_faces\tmax_face_angle\n";
+        for (i, cell) in self.cells.iter().enumerate() {
+            if let Some(cell) = cell {
+                result += &format!("{}\t{}\t{}\t{}\t{}\t{}\n", i, cell.aspect_ratio, cell.generator_offset,
+                                   cell.min_face_length, cell.n_faces, cell.max_face_angle());
+            }
+        }
+
+        result += "\n";
+        result += &self.aspect_ratio.to_str("Aspect ratio");
+        result += "\n";
+        result += &self.generator_offset.to_str("Generator offset");
+        result += "\n";
+        result += &self.min_face_length.to_str("Minimum face length");
+        result += "\n";
+        result += &self.face_angle.to_str("Face angle");
+
+        result += "\n# Number of faces #\n";
+        for (n, count) in self.n_faces.iter().enumerate() {
+            if *count > 0 {
+                result += &format!("{}\t{}\n", n, count);
+            }
+        }
+        result
+    }
+
+    pub fn to_file(&self, filename: &str) {
+        fs::write(filename, self.to_str()).expect("Unable to write to file!");
+    }
+}
+
+
+impl VoronoiGrid2D {
+    /// The geometric quality metrics of the cells of the actual generators, see `CellQuality2D`
+    pub fn quality(&self) -> VoronoiQuality2D {
+        let cells: Vec<Option<CellQuality2D>> = (0..self.n_cells()).map(|i| self.cell_quality(i)).collect();
+        let metric = |f: fn(&CellQuality2D) -> f64| -> Vec<f64> { cells.iter().flatten().map(f).collect() };
+        let face_angles: Vec<f64> = cells.iter().flatten().flat_map(|c| c.face_angles.iter().cloned()).collect();
+        let mut n_faces = Vec::new();
+        for cell in cells.iter().flatten() {
+            if n_faces.len() <= cell.n_faces {
+                n_faces.resize(cell.n_faces + 1, 0);
+            }
+            n_faces[cell.n_faces] += 1;
+        }
+
+        VoronoiQuality2D {
+            aspect_ratio: QualitySummary::from_values(&metric(|c| c.aspect_ratio)),
+            generator_offset: QualitySummary::from_values(&metric(|c| c.generator_offset)),
+            min_face_length: QualitySummary::from_values(&metric(|c| c.min_face_length)),
+            face_angle: QualitySummary::from_values(&face_angles),
+            n_faces,
+            cells,
+        }
+    }
+
+    fn cell_quality(&self, cell_idx: usize) -> Option<CellQuality2D> {
+        let cell = &self.cells()[cell_idx];
+        if cell.vertices().is_empty() {
+            return None;
+        }
+        let centroid = cell.centroid();
+        let generator = self.generators()[cell_idx];
+        let vertices: Vec<Vertex2D> = cell.vertices().iter().map(|&v| self.vertices()[v as usize]).collect();
+
+        // The second moments of area of the polygon around its centroid
+        let (mut i_xx, mut i_yy, mut i_xy) = (0., 0., 0.);
+        for (i, current) in vertices.iter().enumerate() {
+            let (a, b) = (*current - centroid, vertices[(i + 1) % vertices.len()] - centroid);
+            let cross = a.x * b.y - a.y * b.x;
+            i_xx += cross * (a.y * a.y + a.y * b.y + b.y * b.y) / 12.;
+            i_yy += cross * (a.x * a.x + a.x * b.x + b.x * b.x) / 12.;
+            i_xy += cross * (a.x * b.y + 2. * a.x * a.y + 2. * b.x * b.y + b.x * a.y) / 24.;
+        }
+        let mean = 0.5 * (i_xx + i_yy);
+        let deviation = (0.25 * (i_xx - i_yy) * (i_xx - i_yy) + i_xy * i_xy).sqrt();
+
+        let face_angles = vertices.iter().enumerate().map(|(i, current)| {
+            let (a, b) = (*current - generator, vertices[(i + 1) % vertices.len()] - generator);
+            f64::atan2((a.x * b.y - a.y * b.x).abs(), a.x * b.x + a.y * b.y)
+        }).collect();
+
+        Some(CellQuality2D {
+            aspect_ratio: ((mean + deviation) / (mean - deviation)).sqrt(),
+            generator_offset: (generator - centroid).norm() / (cell.volume() / PI).sqrt(),
+            min_face_length: cell.faces().iter()
+                .map(|&f| self.faces()[f as usize].area())
+                .fold(f64::INFINITY, f64::min),
+            n_faces: cell.faces().len(),
+            face_angles,
+        })
+    }
+}